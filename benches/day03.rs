@@ -0,0 +1,42 @@
+use aoc2019::day03::Wire;
+use aoc2019::prelude::Point;
+use std::time::{Duration, Instant};
+
+/// A wire of `len` segments from a fixed linear congruential generator.
+fn wire(seed: u64, len: usize) -> Vec<String> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            let direction = ["R", "U", "L", "D"][(state >> 62) as usize];
+            format!("{}{}", direction, (state >> 32) % 1000 + 1)
+        })
+        .collect()
+}
+
+fn parse(segments: &[String]) -> Wire {
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+    Wire::from_str(Point::new(0, 0), &segments).unwrap()
+}
+
+fn time<T, F: FnMut() -> T>(name: &str, runs: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        std::hint::black_box(f());
+    }
+    let average = start.elapsed() / runs;
+    println!("{:<10} {:>12.3?}", name, average);
+    average
+}
+
+fn main() {
+    let (a, b) = (parse(&wire(1, 301)), parse(&wire(2, 301)));
+    let steps = time("steps", 5, || a.step_intersections(&b).count());
+    let segments = time("segments", 50, || a.intersections(&b).count());
+    println!(
+        "segments are {:.1}x faster",
+        steps.as_secs_f64() / segments.as_secs_f64()
+    );
+}
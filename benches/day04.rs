@@ -0,0 +1,35 @@
+use aoc2019::day04::{count, count_parallel, rules, scan, scan_parallel, HasExactPair};
+use std::time::{Duration, Instant};
+
+const LOW: u64 = 100_000;
+const HIGH: u64 = 999_999;
+
+fn time<T, F: FnMut() -> T>(name: &str, runs: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        std::hint::black_box(f());
+    }
+    let average = start.elapsed() / runs;
+    println!("{:<10} {:>12.3?}", name, average);
+    average
+}
+
+fn main() {
+    let rules = rules(LOW, HIGH, HasExactPair);
+    let threads = rayon::current_num_threads() as u32;
+    let serial = time("serial", 5, || scan(LOW, HIGH, &rules));
+    let parallel = time("parallel", 5, || scan_parallel(LOW, HIGH, &rules, threads));
+    let enumerated = time("enumerate", 50, || count(LOW, HIGH, &rules));
+    let both = time("par-enum", 50, || count_parallel(LOW, HIGH, &rules));
+    println!(
+        "{} threads are {:.1}x faster than one, enumerating is {:.1}x faster still",
+        threads,
+        serial.as_secs_f64() / parallel.as_secs_f64(),
+        parallel.as_secs_f64() / enumerated.as_secs_f64()
+    );
+    println!(
+        "Enumerating on {} threads is {:.1}x faster than on one",
+        threads,
+        enumerated.as_secs_f64() / both.as_secs_f64()
+    );
+}
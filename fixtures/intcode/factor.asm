@@ -0,0 +1,73 @@
+; Reads n > 0 and outputs its prime factors in ascending order.
+const STACK = 1000
+var n
+var d
+var square
+var cond
+var num
+var den
+var quo
+var rem
+var step
+var bit
+var next
+var neg
+var depth
+    in n
+    mov #2, d
+try:
+    mul d, d, square
+    lt n, square, cond
+    jt cond, #last
+    mov n, num
+    mov d, den
+    call divmod
+    jt rem, #advance
+    out d
+    mov quo, n
+    jmp try
+advance:
+    add d, #1, d
+    jmp try
+last:
+    lt #1, n, cond
+    jf cond, #done
+    out n
+done:
+    halt
+
+; Divides num >= 0 by den > 0 into quo and rem. Intcode cannot halve, so
+; the doubled divisors are kept on a stack from STACK on.
+sub divmod
+    mov num, rem
+    mov #0, quo
+    mov den, step
+    mov #1, bit
+    mov #0, depth
+grow:
+    mul step, #2, next
+    lt rem, next, cond
+    jt cond, #shrink
+    mov step, @STACK
+    mov bit, @STACK+1
+    arb #2
+    add depth, #1, depth
+    mov next, step
+    mul bit, #2, bit
+    jmp grow
+shrink:
+    lt rem, step, cond
+    jt cond, #pop
+    mul step, #-1, neg
+    add rem, neg, rem
+    add quo, bit, quo
+pop:
+    jf depth, #end
+    arb #-2
+    mov @STACK, step
+    mov @STACK+1, bit
+    add depth, #-1, depth
+    jmp shrink
+end:
+    ret
+endsub
@@ -0,0 +1,11 @@
+; Prints "Hello, World!" and a newline.
+    arb #text
+next:
+    jf @0, #done
+    out @0
+    arb #1
+    jmp next
+done:
+    halt
+text:
+    data 72, 101, 108, 108, 111, 44, 32, 87, 111, 114, 108, 100, 33, 10, 0
@@ -0,0 +1,36 @@
+; Reads n and outputs the sum of the primes below n. A sieve of
+; Eratosthenes marks the composites in the cells from SIEVE on.
+const SIEVE = 1000
+var n
+var i
+var j
+var neg
+var sum
+var cond
+    in n
+    mov #2, i
+next:
+    lt i, n, cond
+    jf cond, #done
+    arb i
+    mov @SIEVE, cond
+    mul i, #-1, neg
+    arb neg
+    jt cond, #advance
+    add sum, i, sum
+    mul i, i, j
+mark:
+    lt j, n, cond
+    jf cond, #advance
+    arb j
+    mov #1, @SIEVE
+    mul j, #-1, neg
+    arb neg
+    add j, i, j
+    jmp mark
+advance:
+    add i, #1, i
+    jmp next
+done:
+    out sum
+    halt
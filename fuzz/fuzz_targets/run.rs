@@ -0,0 +1,43 @@
+#![no_main]
+use aoc2019::vm::memory::{GrowableMemory, SparseMemory};
+use aoc2019::vm::{Computer, Engine, Memory, State};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    program: Vec<i64>,
+    inputs: Vec<i64>,
+    decoded: bool,
+    sparse: bool,
+}
+
+fn run<M: Memory<Value = i64> + Clone>(memory: M, inputs: Vec<i64>, decoded: bool) {
+    let mut computer = Computer::new(memory);
+    if decoded {
+        computer.set_engine(Engine::Decoded);
+    }
+    computer.extend_inputs(inputs);
+    match computer.run_with_limit(10_000) {
+        State::Faulted(err) => drop(err.to_string()),
+        _ => drop(computer.take_outputs()),
+    }
+}
+
+// Any program may fail, but none may panic. The memory cap and step limit
+// keep runaway programs from exhausting the fuzzer's memory and time.
+// Sparse memory has no cap, so it checks that nothing allocates per
+// address, such as the decoded engine's slots.
+fuzz_target!(|input: Input| {
+    let Input {
+        program,
+        inputs,
+        decoded,
+        sparse,
+    } = input;
+    if sparse {
+        run(SparseMemory::from(program), inputs, decoded);
+    } else {
+        run(GrowableMemory::with_cap(program, 1 << 16), inputs, decoded);
+    }
+});
@@ -0,0 +1,134 @@
+#ifndef INTCODE_H
+#define INTCODE_H
+
+/* Generated by `cargo xtask header`, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum IntcodeState {
+  IntcodeState_Running,
+  IntcodeState_Halted,
+  IntcodeState_NeedsInput,
+  IntcodeState_OutputFull,
+  IntcodeState_StepLimitExceeded,
+  IntcodeState_DeadlineExceeded,
+  IntcodeState_Watchpoint,
+  /**
+   * The message is available from `intcode_error`.
+   */
+  IntcodeState_Faulted,
+} IntcodeState;
+
+/**
+ * An Intcode computer owned by C code.
+ */
+typedef struct IntcodeComputer IntcodeComputer;
+
+typedef int64_t IntcodeValue;
+
+/**
+ * Creates a computer for the `len` values at `program`.
+ *
+ * # Safety
+ *
+ * `program` must point to `len` readable values. The result must be
+ * released with `intcode_free`.
+ */
+struct IntcodeComputer *intcode_new(const IntcodeValue *program, uintptr_t len);
+
+/**
+ * Creates a computer for the comma separated program in `source`, or
+ * returns null if it does not parse.
+ *
+ * # Safety
+ *
+ * `source` must be a NUL terminated string. The result must be released
+ * with `intcode_free`.
+ */
+struct IntcodeComputer *intcode_parse(const char *source);
+
+/**
+ * # Safety
+ *
+ * `computer` must come from `intcode_new` or `intcode_parse` and must not
+ * be used afterwards. Null is ignored.
+ */
+void intcode_free(struct IntcodeComputer *computer);
+
+/**
+ * Restores the initial memory and drops all queued inputs and outputs.
+ *
+ * # Safety
+ *
+ * `computer` must be a live computer.
+ */
+void intcode_reset(struct IntcodeComputer *computer);
+
+/**
+ * # Safety
+ *
+ * `computer` must be a live computer.
+ */
+void intcode_push_input(struct IntcodeComputer *computer, IntcodeValue value);
+
+/**
+ * Stores the oldest output in `value` and returns true, or returns false
+ * if there is none.
+ *
+ * # Safety
+ *
+ * `computer` must be a live computer and `value` writable.
+ */
+bool intcode_pop_output(struct IntcodeComputer *computer, IntcodeValue *value);
+
+/**
+ * Executes a single instruction.
+ *
+ * # Safety
+ *
+ * `computer` must be a live computer.
+ */
+enum IntcodeState intcode_step(struct IntcodeComputer *computer);
+
+/**
+ * Runs until the program blocks or `max_steps` instructions were executed.
+ *
+ * # Safety
+ *
+ * `computer` must be a live computer.
+ */
+enum IntcodeState intcode_run(struct IntcodeComputer *computer, uintptr_t max_steps);
+
+/**
+ * Stores the cell at `address` in `value`; returns false if it cannot be
+ * read.
+ *
+ * # Safety
+ *
+ * `computer` must be a live computer and `value` writable.
+ */
+bool intcode_read_memory(const struct IntcodeComputer *computer,
+                         uintptr_t address,
+                         IntcodeValue *value);
+
+/**
+ * # Safety
+ *
+ * `computer` must be a live computer.
+ */
+uintptr_t intcode_ip(const struct IntcodeComputer *computer);
+
+/**
+ * The message of the last fault, or null. The string is valid until the
+ * next call with `computer`.
+ *
+ * # Safety
+ *
+ * `computer` must be a live computer.
+ */
+const char *intcode_error(const struct IntcodeComputer *computer);
+
+#endif  /* INTCODE_H */
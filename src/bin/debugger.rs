@@ -0,0 +1,242 @@
+use anyhow::{anyhow, bail, Context, Result};
+use aoc2019::vm::decompile::disassemble_one;
+use aoc2019::vm::memory::GrowableMemory;
+use aoc2019::vm::{parse_program, Computer, Memory, State};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+break <addr>         set a breakpoint
+delete <addr>        remove a breakpoint
+watch <addr>         stop when an instruction reads or writes addr
+unwatch <addr>       remove a watchpoint
+step [n]             execute n instructions (default 1)
+continue             run until a breakpoint, halt or missing input
+input <values...>    queue input values
+mem <addr> [count]   show memory
+set <addr> <value>   change memory
+regs                 show ip, relative base, state and breakpoints
+list [count]         disassemble from the ip
+quit                 exit";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Break(usize),
+    Delete(usize),
+    Watch(usize),
+    Unwatch(usize),
+    Step(usize),
+    Continue,
+    Input(Vec<i64>),
+    Mem(usize, usize),
+    Set(usize, i64),
+    Regs,
+    List(usize),
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or("help");
+    let args = words.collect::<Vec<_>>();
+    let number = |index: usize| -> Result<i64> {
+        args.get(index)
+            .ok_or_else(|| anyhow!("Missing argument for {}", command))?
+            .parse()
+            .with_context(|| format!("Invalid number {:?}", args[index]))
+    };
+    let address = |index: usize| -> Result<usize> {
+        let value = number(index)?;
+        if value < 0 {
+            bail!("Negative address {}", value);
+        }
+        Ok(value as usize)
+    };
+    let count = |index: usize, default: usize| -> Result<usize> {
+        if args.len() > index {
+            address(index)
+        } else {
+            Ok(default)
+        }
+    };
+    Ok(match command {
+        "b" | "break" => Command::Break(address(0)?),
+        "d" | "delete" => Command::Delete(address(0)?),
+        "w" | "watch" => Command::Watch(address(0)?),
+        "unwatch" => Command::Unwatch(address(0)?),
+        "s" | "step" => Command::Step(count(0, 1)?),
+        "c" | "continue" => Command::Continue,
+        "i" | "input" => Command::Input((0..args.len()).map(number).collect::<Result<Vec<_>>>()?),
+        "m" | "mem" => Command::Mem(address(0)?, count(1, 8)?),
+        "set" => Command::Set(address(0)?, number(1)?),
+        "r" | "regs" => Command::Regs,
+        "l" | "list" => Command::List(count(0, 5)?),
+        "h" | "help" => Command::Help,
+        "q" | "quit" => Command::Quit,
+        _ => bail!("Unknown command {:?}, try help", command),
+    })
+}
+
+struct Debugger {
+    computer: Computer<GrowableMemory>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    /// Executes until `stop` returns true after an instruction or the
+    /// program cannot continue.
+    fn run(&mut self, mut stop: impl FnMut(&Computer<GrowableMemory>) -> bool) {
+        let state = loop {
+            match self.computer.single_step() {
+                State::Running if stop(&self.computer) => break State::Running,
+                State::Running => (),
+                state => break state,
+            }
+        };
+        for value in self.computer.take_outputs() {
+            println!("output: {}", value);
+        }
+        match state {
+            State::Running => println!("stopped at {}", self.computer.ip()),
+            State::NeedsInput => println!("waiting for input at {}", self.computer.ip()),
+            State::Watchpoint { address, access } => {
+                println!(
+                    "{:?} of {}, stopped at {}",
+                    access,
+                    address,
+                    self.computer.ip()
+                )
+            }
+            State::Faulted(err) => println!("fault: {}", err),
+            state => println!("{:?}", state),
+        }
+    }
+
+    fn list(&self, count: usize) -> Result<()> {
+        let mut address = self.computer.ip();
+        for _ in 0..count {
+            let words = self.computer.memory().read_slice(address..address + 4)?;
+            let marker = if address == self.computer.ip() {
+                '>'
+            } else {
+                ' '
+            };
+            match disassemble_one(&words) {
+                Some((text, len)) => {
+                    println!("{}{:5}: {}", marker, address, text);
+                    address += len;
+                }
+                None => {
+                    println!("{}{:5}: data {}", marker, address, words[0]);
+                    address += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, command: Command) -> Result<bool> {
+        match command {
+            Command::Break(address) => {
+                self.breakpoints.insert(address);
+            }
+            Command::Delete(address) => {
+                if !self.breakpoints.remove(&address) {
+                    println!("no breakpoint at {}", address);
+                }
+            }
+            Command::Watch(address) => self.computer.add_watchpoint(address),
+            Command::Unwatch(address) => {
+                if !self.computer.remove_watchpoint(address) {
+                    println!("no watchpoint at {}", address);
+                }
+            }
+            Command::Step(count) => {
+                let mut remaining = count;
+                self.run(|_| {
+                    remaining = remaining.saturating_sub(1);
+                    remaining == 0
+                });
+                self.list(1)?;
+            }
+            Command::Continue => {
+                let breakpoints = self.breakpoints.clone();
+                self.run(|computer| breakpoints.contains(&computer.ip()));
+                self.list(1)?;
+            }
+            Command::Input(values) => self.computer.extend_inputs(values),
+            Command::Mem(address, count) => {
+                let values = self
+                    .computer
+                    .memory()
+                    .read_slice(address..address + count)?;
+                for (row, chunk) in values.chunks(8).enumerate() {
+                    let chunk = chunk.iter().map(|value| format!("{:>8}", value));
+                    println!("{:5}: {}", address + 8 * row, chunk.collect::<String>());
+                }
+            }
+            Command::Set(address, value) => self.computer.patch(address, value)?,
+            Command::Regs => println!(
+                "ip {}  rb {}  state {:?}  breakpoints {:?}",
+                self.computer.ip(),
+                self.computer.relative_base(),
+                self.computer.state(),
+                self.breakpoints
+            ),
+            Command::List(count) => self.list(count)?,
+            Command::Help => println!("{}", HELP),
+            Command::Quit => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+fn main() -> Result<()> {
+    let path = env::args()
+        .nth(1)
+        .ok_or_else(|| anyhow!("Usage: debugger <program>"))?;
+    let input = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?;
+    let mut debugger = Debugger {
+        computer: Computer::new(GrowableMemory::new(parse_program(&input)?)),
+        breakpoints: BTreeSet::new(),
+    };
+    debugger.list(1)?;
+    let stdin = io::stdin();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let keep_going = parse_command(&line).and_then(|command| debugger.execute(command));
+        match keep_going {
+            Ok(true) => (),
+            Ok(false) => return Ok(()),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() -> Result<()> {
+        assert_eq!(parse_command("break 12")?, Command::Break(12));
+        assert_eq!(parse_command("watch 7")?, Command::Watch(7));
+        assert_eq!(parse_command("s")?, Command::Step(1));
+        assert_eq!(parse_command("step 5")?, Command::Step(5));
+        assert_eq!(parse_command("mem 100")?, Command::Mem(100, 8));
+        assert_eq!(parse_command("m 100 3")?, Command::Mem(100, 3));
+        assert_eq!(parse_command("input 1 -2")?, Command::Input(vec![1, -2]));
+        assert_eq!(parse_command("set 3 -4")?, Command::Set(3, -4));
+        assert!(parse_command("break -1").is_err());
+        assert!(parse_command("frob").is_err());
+        Ok(())
+    }
+}
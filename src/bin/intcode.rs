@@ -0,0 +1,209 @@
+use anyhow::{anyhow, bail, Context, Result};
+use aoc2019::vm::memory::GrowableMemory;
+use aoc2019::vm::{
+    parse_program, stream, Computer, DebugPrint, JsonTracer, Recorder, Recording, State,
+};
+use std::any::Any;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::net::TcpListener;
+
+const USAGE: &str = "\
+Usage: intcode [--ascii] [--debug] [--trace <file>] [--record <file>] [--listen <address>]
+               [--vm-stats <file.csv>] <program>
+       intcode --replay <file> <program>
+
+Runs an Intcode program with stdin as its input and stdout as its output.
+Numbers are read separated by whitespace or commas and printed one per line.
+With --ascii, input lines are sent as text and ASCII output is printed as
+text; other values are printed on their own line. With --listen, the I/O is
+served over TCP instead, one connection at a time. With --debug, the
+nonstandard opcode 98 prints its parameter and the ip to stderr.
+
+--record saves every input and output in order to a file, which --replay
+feeds back to the program, failing unless it produces the same outputs.
+
+--vm-stats writes the executions and time per opcode, the hits per
+instruction address and the reads and writes per data address as CSV with
+the columns kind,key,value after the run.";
+
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    ascii: bool,
+    debug: bool,
+    trace: Option<String>,
+    record: Option<String>,
+    replay: Option<String>,
+    listen: Option<String>,
+    vm_stats: Option<String>,
+    program: String,
+}
+
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Options> {
+    let mut options = Options::default();
+    let mut program = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ascii" => options.ascii = true,
+            "--debug" => options.debug = true,
+            "--trace" => {
+                options.trace = Some(args.next().ok_or_else(|| anyhow!("--trace needs a path"))?)
+            }
+            "--record" => {
+                options.record = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--record needs a path"))?,
+                )
+            }
+            "--replay" => {
+                options.replay = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--replay needs a path"))?,
+                )
+            }
+            "--listen" => {
+                options.listen = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--listen needs an address"))?,
+                )
+            }
+            "--vm-stats" => {
+                options.vm_stats = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--vm-stats needs a path"))?,
+                )
+            }
+            "-h" | "--help" => bail!("{}", USAGE),
+            _ if arg.starts_with('-') => bail!("Unknown argument {}\n\n{}", arg, USAGE),
+            _ if program.is_some() => bail!("Only one program can be run\n\n{}", USAGE),
+            _ => program = Some(arg),
+        }
+    }
+    options.program = program.ok_or_else(|| anyhow!("{}", USAGE))?;
+    if options.trace.is_some() && options.record.is_some() {
+        bail!("--trace and --record cannot be combined");
+    }
+    Ok(options)
+}
+
+fn main() -> Result<()> {
+    let options = parse_args(env::args().skip(1))?;
+    let source = fs::read_to_string(&options.program)
+        .with_context(|| format!("Failed to read {}", options.program))?;
+    let mut computer = Computer::new(GrowableMemory::new(parse_program(&source)?));
+    if options.debug {
+        computer.set_extension(DebugPrint::new(io::stderr()));
+    }
+    if let Some(path) = &options.trace {
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path))?;
+        computer.set_tracer(JsonTracer::new(BufWriter::new(file)));
+    }
+    if options.record.is_some() {
+        computer.set_tracer(Recorder::new());
+    }
+    if options.vm_stats.is_some() {
+        computer.enable_profiling();
+        computer.enable_heatmap();
+    }
+    if let Some(path) = &options.replay {
+        let recording = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path))?
+            .parse::<Recording>()?;
+        computer.replay(&recording)?;
+        eprintln!("Replayed {} events", recording.events.len());
+        return Ok(());
+    }
+
+    let state = match &options.listen {
+        Some(address) => {
+            let listener = TcpListener::bind(address)
+                .with_context(|| format!("Failed to listen on {}", address))?;
+            eprintln!("Listening on {}", listener.local_addr()?);
+            stream::serve(&mut computer, &listener, options.ascii)?
+        }
+        None => {
+            let stdin = io::stdin();
+            let stdout = io::stdout();
+            let output = BufWriter::new(stdout.lock());
+            stream::run(&mut computer, stdin.lock(), output, options.ascii)?
+        }
+    };
+    if let (Some(path), Some(recorder)) = (&options.record, computer.tracer::<Recorder>()) {
+        fs::write(path, recorder.recording().to_string())
+            .with_context(|| format!("Failed to write {}", path))?;
+    }
+    if let Some(path) = &options.vm_stats {
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path))?;
+        computer
+            .write_stats(BufWriter::new(file))
+            .with_context(|| format!("Failed to write {}", path))?;
+    }
+    match state {
+        State::Halted => (),
+        State::NeedsInput => bail!(
+            "Program needs input at {} but stdin is closed",
+            computer.ip()
+        ),
+        State::Faulted(err) => return Err(err.into()),
+        state => bail!("Unexpected state {:?}", state),
+    }
+    if let Some(tracer) = computer.take_tracer() {
+        let tracer: Box<dyn Any> = tracer;
+        if let Ok(tracer) = tracer.downcast::<JsonTracer<BufWriter<File>>>() {
+            tracer.finish().context("Failed to write the trace")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Options> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() -> Result<()> {
+        assert_eq!(
+            args(&["--ascii", "day17.txt"])?,
+            Options {
+                ascii: true,
+                debug: false,
+                trace: None,
+                record: None,
+                replay: None,
+                listen: None,
+                vm_stats: None,
+                program: "day17.txt".to_string()
+            }
+        );
+        assert!(args(&["--debug", "p"])?.debug);
+        assert_eq!(
+            args(&["--trace", "t.jsonl", "p"])?.trace.as_deref(),
+            Some("t.jsonl")
+        );
+        assert_eq!(
+            args(&["--listen", "127.0.0.1:2525", "p"])?
+                .listen
+                .as_deref(),
+            Some("127.0.0.1:2525")
+        );
+        assert_eq!(
+            args(&["--replay", "run.txt", "p"])?.replay.as_deref(),
+            Some("run.txt")
+        );
+        assert_eq!(
+            args(&["--vm-stats", "stats.csv", "p"])?.vm_stats.as_deref(),
+            Some("stats.csv")
+        );
+        assert!(args(&["--trace", "t", "--record", "r", "p"]).is_err());
+        assert!(args(&[]).is_err());
+        assert!(args(&["--trace"]).is_err());
+        assert!(args(&["a", "b"]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{anyhow, Context, Result};
+use aoc2019::vm::decompile::disassemble_one;
+use aoc2019::vm::memory::GrowableMemory;
+use aoc2019::vm::{parse_program, Computer, Memory, State};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeSet, VecDeque};
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+const HELP: &str = "s step  c continue  p pause  b breakpoint  i input  j/k memory  q quit";
+const HISTORY: usize = 4;
+const STEPS_PER_FRAME: usize = 10_000;
+
+struct App {
+    computer: Computer<GrowableMemory>,
+    breakpoints: BTreeSet<usize>,
+    /// Addresses of the most recently executed instructions.
+    history: VecDeque<usize>,
+    outputs: Vec<i64>,
+    running: bool,
+    memory_start: usize,
+    /// Text typed after pressing `i`, queued as input on enter.
+    input: Option<String>,
+    status: String,
+}
+
+impl App {
+    fn new(computer: Computer<GrowableMemory>) -> Self {
+        Self {
+            computer,
+            breakpoints: BTreeSet::new(),
+            history: VecDeque::new(),
+            outputs: Vec::new(),
+            running: false,
+            memory_start: 0,
+            input: None,
+            status: "paused".to_string(),
+        }
+    }
+
+    /// Executes up to `count` instructions, stopping early at breakpoints
+    /// and when the program cannot continue.
+    fn step(&mut self, count: usize) {
+        for _ in 0..count {
+            let ip = self.computer.ip();
+            let state = self.computer.single_step();
+            if self.computer.ip() != ip || state == State::Halted {
+                self.history.push_back(ip);
+                if self.history.len() > HISTORY {
+                    self.history.pop_front();
+                }
+            }
+            self.outputs.extend(self.computer.take_outputs());
+            match state {
+                State::Running if self.breakpoints.contains(&self.computer.ip()) => {
+                    self.pause(format!("breakpoint at {}", self.computer.ip()));
+                    return;
+                }
+                State::Running => (),
+                State::Watchpoint { address, access } => {
+                    self.pause(format!("{:?} of {}", access, address));
+                    return;
+                }
+                State::NeedsInput => {
+                    self.pause("waiting for input".to_string());
+                    return;
+                }
+                State::Faulted(err) => {
+                    self.pause(format!("fault: {}", err));
+                    return;
+                }
+                state => {
+                    self.pause(format!("{:?}", state));
+                    return;
+                }
+            }
+        }
+        self.status = if self.running { "running" } else { "paused" }.to_string();
+    }
+
+    fn pause(&mut self, status: String) {
+        self.running = false;
+        self.status = status;
+    }
+
+    /// Handles one key press, returning false to quit.
+    fn key(&mut self, code: KeyCode) -> bool {
+        if let Some(input) = &mut self.input {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Enter => {
+                    let values = input
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<Vec<i64>, _>>();
+                    match values {
+                        Ok(values) => self.computer.extend_inputs(values),
+                        Err(err) => self.status = format!("invalid input: {}", err),
+                    }
+                    self.input = None;
+                }
+                _ => (),
+            }
+            return true;
+        }
+        match code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('s') => self.step(1),
+            KeyCode::Char('c') => self.running = true,
+            KeyCode::Char('p') => self.pause("paused".to_string()),
+            KeyCode::Char('b') => {
+                let ip = self.computer.ip();
+                if !self.breakpoints.remove(&ip) {
+                    self.breakpoints.insert(ip);
+                }
+            }
+            KeyCode::Char('i') => self.input = Some(String::new()),
+            KeyCode::Char('j') | KeyCode::Down => self.memory_start += 8,
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.memory_start = self.memory_start.saturating_sub(8)
+            }
+            _ => (),
+        }
+        true
+    }
+
+    fn disassemble(&self, address: usize) -> (String, usize) {
+        let words = self
+            .computer
+            .memory()
+            .read_slice(address..address + 4)
+            .unwrap_or_default();
+        match disassemble_one(&words) {
+            Some((text, len)) => (text, len),
+            None => (format!("data {}", words.first().unwrap_or(&0)), 1),
+        }
+    }
+
+    fn code_lines(&self, height: usize) -> Vec<Line<'_>> {
+        let ip = self.computer.ip();
+        let marker = |address: usize| {
+            if self.breakpoints.contains(&address) {
+                '*'
+            } else {
+                ' '
+            }
+        };
+        let mut lines = self
+            .history
+            .iter()
+            .map(|&address| {
+                let text = format!(
+                    "{}{:6}: {}",
+                    marker(address),
+                    address,
+                    self.disassemble(address).0
+                );
+                Line::from(text).dim()
+            })
+            .collect::<Vec<_>>();
+        let mut address = ip;
+        while lines.len() < height {
+            let (text, len) = self.disassemble(address);
+            let line = Line::from(format!("{}{:6}: {}", marker(address), address, text));
+            lines.push(if address == ip { line.reversed() } else { line });
+            address += len;
+        }
+        lines
+    }
+
+    fn memory_lines(&self, height: usize) -> Vec<Line<'_>> {
+        (0..height)
+            .map(|row| {
+                let start = self.memory_start + 8 * row;
+                let values = self
+                    .computer
+                    .memory()
+                    .read_slice(start..start + 8)
+                    .unwrap_or_default();
+                let values = values.iter().map(|value| format!("{:>8}", value));
+                Line::from(format!("{:6}: {}", start, values.collect::<String>()))
+            })
+            .collect()
+    }
+
+    fn profile_lines(&self, height: usize) -> Vec<Line<'_>> {
+        let profile = match self.computer.profile() {
+            Some(profile) => profile,
+            None => return Vec::new(),
+        };
+        let mut lines = vec![Line::from(format!("{} instructions", profile.total()))];
+        for (address, hits) in profile.hottest(height.saturating_sub(1)) {
+            let text = self.disassemble(address).0;
+            lines.push(Line::from(format!("{:>10} {:6}: {}", hits, address, text)));
+        }
+        lines
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [code, right] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(main);
+        let [memory, io, profile] = Layout::vertical([
+            Constraint::Percentage(50),
+            Constraint::Length(4),
+            Constraint::Min(0),
+        ])
+        .areas(right);
+        let inner = |area: Rect| area.height.saturating_sub(2) as usize;
+
+        let title = format!(
+            "ip {}  rb {}",
+            self.computer.ip(),
+            self.computer.relative_base()
+        );
+        frame.render_widget(
+            Paragraph::new(self.code_lines(inner(code))).block(Block::bordered().title(title)),
+            code,
+        );
+        frame.render_widget(
+            Paragraph::new(self.memory_lines(inner(memory)))
+                .block(Block::bordered().title("memory")),
+            memory,
+        );
+        let outputs = self.outputs.iter().rev().take(32).rev();
+        let io_lines = vec![
+            Line::from(format!("in:  {:?}", self.computer.pending_inputs())),
+            Line::from(format!("out: {:?}", outputs.collect::<Vec<_>>())),
+        ];
+        frame.render_widget(
+            Paragraph::new(io_lines).block(Block::bordered().title("i/o")),
+            io,
+        );
+        frame.render_widget(
+            Paragraph::new(self.profile_lines(inner(profile)))
+                .block(Block::bordered().title("profile")),
+            profile,
+        );
+        let status_line = match &self.input {
+            Some(input) => format!("input> {}", input),
+            None => format!("{}  |  {}", self.status, HELP),
+        };
+        frame.render_widget(
+            Paragraph::new(status_line).style(Style::new().reversed()),
+            status,
+        );
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let timeout = if self.running {
+                Duration::from_millis(0)
+            } else {
+                Duration::from_millis(250)
+            };
+            if event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.key(key.code) {
+                        return Ok(());
+                    }
+                }
+            }
+            if self.running {
+                self.step(STEPS_PER_FRAME);
+            }
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let path = env::args()
+        .nth(1)
+        .ok_or_else(|| anyhow!("Usage: tui <program>"))?;
+    let input = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?;
+    let mut computer = Computer::new(GrowableMemory::new(parse_program(&input)?));
+    computer.enable_profiling();
+    let mut terminal = ratatui::init();
+    let result = App::new(computer).run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -1,39 +1,71 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::prelude::*;
 use std::iter;
 
-use anyhow::Result;
-
-fn fuel_requirement(mass: u64) -> u64 {
+/// The fuel to launch a module of `mass`, not counting the fuel's own mass.
+pub fn fuel_requirement(mass: u64) -> u64 {
     (mass / 3).saturating_sub(2)
 }
 
-fn full_fuel_requirement(mass: u64) -> u64 {
-    let total_fuel_requirement = fuel_requirement(mass);
-    let mut last_fuel_requirement = total_fuel_requirement;
-    iter::from_fn(move || {
-        last_fuel_requirement = fuel_requirement(last_fuel_requirement);
-        match last_fuel_requirement {
-            0 => None,
-            _ => Some(last_fuel_requirement),
-        }
+/// The fuel for `mass`, then the fuel for that fuel and so on, until no more
+/// fuel is needed.
+pub fn fuel_terms(mass: u64) -> impl Iterator<Item = u64> {
+    iter::successors(Some(fuel_requirement(mass)), |&fuel| {
+        Some(fuel_requirement(fuel))
     })
-    .fold(total_fuel_requirement, |acc, x| acc + x)
+    .take_while(|&fuel| fuel > 0)
+}
+
+/// The fuel to launch a module of `mass`, including the fuel's own mass.
+pub fn full_fuel_requirement(mass: u64) -> u64 {
+    fuel_terms(mass).sum()
 }
 
-fn get_modules() -> Result<impl Iterator<Item = Result<u64>>> {
-    let file: File = File::open("data/day01.txt")?;
-    let buf_reader = BufReader::new(file);
-    Ok(buf_reader.lines().map(|line| Ok(line?.parse::<u64>()?)))
+fn get_modules() -> Result<Vec<u64>> {
+    input::read(1)?
+        .lines()
+        .map(|line| Ok(line.parse::<u64>()?))
+        .collect()
 }
 
-pub(crate) fn calculate_fuel(fn_fuel: &dyn Fn(u64) -> u64) -> Result<u64> {
-    let mut modules = get_modules()?;
-    modules.try_fold(0u64, |acc, x| Ok(acc + fn_fuel(x?)))
+pub fn total_fuel(modules: &[u64], fn_fuel: &dyn Fn(u64) -> u64) -> u64 {
+    modules.iter().map(|&mass| fn_fuel(mass)).sum()
 }
 
 pub fn main() -> Result<()> {
-    println!("Part 1: {}", calculate_fuel(&fuel_requirement)?);
-    println!("Part 2: {}", calculate_fuel(&full_fuel_requirement)?);
+    let modules = get_modules()?;
+    answer(1, total_fuel(&modules, &fuel_requirement));
+    answer(2, total_fuel(&modules, &full_fuel_requirement));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(fuel_requirement(12), 2);
+        assert_eq!(fuel_requirement(14), 2);
+        assert_eq!(fuel_requirement(1969), 654);
+        assert_eq!(fuel_requirement(100_756), 33_583);
+        assert_eq!(total_fuel(&[12, 14], &fuel_requirement), 4);
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(
+            fuel_terms(1969).collect::<Vec<_>>(),
+            vec![654, 216, 70, 21, 5]
+        );
+        assert_eq!(fuel_terms(2).count(), 0);
+        assert_eq!(full_fuel_requirement(14), 2);
+        assert_eq!(full_fuel_requirement(1969), 966);
+        assert_eq!(full_fuel_requirement(100_756), 50_346);
+        assert_eq!(total_fuel(&[1969, 100_756], &full_fuel_requirement), 51_312);
+    }
+
+    #[test]
+    fn test_main() -> Result<()> {
+        main()
+    }
+}
@@ -1,32 +1,122 @@
-use crate::vm::Computer;
-use anyhow::{Error, Result};
-use std::fs::read_to_string;
-
-fn run(data: &[i64], noun: i64, verb: i64) -> Result<i64> {
-    let mut data = data.to_owned();
-    data[1] = noun;
-    data[2] = verb;
-    Ok(Computer::new(data).execute()?)
+use crate::prelude::*;
+use anyhow::anyhow;
+
+const TARGET: i64 = 19_690_720;
+
+fn run(vm: &mut Computer<Vec<i64>>, noun: i64, verb: i64) -> Result<i64> {
+    vm.reset();
+    vm.patch(1, noun)?;
+    vm.patch(2, verb)?;
+    Ok(vm.execute()?)
 }
 
-pub fn main() -> Result<()> {
-    let input = read_to_string("data/day02.txt")?;
-    let data = input
-        .trim()
-        .split(',')
-        .map(|val| val.parse::<i64>().map_err(Error::from))
-        .collect::<Result<Vec<_>>>()?;
-    println!("Part 1: {}", run(&data, 12, 2)?);
-    for noun in 0..99 {
-        for verb in 0..99 {
-            match run(&data, noun, verb) {
-                Ok(result) if result == 19_690_720 => {
-                    println!("Part 2: {}", 100 * noun + verb);
-                    break;
+/// Tries every noun and verb until the program produces `target`.
+fn brute_force(vm: &mut Computer<Vec<i64>>, target: i64) -> Option<(i64, i64)> {
+    for noun in 0..=99 {
+        for verb in 0..=99 {
+            if let Ok(output) = run(vm, noun, verb) {
+                if output == target {
+                    return Some((noun, verb));
                 }
-                _ => (),
-            };
+            }
+        }
+    }
+    None
+}
+
+/// Solves `c + a·noun + b·verb = target` with the coefficients taken from
+/// three runs, and checks the answer with a fourth.
+///
+/// Returns `None` if the program turns out not to be affine in the noun and
+/// verb, if no noun and verb solve the equation, or if its outputs are too
+/// large to solve it without overflowing.
+fn solve_affine(vm: &mut Computer<Vec<i64>>, target: i64) -> Option<(i64, i64)> {
+    let c = run(vm, 0, 0).ok()?;
+    let a = run(vm, 1, 0).ok()?.checked_sub(c)?;
+    let b = run(vm, 0, 1).ok()?.checked_sub(c)?;
+    let (noun, verb) = (0..=99).find_map(|noun| {
+        let rest = target.checked_sub(c)?.checked_sub(a.checked_mul(noun)?)?;
+        let verb = match b {
+            0 if rest == 0 => 0,
+            0 => return None,
+            _ if rest.checked_rem(b)? == 0 => rest.checked_div(b)?,
+            _ => return None,
+        };
+        if (0..=99).contains(&verb) {
+            Some((noun, verb))
+        } else {
+            None
         }
+    })?;
+    match run(vm, noun, verb) {
+        Ok(output) if output == target => Some((noun, verb)),
+        _ => None,
+    }
+}
+
+fn solve(vm: &mut Computer<Vec<i64>>, target: i64) -> Result<(i64, i64)> {
+    if let Some(answer) = solve_affine(vm, target) {
+        return Ok(answer);
     }
+    explain::emit(Event::BruteForce);
+    brute_force(vm, target).ok_or_else(|| anyhow!("No noun and verb produce {}", target))
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(2)?;
+    let program: Program = input.parse()?;
+    let mut vm = program.into_computer();
+    answer(1, run(&mut vm, 12, 2)?);
+    let (noun, verb) = solve(&mut vm, TARGET)?;
+    answer(2, 100 * noun + verb);
+    explain::emit(Event::NounVerb {
+        noun,
+        verb,
+        output: TARGET,
+    });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affine() -> Result<()> {
+        // 100·noun + verb + 5
+        let mut vm = Program::new(vec![
+            1101, 0, 0, 17, 1002, 1, 99, 18, 1, 17, 18, 0, 1001, 0, 5, 0, 99, 0, 0,
+        ])
+        .into_computer();
+        assert_eq!(solve_affine(&mut vm, 4207), Some((42, 2)));
+        assert_eq!(brute_force(&mut vm, 4207), Some((42, 2)));
+        assert_eq!(solve(&mut vm, 4207)?, (42, 2));
+        assert!(solve(&mut vm, 20_000).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback() -> Result<()> {
+        // noun·verb
+        let mut vm = Program::new(vec![1102, 0, 0, 0, 99]).into_computer();
+        assert_eq!(solve_affine(&mut vm, 156), None);
+        assert_eq!(solve(&mut vm, 156)?, (2, 78));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> Result<()> {
+        // noun + verb + i64::MIN + 10
+        let mut vm =
+            Program::new(vec![1101, 0, 0, 9, 1001, 9, i64::MIN + 10, 0, 99, 0]).into_computer();
+        assert_eq!(solve_affine(&mut vm, 100), None);
+        assert!(solve(&mut vm, 100).is_err());
+        assert_eq!(solve(&mut vm, i64::MIN + 20)?, (0, 10));
+        Ok(())
+    }
+
+    #[test]
+    fn test_main() -> Result<()> {
+        main()
+    }
+}
@@ -1,16 +1,16 @@
-use anyhow::Result;
-use nalgebra::{Point2, Vector2};
+use crate::output;
+use crate::prelude::*;
+use crate::runner::{self, Picture};
 use regex::Regex;
 use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::fs::read_to_string;
+use std::fmt::Write;
+use std::fs;
 use thiserror::Error;
 
 use lazy_static::lazy_static;
 
 type Value = i64;
-type Point = Point2<Value>;
-type Vector = Vector2<Value>;
 
 #[derive(Clone, Error, Debug, PartialEq)]
 pub enum Error {
@@ -18,6 +18,8 @@ pub enum Error {
     InvalidSegment(String),
     #[error("Non intersections found")]
     NoIntersections,
+    #[error("Need at least two wires, got {0}")]
+    TooFewWires(usize),
 }
 
 #[derive(Debug, PartialEq)]
@@ -38,12 +40,12 @@ impl<'a> StepIter<'a> {
 }
 
 impl Segment {
-    fn iter_steps(&self) -> StepIter {
-        StepIter::new(&self)
+    fn iter_steps(&self) -> StepIter<'_> {
+        StepIter::new(self)
     }
 }
 lazy_static! {
-    static ref MATCHER: Regex = Regex::new(r"^(\w)(\d+)$").unwrap();
+    static ref MATCHER: Regex = Regex::new(r"^([A-Z]{1,2})(\d+)$").unwrap();
 }
 
 impl TryFrom<&str> for Segment {
@@ -58,6 +60,10 @@ impl TryFrom<&str> for Segment {
             "L" => Direction::Left,
             "U" => Direction::Up,
             "D" => Direction::Down,
+            "UR" => Direction::UpRight,
+            "UL" => Direction::UpLeft,
+            "DR" => Direction::DownRight,
+            "DL" => Direction::DownLeft,
             _ => return Err(Error::InvalidSegment(value.to_owned())),
         };
         let length: Value = captures[2]
@@ -80,12 +86,85 @@ impl<'a> Iterator for StepIter<'a> {
     }
 }
 
+/// A straight part of a wire: `length` grid `step`s from `start`, which the
+/// wire reaches after `steps` steps.
+#[derive(Debug, Clone)]
+struct Span {
+    start: Point,
+    step: Vector,
+    length: Value,
+    steps: Value,
+}
+
+fn cross(a: &Vector, b: &Vector) -> Value {
+    a.x * b.y - a.y * b.x
+}
+
+impl Span {
+    /// Steps along the span to `point`, if it lies on the span.
+    fn position(&self, point: &Point) -> Option<Value> {
+        let offset = point - self.start;
+        if cross(&offset, &self.step) != 0 {
+            return None;
+        }
+        let norm = self.step.dot(&self.step);
+        let s = offset.dot(&self.step);
+        if s % norm != 0 || !(0..=self.length).contains(&(s / norm)) {
+            return None;
+        }
+        Some(s / norm)
+    }
+
+    /// The points both spans cover, with the steps each wire takes to get
+    /// there.
+    fn crossings(&self, other: &Span) -> Vec<(Point, Value, Value)> {
+        let offset = other.start - self.start;
+        let denominator = cross(&self.step, &other.step);
+        if denominator != 0 {
+            // Solve `self.start + s * self.step == other.start + t * other.step`.
+            let (s, t) = (cross(&offset, &other.step), cross(&offset, &self.step));
+            if s % denominator != 0 || t % denominator != 0 {
+                return Vec::new();
+            }
+            let (s, t) = (s / denominator, t / denominator);
+            if (0..=self.length).contains(&s) && (0..=other.length).contains(&t) {
+                let point = self.start + self.step * s;
+                return vec![(point, self.steps + s, other.steps + t)];
+            }
+            return Vec::new();
+        }
+        if cross(&offset, &self.step) != 0 {
+            return Vec::new();
+        }
+        // Both spans lie on one line, so every point of the overlap counts.
+        let norm = self.step.dot(&self.step);
+        let first = offset.dot(&self.step) / norm;
+        let direction = other.step.dot(&self.step) / norm;
+        // `s = first + direction * t` must lie in `0..=self.length`.
+        let (low, high) = if direction > 0 {
+            (-first, self.length - first)
+        } else {
+            (first - self.length, first)
+        };
+        (low.max(0)..=high.min(other.length))
+            .map(|t| {
+                let s = first + direction * t;
+                (self.start + self.step * s, self.steps + s, other.steps + t)
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum Direction {
     Left,
     Right,
     Up,
     Down,
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
 }
 
 impl Direction {
@@ -95,11 +174,37 @@ impl Direction {
             Direction::Right => Vector::new(length, 0),
             Direction::Up => Vector::new(0, length),
             Direction::Down => Vector::new(0, -length),
+            Direction::UpRight => Vector::new(length, length),
+            Direction::UpLeft => Vector::new(-length, length),
+            Direction::DownRight => Vector::new(length, -length),
+            Direction::DownLeft => Vector::new(-length, -length),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Metric {
+    Manhattan,
+    Chebyshev,
+    EuclideanSquared,
+}
+
+impl Metric {
+    pub fn distance(self, from: &Point, to: &Point) -> Value {
+        let offset = to - from;
+        let (x, y) = (offset.x.abs(), offset.y.abs());
+        match self {
+            Metric::Manhattan => x + y,
+            Metric::Chebyshev => x.max(y),
+            Metric::EuclideanSquared => x * x + y * y,
         }
     }
 }
 
-struct Wire {
+/// Points where wires cross, each with the combined steps to get there.
+pub type Intersections = Vec<(Point, usize)>;
+
+pub struct Wire {
     start: Point,
     segments: Vec<Segment>,
 }
@@ -126,7 +231,13 @@ impl Wire {
             })
     }
 
-    pub fn intersections<'a>(
+    /// Intersections with `other` found by walking both wires step by step.
+    ///
+    /// Yields a point for every time `other` reaches it, with the fewest
+    /// combined steps for this wire. Kept to compare against
+    /// [`intersections`](Self::intersections), which is much faster on long
+    /// wires.
+    pub fn step_intersections<'a>(
         &'a self,
         other: &'a Wire,
     ) -> impl Iterator<Item = (Point, usize)> + 'a {
@@ -150,10 +261,176 @@ impl Wire {
                     .map(|dist| (point, curr_dist + *dist + 2))
             })
     }
+
+    fn spans(&self) -> Vec<Span> {
+        let mut start = self.start;
+        let mut steps = 0;
+        self.segments
+            .iter()
+            .map(|segment| {
+                let span = Span {
+                    start,
+                    step: segment.direction.as_offset(1),
+                    length: segment.length,
+                    steps,
+                };
+                start += span.step * segment.length;
+                steps += segment.length;
+                span
+            })
+            .collect()
+    }
+
+    /// The points both wires reach after at least one step, each with the
+    /// fewest combined steps to get there, ordered by `x` and `y`.
+    ///
+    /// Intersects the straight spans of the wires directly instead of
+    /// visiting every point on them.
+    pub fn intersections(&self, other: &Wire) -> impl Iterator<Item = (Point, usize)> {
+        let mut points: HashMap<Point, Value> = HashMap::new();
+        let others = other.spans();
+        for span in self.spans() {
+            for other in &others {
+                for (point, steps, other_steps) in span.crossings(other) {
+                    if steps == 0 || other_steps == 0 {
+                        continue;
+                    }
+                    let delay = points.entry(point).or_insert(Value::MAX);
+                    *delay = (*delay).min(steps + other_steps);
+                }
+            }
+        }
+        let mut points = points
+            .into_iter()
+            .map(|(point, delay)| (point, delay as usize))
+            .collect::<Vec<_>>();
+        points.sort_by_key(|(point, _)| (point.x, point.y));
+        points.into_iter()
+    }
+
+    /// The start of the wire and the ends of its segments.
+    fn corners(&self) -> Vec<Point> {
+        Some(self.start)
+            .into_iter()
+            .chain(
+                self.spans()
+                    .iter()
+                    .map(|span| span.start + span.step * span.length),
+            )
+            .collect()
+    }
+
+    /// The fewest steps it takes the wire to reach `point`, if it does.
+    pub fn steps_to(&self, point: &Point) -> Option<Value> {
+        self.spans()
+            .iter()
+            .filter_map(|span| span.position(point).map(|s| span.steps + s))
+            .filter(|&steps| steps > 0)
+            .min()
+    }
+
+    /// The intersection with `other` closest to the start of this wire.
+    pub fn closest_intersection(&self, other: &Wire, metric: Metric) -> Option<(Point, Value)> {
+        self.intersections(other)
+            .map(|(point, _)| (point, metric.distance(&self.start, &point)))
+            .min_by_key(|&(_, dist)| dist)
+    }
+}
+
+/// Intersections of every pair of wires, keyed by the indices of the wires.
+pub fn pairwise_intersections(wires: &[Wire]) -> Vec<((usize, usize), Intersections)> {
+    let mut pairs = Vec::new();
+    for (i, a) in wires.iter().enumerate() {
+        for (j, b) in wires.iter().enumerate().skip(i + 1) {
+            pairs.push(((i, j), a.intersections(b).collect()));
+        }
+    }
+    pairs
+}
+
+/// The points every wire reaches after at least one step, each with the
+/// fewest steps of all wires combined, ordered by `x` and `y`.
+pub fn common_intersections(wires: &[Wire]) -> Result<Intersections, Error> {
+    match wires {
+        [first, second, rest @ ..] => Ok(first
+            .intersections(second)
+            .filter_map(|(point, delay)| {
+                rest.iter()
+                    .try_fold(delay, |delay, wire| {
+                        wire.steps_to(&point).map(|steps| delay + steps as usize)
+                    })
+                    .map(|delay| (point, delay))
+            })
+            .collect()),
+        _ => Err(Error::TooFewWires(wires.len())),
+    }
+}
+
+const COLOURS: [&str; 6] = [
+    "#4e79a7", "#f28e2b", "#59a14f", "#b07aa1", "#76b7b2", "#edc948",
+];
+
+/// Draws `wires` and their `intersections` as an SVG image, with the closest
+/// intersection in red and the one with the shortest delay in blue.
+///
+/// The image uses the wires' coordinates with `y` pointing up.
+pub fn svg(
+    wires: &[Wire],
+    intersections: &[(Point, usize)],
+    closest: &Point,
+    shortest: &Point,
+) -> String {
+    let corners = wires.iter().map(Wire::corners).collect::<Vec<_>>();
+    let points = || corners.iter().flatten();
+    let min_x = points().map(|p| p.x).min().unwrap_or(0);
+    let max_x = points().map(|p| p.x).max().unwrap_or(0);
+    let min_y = points().map(|p| -p.y).min().unwrap_or(0);
+    let max_y = points().map(|p| -p.y).max().unwrap_or(0);
+    let size = (max_x - min_x).max(max_y - min_y).max(1);
+    let (margin, radius) = (size / 50 + 1, size / 200 + 1);
+
+    let mut svg = String::new();
+    // Writing to a `String` cannot fail.
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min_x - margin,
+        min_y - margin,
+        max_x - min_x + 2 * margin,
+        max_y - min_y + 2 * margin
+    );
+    for (index, corners) in corners.iter().enumerate() {
+        let points = corners
+            .iter()
+            .map(|p| format!("{},{}", p.x, -p.y))
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1" vector-effect="non-scaling-stroke"/>"#,
+            points.join(" "),
+            COLOURS[index % COLOURS.len()]
+        );
+    }
+    for (point, _) in intersections {
+        let (colour, radius) = if point == closest {
+            ("red", radius * 3)
+        } else if point == shortest {
+            ("blue", radius * 3)
+        } else {
+            ("black", radius)
+        };
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            point.x, -point.y, radius, colour
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
 }
 
 pub fn main() -> Result<()> {
-    let input = read_to_string("data/day03.txt")?;
+    let input = input::read(3)?;
     let lines = input.lines();
     let data = lines
         .map(|line| {
@@ -161,28 +438,38 @@ pub fn main() -> Result<()> {
             Wire::from_str(Point::new(0, 0), &segments).map_err(::anyhow::Error::from)
         })
         .collect::<Result<Vec<_>>>()?;
-    assert_eq!(data.len(), 2);
-    let intersections = data[0].intersections(&data[1]).collect::<Vec<_>>();
-    let closest = intersections
+    for ((i, j), intersections) in pairwise_intersections(&data) {
+        explain::emit(Event::Crossings {
+            wires: (i, j),
+            count: intersections.len(),
+        });
+    }
+    let intersections = common_intersections(&data)?;
+    let (closest, distance) = intersections
         .iter()
-        .fold(None, |acc, val| match acc {
-            None => Some(val.0),
-            Some(curr) => {
-                if val.0.x.abs() + val.0.y.abs() < curr.x.abs() + curr.y.abs() {
-                    Some(val.0)
-                } else {
-                    Some(curr)
-                }
-            }
-        })
+        .map(|(point, _)| (point, Metric::Manhattan.distance(&data[0].start, point)))
+        .min_by_key(|&(_, dist)| dist)
         .ok_or_else(|| ::anyhow::Error::from(Error::NoIntersections))?;
-    println!("Part 1: {}", closest.x.abs() + closest.y.abs());
-    let shortest = intersections
+    answer(1, distance);
+    explain::emit(Event::ClosestIntersection {
+        point: *closest,
+        of: intersections.len(),
+    });
+    let (point, shortest) = intersections
         .iter()
-        .map(|(_, dist)| dist)
-        .min()
+        .min_by_key(|(_, dist)| dist)
         .ok_or_else(|| ::anyhow::Error::from(Error::NoIntersections))?;
-    println!("Part 2: {}", shortest);
+    answer(2, shortest);
+    explain::emit(Event::ShortestDelay { point: *point });
+    if output::enabled() || runner::visuals_enabled() {
+        let svg = svg(&data, &intersections, closest, point);
+        if output::enabled() {
+            let path = output::path(3, "wires.svg")?;
+            fs::write(&path, &svg)?;
+            explain::emit(Event::Wrote(path));
+        }
+        runner::visualize("Wires", Picture::Svg(svg));
+    }
     Ok(())
 }
 
@@ -251,12 +538,169 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_closest_intersection() {
+        let wire_a = Wire::new(
+            Point::new(0, 0),
+            vec![
+                Segment::new(Direction::Up, 3),
+                Segment::new(Direction::Right, 6),
+                Segment::new(Direction::Down, 3),
+            ],
+        );
+        let wire_b = Wire::new(
+            Point::new(0, 0),
+            vec![
+                Segment::new(Direction::Right, 8),
+                Segment::new(Direction::Up, 1),
+                Segment::new(Direction::Left, 4),
+                Segment::new(Direction::Up, 5),
+            ],
+        );
+        assert_eq!(
+            wire_a.closest_intersection(&wire_b, Metric::Manhattan),
+            Some((Point::new(6, 0), 6))
+        );
+        assert_eq!(
+            wire_a.closest_intersection(&wire_b, Metric::Chebyshev),
+            Some((Point::new(4, 3), 4))
+        );
+        assert_eq!(
+            wire_a.closest_intersection(&wire_b, Metric::EuclideanSquared),
+            Some((Point::new(4, 3), 25))
+        );
+    }
+
+    fn parse(line: &str) -> Wire {
+        Wire::from_str(Point::new(0, 0), &line.split(',').collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_examples() {
+        for &(a, b, distance, delay) in &[
+            ("R8,U5,L5,D3", "U7,R6,D4,L4", 6, 30),
+            (
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83",
+                159,
+                610,
+            ),
+            (
+                "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+                "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+                135,
+                410,
+            ),
+        ] {
+            let (a, b) = (parse(a), parse(b));
+            let closest = a.closest_intersection(&b, Metric::Manhattan);
+            assert_eq!(closest.map(|(_, dist)| dist), Some(distance));
+            let shortest = a.intersections(&b).map(|(_, delay)| delay).min();
+            assert_eq!(shortest, Some(delay));
+        }
+    }
+
+    #[test]
+    fn test_segments_match_steps() {
+        let pairs = [
+            ("R8,U5,L5,D3", "U7,R6,D4,L4"),
+            // Overlapping in both directions and crossing the start again.
+            ("R5,U2,L7,D2,R3", "R3,L6,U4,D6,R1"),
+            // Diagonals crossing on and between points, and overlapping.
+            ("UR4,DR4,L8", "R1,UR1,UL1,R3,DR6"),
+            ("UR2,R2,DL5", "U3,DR3,UR1,DL2"),
+        ];
+        for &(a, b) in &pairs {
+            let (a, b) = (parse(a), parse(b));
+            let mut expected: HashMap<Point, usize> = HashMap::new();
+            for (point, delay) in a.step_intersections(&b) {
+                let min = expected.entry(point).or_insert(delay);
+                *min = (*min).min(delay);
+            }
+            let mut expected = expected.into_iter().collect::<Vec<_>>();
+            expected.sort_by_key(|(point, _)| (point.x, point.y));
+            assert_eq!(a.intersections(&b).collect::<Vec<_>>(), expected);
+        }
+        let overlap = parse("R5").intersections(&parse("R3")).collect::<Vec<_>>();
+        assert_eq!(
+            overlap,
+            vec![
+                (Point::new(1, 0), 2),
+                (Point::new(2, 0), 4),
+                (Point::new(3, 0), 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let wires = [parse("R8,U5,L5,D3"), parse("U7,R6,D4,L4")];
+        let intersections = wires[0].intersections(&wires[1]).collect::<Vec<_>>();
+        let image = svg(&wires, &intersections, &Point::new(3, 3), &Point::new(6, 5));
+        assert!(
+            image.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-1 -8 10 9">"#)
+        );
+        assert!(image.contains(r#"<polyline points="0,0 8,0 8,-5 3,-5 3,-2" "#));
+        assert!(image.contains(r#"<circle cx="3" cy="-3" r="3" fill="red"/>"#));
+        assert!(image.contains(r#"<circle cx="6" cy="-5" r="3" fill="blue"/>"#));
+        assert_eq!(image.matches("<circle").count(), 2);
+    }
+
+    #[test]
+    fn test_diagonals() {
+        // Crossing between points does not count.
+        assert_eq!(parse("UR3").intersections(&parse("R1,UL3")).count(), 0);
+        assert_eq!(
+            parse("UR4")
+                .intersections(&parse("R4,UL4"))
+                .collect::<Vec<_>>(),
+            vec![(Point::new(2, 2), 8)]
+        );
+        assert_eq!(
+            parse("U3,R4").closest_intersection(&parse("UR5"), Metric::Chebyshev),
+            Some((Point::new(3, 3), 3))
+        );
+    }
+
+    #[test]
+    fn test_many_wires() {
+        let wires = [parse("R8,U5,L5,D3"), parse("U7,R6,D4,L4"), parse("U3,R10")];
+        let pairs = pairwise_intersections(&wires);
+        assert_eq!(
+            pairs.iter().map(|(pair, _)| *pair).collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+        assert_eq!(pairs[0].1.len(), 2);
+        assert_eq!(
+            pairs[1].1,
+            vec![(Point::new(3, 3), 26), (Point::new(8, 3), 22)]
+        );
+        assert_eq!(
+            common_intersections(&wires),
+            Ok(vec![(Point::new(3, 3), 6 + 20 + 20)])
+        );
+        assert_eq!(
+            common_intersections(&wires[..2]),
+            Ok(wires[0].intersections(&wires[1]).collect())
+        );
+        assert_eq!(
+            common_intersections(&wires[..1]),
+            Err(Error::TooFewWires(1))
+        );
+    }
+
     #[test]
     fn test_from() {
         assert_eq!(
             Segment::try_from("R255").unwrap(),
             Segment::new(Direction::Right, 255)
         );
+        assert_eq!(
+            Segment::try_from("DL3").unwrap(),
+            Segment::new(Direction::DownLeft, 3)
+        );
+        assert!(Segment::try_from("LU3").is_err());
+        assert!(Segment::try_from("X3").is_err());
     }
 
     #[test]
@@ -1,60 +1,235 @@
-use anyhow::Result;
+use crate::prelude::*;
+use anyhow::anyhow;
 use regex::Regex;
-use std::fs::read_to_string;
-
-fn validate(num: u32) -> Option<u32> {
-    if !(num >= 100_000 && num <= 999_999) {
-        return None;
-    }
-    let mut last = num % 10;
-    let mut num = num;
-    let mut curr_cluster = 1;
-    let mut shortest_cluster = None;
-    while num > 0 {
-        num /= 10;
-        let curr = num % 10;
-        if curr > last {
-            return None;
-        } else if curr == last {
-            curr_cluster += 1;
+use std::ops::RangeInclusive;
+
+/// A condition a password candidate has to meet.
+///
+/// Rules are shared between the threads of a parallel scan.
+pub trait PasswordRule: Sync {
+    fn check(&self, password: u64) -> bool;
+}
+
+impl<F: Fn(u64) -> bool + Sync> PasswordRule for F {
+    fn check(&self, password: u64) -> bool {
+        self(password)
+    }
+}
+
+/// The lengths of the runs of equal digits, from the most significant one.
+fn clusters(password: u64) -> Vec<u32> {
+    let digits = password.to_string().into_bytes();
+    let mut clusters = Vec::new();
+    for (i, digit) in digits.iter().enumerate() {
+        if i > 0 && digits[i - 1] == *digit {
+            *clusters.last_mut().unwrap() += 1;
         } else {
-            if curr_cluster > 1 {
-                match shortest_cluster {
-                    None => shortest_cluster = Some(curr_cluster),
-                    Some(sc) if curr_cluster < sc => shortest_cluster = Some(curr_cluster),
-                    _ => (),
-                };
-            };
-            curr_cluster = 1;
+            clusters.push(1);
         }
+    }
+    clusters
+}
+
+/// Going from left to right, the digits never decrease.
+pub struct NonDecreasing;
+
+impl PasswordRule for NonDecreasing {
+    fn check(&self, password: u64) -> bool {
+        let digits = password.to_string().into_bytes();
+        digits.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+}
+
+/// Two adjacent digits are the same.
+pub struct HasPair;
+
+impl PasswordRule for HasPair {
+    fn check(&self, password: u64) -> bool {
+        clusters(password).iter().any(|&len| len >= 2)
+    }
+}
+
+/// Two adjacent digits are the same and not part of a larger group.
+pub struct HasExactPair;
 
-        last = curr;
+impl PasswordRule for HasExactPair {
+    fn check(&self, password: u64) -> bool {
+        clusters(password).contains(&2)
     }
-    Some(shortest_cluster.unwrap_or(1))
+}
+
+/// The password lies within the range.
+pub struct InRange(pub RangeInclusive<u64>);
+
+impl PasswordRule for InRange {
+    fn check(&self, password: u64) -> bool {
+        self.0.contains(&password)
+    }
+}
+
+/// A candidate passes a rule set if it meets every rule in it.
+#[derive(Default)]
+pub struct Rules(Vec<Box<dyn PasswordRule>>);
+
+impl Rules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, rule: impl PasswordRule + 'static) -> Self {
+        self.0.push(Box::new(rule));
+        self
+    }
+}
+
+impl PasswordRule for Rules {
+    fn check(&self, password: u64) -> bool {
+        self.0.iter().all(|rule| rule.check(password))
+    }
+}
+
+/// The rules of the puzzle, with `pair` being the adjacency rule of the part.
+pub fn rules(low: u64, high: u64, pair: impl PasswordRule + 'static) -> Rules {
+    Rules::new()
+        .with(InRange(low..=high))
+        .with(NonDecreasing)
+        .with(pair)
+}
+
+fn width(num: u64) -> u32 {
+    num.to_string().len() as u32
+}
+
+/// The numbers in `low..=high` whose digits never decrease, built digit by
+/// digit instead of testing every number in the range.
+///
+/// There are only 3003 such numbers with six digits and about 3 million
+/// with twenty, and the bounds prune whole prefixes.
+fn non_decreasing(low: u64, high: u64) -> Vec<u64> {
+    fn extend(prefix: u64, remaining: u32, low: u64, high: u64, found: &mut Vec<u64>) {
+        if remaining == 0 {
+            found.push(prefix);
+            return;
+        }
+        // Only a lone digit may be a zero.
+        let first = if prefix == 0 && remaining > 1 {
+            1
+        } else {
+            prefix % 10
+        };
+        // Completions of twenty digits can overflow a u64.
+        let scale = 10u128.pow(remaining - 1);
+        for digit in first..=9 {
+            let next = u128::from(prefix) * 10 + u128::from(digit);
+            // Repeating the digit gives the smallest completion, nines the largest.
+            let smallest = next * scale + u128::from(digit) * (scale - 1) / 9;
+            let largest = (next + 1) * scale - 1;
+            if smallest > u128::from(high) {
+                break;
+            }
+            if largest >= u128::from(low) {
+                extend(next as u64, remaining - 1, low, high, found);
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    if low <= high {
+        for digits in width(low)..=width(high) {
+            extend(0, digits, low, high, &mut found);
+        }
+    }
+    found
+}
+
+/// Counts the candidates in `low..=high` that pass `rules`.
+pub fn count(low: u64, high: u64, rules: &Rules) -> usize {
+    non_decreasing(low, high)
+        .into_iter()
+        .filter(|&password| rules.check(password))
+        .count()
+}
+
+/// Counts the passwords by testing every number in `low..=high`.
+pub fn scan(low: u64, high: u64, rules: &Rules) -> usize {
+    (low..=high)
+        .filter(|&password| rules.check(password))
+        .count()
+}
+
+/// Splits `low..=high` into at most `parts` ranges whose sizes differ by at
+/// most one.
+#[cfg(feature = "parallel")]
+fn chunks(low: u64, high: u64, parts: u64) -> Vec<(u64, u64)> {
+    let span = high - low;
+    let (base, extra) = (span / parts, span % parts);
+    let mut chunks = Vec::new();
+    let mut start = low;
+    for i in 0..parts {
+        // `span + 1` numbers in all, so the first `extra + 1` chunks get
+        // `base + 1` of them and the rest `base`.
+        let end = if i <= extra {
+            start + base
+        } else if base > 0 {
+            start + base - 1
+        } else {
+            break;
+        };
+        chunks.push((start, end));
+        match end.checked_add(1) {
+            Some(next) if next <= high => start = next,
+            _ => break,
+        }
+    }
+    chunks
+}
+
+/// Like `scan`, but splits the range into a chunk for each of `threads` and
+/// counts the chunks on the rayon thread pool.
+#[cfg(feature = "parallel")]
+pub fn scan_parallel(low: u64, high: u64, rules: &Rules, threads: u32) -> usize {
+    use rayon::prelude::*;
+    if low > high {
+        return 0;
+    }
+    chunks(low, high, u64::from(threads.max(1)))
+        .into_par_iter()
+        .map(|(start, end)| scan(start, end, rules))
+        .sum()
+}
+
+/// Like `count`, but checks the candidates on the rayon thread pool.
+#[cfg(feature = "parallel")]
+pub fn count_parallel(low: u64, high: u64, rules: &Rules) -> usize {
+    use rayon::prelude::*;
+    non_decreasing(low, high)
+        .into_par_iter()
+        .filter(|&password| rules.check(password))
+        .count()
+}
+
+/// Counts the passwords for `main`, checking the enumerated candidates on
+/// every thread with the `parallel` feature.
+#[cfg(feature = "parallel")]
+fn solve(low: u64, high: u64, rules: &Rules) -> usize {
+    count_parallel(low, high, rules)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn solve(low: u64, high: u64, rules: &Rules) -> usize {
+    count(low, high, rules)
 }
 
 pub fn main() -> Result<()> {
-    let input = read_to_string("data/day04.txt")?;
-    let matcher = Regex::new(r"^(\d{6})-(\d{6})$")?;
-    let captures = matcher.captures(input.trim()).unwrap();
+    let input = input::read(4)?;
+    let matcher = Regex::new(r"^(\d+)-(\d+)$")?;
+    let captures = matcher
+        .captures(input.trim())
+        .ok_or_else(|| anyhow!("Invalid range {:?}", input.trim()))?;
     let low = captures.get(1).unwrap().as_str().parse()?;
     let high = captures.get(2).unwrap().as_str().parse()?;
-    let count = (low..=high)
-        .filter(|num| {
-            validate(*num)
-                .and_then(|val| if val > 1 { Some(val) } else { None })
-                .is_some()
-        })
-        .count();
-    println!("Part 1: {}", count);
-    let count = (low..=high)
-        .filter(|num| {
-            validate(*num)
-                .and_then(|val| if val == 2 { Some(val) } else { None })
-                .is_some()
-        })
-        .count();
-    println!("Part 2: {}", count);
+    answer(1, solve(low, high, &rules(low, high, HasPair)));
+    answer(2, solve(low, high, &rules(low, high, HasExactPair)));
     Ok(())
 }
 
@@ -63,15 +238,79 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_validate() {
-        assert_eq!(validate(122456).unwrap(), 2);
-        assert_eq!(validate(123456).unwrap(), 1);
-
-        assert_eq!(validate(111111).unwrap(), 6);
-        assert!(validate(223450).is_none());
-        assert_eq!(validate(123789).unwrap(), 1);
-        assert!(validate(359288).is_none());
-        assert_eq!(validate(111122).unwrap(), 2);
+    fn test_rules() {
+        let part1 = rules(100_000, 999_999, HasPair);
+        let part2 = rules(100_000, 999_999, HasExactPair);
+        assert!(part1.check(111_111));
+        assert!(!part1.check(223_450));
+        assert!(!part1.check(123_789));
+        assert!(part2.check(112_233));
+        assert!(!part2.check(123_444));
+        assert!(part2.check(111_122));
+        assert!(!rules(200_000, 300_000, HasPair).check(111_111));
+        assert!(!part1.check(11_111));
+        assert!(rules(1, 99, HasPair).check(55));
+        assert!(rules(0, u64::MAX, HasExactPair).check(12_345_678_899));
+
+        let custom = part2.with(|password: u64| !password.to_string().ends_with('3'));
+        assert!(custom.check(112_234));
+        assert!(!custom.check(112_233));
+    }
+
+    #[test]
+    fn test_enumeration() {
+        for &(low, high) in &[(359_282, 420_401), (100_000, 999_999), (0, 150_000)] {
+            let scanned = (low..=high)
+                .filter(|&num| NonDecreasing.check(num))
+                .collect::<Vec<_>>();
+            assert_eq!(non_decreasing(low, high), scanned);
+        }
+        assert_eq!(non_decreasing(100_000, 999_999).len(), 3003);
+        assert_eq!(non_decreasing(123_456, 123_456), vec![123_456]);
+        assert!(non_decreasing(654_321, 123_456).is_empty());
+        // Twelve digits, choosing with repetition from the nine non-zero ones.
+        assert_eq!(
+            non_decreasing(10u64.pow(11), 10u64.pow(12) - 1).len(),
+            125_970
+        );
+        let widest = non_decreasing(10u64.pow(19), u64::MAX);
+        assert_eq!(widest.first(), Some(&11_111_111_111_111_111_111));
+        assert_eq!(widest.last(), Some(&17_999_999_999_999_999_999));
+    }
+
+    #[test]
+    fn test_scan() {
+        let rules = rules(359_282, 420_401, HasExactPair);
+        assert_eq!(
+            scan(359_282, 420_401, &rules),
+            count(359_282, 420_401, &rules)
+        );
+        assert_eq!(scan(420_401, 359_282, &rules), 0);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_scan_parallel() {
+        let rules = rules(100_000, 999_999, HasPair);
+        let serial = scan(100_000, 999_999, &rules);
+        for threads in 0..=7 {
+            assert_eq!(scan_parallel(100_000, 999_999, &rules, threads), serial);
+        }
+        assert_eq!(scan_parallel(5, 5, &rules, 4), 0);
+        assert_eq!(scan_parallel(6, 5, &rules, 4), 0);
+        assert_eq!(scan_parallel(u64::MAX - 2, u64::MAX, &Rules::new(), 8), 3);
+        assert_eq!(chunks(0, u64::MAX, 1), vec![(0, u64::MAX)]);
+        assert_eq!(chunks(0, 9, 3), vec![(0, 3), (4, 6), (7, 9)]);
+        assert_eq!(chunks(5, 6, 4), vec![(5, 5), (6, 6)]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_count_parallel() {
+        for &(low, high) in &[(100_000, 999_999), (0, 99_999_999_999)] {
+            let rules = rules(low, high, HasExactPair);
+            assert_eq!(count_parallel(low, high, &rules), count(low, high, &rules));
+        }
     }
 
     #[test]
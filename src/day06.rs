@@ -1,6 +1,4 @@
-use std::fs::read_to_string;
-
-use anyhow::Result;
+use crate::prelude::*;
 use ego_tree::{NodeId, NodeMut, NodeRef, Tree};
 use nom::bytes::complete::tag;
 use nom::character::complete::{alphanumeric1, line_ending};
@@ -27,7 +25,7 @@ impl<'s> Body<'s> {
     }
 }
 
-fn parse(input: &str) -> IResult<&str, Vec<Orbit>> {
+fn parse(input: &str) -> IResult<&str, Vec<Orbit<'_>>> {
     let pair = separated_pair(alphanumeric1, tag(")"), alphanumeric1);
     let map = map(pair, |(object, satellite)| Orbit { object, satellite });
     let parser = separated_list(line_ending, map);
@@ -48,13 +46,14 @@ fn build_subtree<'n, 'b: 'n>(
     }
 }
 
-struct OrbitTree<'s> {
+/// The orbit map: every body and the body it orbits.
+pub struct OrbitTree<'s> {
     tree: Tree<Body<'s>>,
     node_ids: HashMap<&'s str, NodeId>,
 }
 
 impl<'s> OrbitTree<'s> {
-    fn build(input: &'s str) -> Result<Self> {
+    pub fn build(input: &'s str) -> Result<Self> {
         let result = parse(input.trim()).map_err(|_| ::anyhow::anyhow!("Parse failed"))?;
         assert_eq!(result.0.len(), 0);
         let mut satellites: HashMap<&str, Vec<&str>> = HashMap::new();
@@ -62,7 +61,7 @@ impl<'s> OrbitTree<'s> {
         result.1.iter().for_each(|orbit| {
             satellites
                 .entry(orbit.object)
-                .or_insert_with(|| vec![])
+                .or_default()
                 .push(orbit.satellite)
         });
         let mut tree = Tree::new(Body::new("COM"));
@@ -82,28 +81,31 @@ impl<'s> OrbitTree<'s> {
         children + depth
     }
 
-    fn total_orbits(&self) -> u64 {
+    pub fn total_orbits(&self) -> u64 {
         Self::count(&self.tree.root(), 0)
     }
 
-    fn distance(&self, a: &str, b: &str) -> Result<usize> {
-        let parents = |node| -> Result<_> {
-            Ok(self
-                .tree
-                .get(
-                    *self
-                        .node_ids
-                        .get(node)
-                        .ok_or_else(|| ::anyhow::anyhow!("Node not found"))?,
-                )
-                .ok_or_else(|| ::anyhow::anyhow!("Node not found"))?
-                .ancestors())
-        };
-        let parent_dist = parents(a)?
+    fn parents(&self, node: &str) -> Result<impl Iterator<Item = NodeRef<'_, Body<'s>>>> {
+        Ok(self
+            .tree
+            .get(
+                *self
+                    .node_ids
+                    .get(node)
+                    .ok_or_else(|| ::anyhow::anyhow!("Node not found"))?,
+            )
+            .ok_or_else(|| ::anyhow::anyhow!("Node not found"))?
+            .ancestors())
+    }
+
+    pub fn distance(&self, a: &str, b: &str) -> Result<usize> {
+        let parent_dist = self
+            .parents(a)?
             .enumerate()
             .map(|(dist, node)| (node.value().name, dist))
             .collect::<HashMap<_, _>>();
-        let dist = parents(b)?
+        let dist = self
+            .parents(b)?
             .enumerate()
             .filter_map(|(dist2, node)| {
                 parent_dist
@@ -111,18 +113,41 @@ impl<'s> OrbitTree<'s> {
                     .map(|dist1| dist1 + dist2)
             })
             .next()
-            .map(|dist| dist)
             .ok_or_else(|| ::anyhow::anyhow!("Nodes don't have the same root"))?;
 
         Ok(dist)
     }
+
+    /// The bodies orbited along the way from the body `a` orbits to the one `b` orbits.
+    pub fn transfer_path(&self, a: &str, b: &str) -> Result<Vec<&'s str>> {
+        let b_parents = self
+            .parents(b)?
+            .map(|node| node.value().name)
+            .collect::<Vec<_>>();
+        let mut path = Vec::new();
+        for name in self.parents(a)?.map(|node| node.value().name) {
+            path.push(name);
+            if let Some(pos) = b_parents.iter().position(|&other| other == name) {
+                path.extend(b_parents[..pos].iter().rev());
+                return Ok(path);
+            }
+        }
+        Err(::anyhow::anyhow!("Nodes don't have the same root"))
+    }
 }
 
 pub fn main() -> Result<()> {
-    let input = read_to_string("data/day06.txt")?;
+    let input = input::read(6)?;
     let tree = OrbitTree::build(&input)?;
-    println!("Part 1: {}", tree.total_orbits());
-    println!("Part 2: {}", tree.distance("YOU", "SAN")?);
+    answer(1, tree.total_orbits());
+    explain::emit(Event::Bodies(tree.node_ids.len() - 1));
+    answer(2, tree.distance("YOU", "SAN")?);
+    explain::emit(Event::TransferPath(
+        tree.transfer_path("YOU", "SAN")?
+            .into_iter()
+            .map(str::to_owned)
+            .collect(),
+    ));
     Ok(())
 }
 
@@ -150,7 +175,7 @@ E)J
 J)K
 K)L
 ";
-        assert_eq!(OrbitTree::build(&input)?.total_orbits(), 42);
+        assert_eq!(OrbitTree::build(input)?.total_orbits(), 42);
         Ok(())
     }
 
@@ -171,7 +196,12 @@ K)L
 K)YOU
 I)SAN
 ";
-        assert_eq!(OrbitTree::build(&input)?.distance("YOU", "SAN")?, 4);
+        let tree = OrbitTree::build(input)?;
+        assert_eq!(tree.distance("YOU", "SAN")?, 4);
+        assert_eq!(
+            tree.transfer_path("YOU", "SAN")?,
+            vec!["K", "J", "E", "D", "I"]
+        );
         Ok(())
     }
 }
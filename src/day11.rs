@@ -0,0 +1,165 @@
+use crate::prelude::*;
+use crate::runner::{self, Picture};
+use crate::vm::types::Value;
+use crate::vm::{Memory, State};
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Colour {
+    Black,
+    White,
+}
+
+/// Moves over the hull as told by its brain, painting panels on the way.
+///
+/// Uses screen coordinates, so the robot starts facing `(0, -1)`.
+struct Robot {
+    hull: HashMap<Point, Colour>,
+    position: Point,
+    heading: Vector,
+}
+
+impl Robot {
+    fn new(start: Colour) -> Self {
+        let position = Point::origin();
+        let mut hull = HashMap::new();
+        if start == Colour::White {
+            hull.insert(position, start);
+        }
+        Self {
+            hull,
+            position,
+            heading: Vector::new(0, -1),
+        }
+    }
+
+    fn colour(&self) -> Colour {
+        self.hull
+            .get(&self.position)
+            .copied()
+            .unwrap_or(Colour::Black)
+    }
+
+    fn apply(&mut self, colour: Value, turn: Value) -> Result<()> {
+        let colour = match colour {
+            0 => Colour::Black,
+            1 => Colour::White,
+            _ => return Err(anyhow!("Invalid colour {}", colour)),
+        };
+        self.hull.insert(self.position, colour);
+        let (x, y) = (self.heading.x, self.heading.y);
+        self.heading = match turn {
+            0 => Vector::new(y, -x),
+            1 => Vector::new(-y, x),
+            _ => return Err(anyhow!("Invalid turn {}", turn)),
+        };
+        self.position += self.heading;
+        Ok(())
+    }
+
+    /// Feeds the colour below the robot to `brain` and follows its
+    /// instructions until it halts.
+    fn run<M: Memory<Value = Value>>(&mut self, brain: &mut Computer<M>) -> Result<()> {
+        loop {
+            brain.push_input(match self.colour() {
+                Colour::Black => 0,
+                Colour::White => 1,
+            });
+            let state = brain.run_until_blocked();
+            for pair in brain.take_outputs().chunks(2) {
+                match *pair {
+                    [colour, turn] => self.apply(colour, turn)?,
+                    _ => return Err(anyhow!("Brain output an incomplete instruction")),
+                }
+            }
+            match state {
+                State::Halted => return Ok(()),
+                State::NeedsInput => (),
+                State::Faulted(err) => return Err(err.into()),
+                state => return Err(anyhow!("Brain stopped with {:?}", state)),
+            }
+        }
+    }
+
+    /// The white panels as rows of `#` on `.`.
+    fn render(&self) -> String {
+        let white = self
+            .hull
+            .iter()
+            .filter(|(_, &colour)| colour == Colour::White)
+            .map(|(point, _)| point)
+            .collect::<Vec<_>>();
+        let min_x = white.iter().map(|p| p.x).min().unwrap_or(0);
+        let max_x = white.iter().map(|p| p.x).max().unwrap_or(-1);
+        let min_y = white.iter().map(|p| p.y).min().unwrap_or(0);
+        let max_y = white.iter().map(|p| p.y).max().unwrap_or(-1);
+        (min_y..=max_y)
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| match self.hull.get(&Point::new(x, y)) {
+                        Some(Colour::White) => '#',
+                        _ => '.',
+                    })
+                    .chain(Some('\n'))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+}
+
+fn paint(program: &Program, start: Colour) -> Result<Robot> {
+    let mut robot = Robot::new(start);
+    robot.run(&mut program.clone().into_growable_computer())?;
+    Ok(robot)
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(11)?;
+    let program = input.parse()?;
+    let robot = paint(&program, Colour::Black)?;
+    answer(1, robot.hull.len());
+    let robot = paint(&program, Colour::White)?;
+    let registration = robot.render();
+    runner::visualize(
+        "Registration identifier",
+        Picture::Text(registration.clone()),
+    );
+    answer(2, registration);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A brain that ignores the colours and answers with `moves`.
+    fn scripted(moves: &[(Value, Value)]) -> Program {
+        let cell = moves.len() as Value * 6 + 1;
+        let mut program = Vec::new();
+        for &(colour, turn) in moves {
+            program.extend_from_slice(&[3, cell, 104, colour, 104, turn]);
+        }
+        program.extend_from_slice(&[99, 0]);
+        Program::new(program)
+    }
+
+    #[test]
+    fn test_example() -> Result<()> {
+        let brain = scripted(&[(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)]);
+        let robot = paint(&brain, Colour::Black)?;
+        assert_eq!(robot.hull.len(), 6);
+        assert_eq!(robot.position, Point::new(0, -1));
+        assert_eq!(robot.heading, Vector::new(-1, 0));
+        assert_eq!(robot.render(), "..#\n..#\n##.\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_white_start() -> Result<()> {
+        let robot = paint(&scripted(&[]), Colour::White)?;
+        assert_eq!(robot.render(), "#\n");
+        assert!(paint(&scripted(&[(2, 0)]), Colour::Black).is_err());
+        Ok(())
+    }
+}
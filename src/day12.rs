@@ -0,0 +1,155 @@
+use crate::prelude::*;
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use num::Integer;
+use regex::Regex;
+use std::str::FromStr;
+
+type Value = i64;
+
+lazy_static! {
+    static ref MATCHER: Regex = Regex::new(r"^<x=(-?\d+), y=(-?\d+), z=(-?\d+)>$").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Moon {
+    position: [Value; 3],
+    velocity: [Value; 3],
+}
+
+impl Moon {
+    fn energy(&self) -> Value {
+        let sum = |values: &[Value; 3]| values.iter().map(|v| v.abs()).sum::<Value>();
+        sum(&self.position) * sum(&self.velocity)
+    }
+}
+
+impl FromStr for Moon {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let captures = MATCHER
+            .captures(line.trim())
+            .ok_or_else(|| anyhow!("Invalid moon {:?}", line))?;
+        let mut position = [0; 3];
+        for (axis, value) in position.iter_mut().enumerate() {
+            *value = captures[axis + 1].parse()?;
+        }
+        Ok(Self {
+            position,
+            velocity: [0; 3],
+        })
+    }
+}
+
+/// Applies gravity and then velocity along one axis.
+///
+/// The axes do not influence each other, so they can be simulated on
+/// their own.
+fn step_axis(moons: &mut [Moon], axis: usize) {
+    for i in 0..moons.len() {
+        for j in i + 1..moons.len() {
+            let pull = (moons[j].position[axis] - moons[i].position[axis]).signum();
+            moons[i].velocity[axis] += pull;
+            moons[j].velocity[axis] -= pull;
+        }
+    }
+    for moon in moons {
+        moon.position[axis] += moon.velocity[axis];
+    }
+}
+
+fn step(moons: &mut [Moon]) {
+    for axis in 0..3 {
+        step_axis(moons, axis);
+    }
+}
+
+fn total_energy(moons: &[Moon], steps: usize) -> Value {
+    let mut moons = moons.to_vec();
+    for _ in 0..steps {
+        step(&mut moons);
+    }
+    moons.iter().map(Moon::energy).sum()
+}
+
+/// Steps until the moons are back in their initial state.
+///
+/// Every step can be undone, so the first repeated state is the initial one.
+/// Each axis repeats on its own period and the whole system after their
+/// least common multiple.
+fn cycle_length(moons: &[Moon]) -> u64 {
+    (0..3)
+        .map(|axis| {
+            let mut current = moons.to_vec();
+            let mut steps = 0u64;
+            loop {
+                step_axis(&mut current, axis);
+                steps += 1;
+                if current == moons {
+                    return steps;
+                }
+            }
+        })
+        .fold(1, |acc, period| acc.lcm(&period))
+}
+
+fn parse(input: &str) -> Result<Vec<Moon>> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(12)?;
+    let moons = parse(&input)?;
+    answer(1, total_energy(&moons, 1000));
+    answer(2, cycle_length(&moons));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE1: &str = "<x=-1, y=0, z=2>
+<x=2, y=-10, z=-7>
+<x=4, y=-8, z=8>
+<x=3, y=5, z=-1>";
+
+    const EXAMPLE2: &str = "<x=-8, y=-10, z=0>
+<x=5, y=5, z=10>
+<x=2, y=-7, z=3>
+<x=9, y=-8, z=-3>";
+
+    #[test]
+    fn test_part1() -> Result<()> {
+        let moons = parse(EXAMPLE1)?;
+        let mut stepped = moons.clone();
+        step(&mut stepped);
+        assert_eq!(
+            stepped[0],
+            Moon {
+                position: [2, -1, 1],
+                velocity: [3, -1, -1],
+            }
+        );
+        assert_eq!(total_energy(&moons, 10), 179);
+        assert_eq!(total_energy(&parse(EXAMPLE2)?, 100), 1940);
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> Result<()> {
+        assert_eq!(cycle_length(&parse(EXAMPLE1)?), 2772);
+        assert_eq!(cycle_length(&parse(EXAMPLE2)?), 4_686_774_924);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("<x=1, y=2>".parse::<Moon>().is_err());
+    }
+}
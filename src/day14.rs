@@ -0,0 +1,257 @@
+use crate::prelude::*;
+use crate::util::search::max_satisfying;
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+const ORE: &str = "ORE";
+const FUEL: &str = "FUEL";
+
+#[derive(Debug)]
+struct Reaction<'s> {
+    output: u64,
+    inputs: Vec<(u64, &'s str)>,
+}
+
+/// The reactions of the nanofactory, ordered so that every chemical is
+/// produced before the reactions consuming it.
+pub struct ReactionGraph<'s> {
+    reactions: HashMap<&'s str, Reaction<'s>>,
+    /// Every chemical after all chemicals whose reactions consume it.
+    order: Vec<&'s str>,
+}
+
+fn parse_amount(text: &str) -> Result<(u64, &str)> {
+    let mut parts = text.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(amount), Some(chemical), None) => Ok((amount.parse()?, chemical)),
+        _ => Err(anyhow!("Invalid amount {:?}", text)),
+    }
+}
+
+impl<'s> ReactionGraph<'s> {
+    pub fn parse(input: &'s str) -> Result<Self> {
+        let mut reactions = HashMap::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let mut sides = line.split("=>");
+            let (inputs, output) = match (sides.next(), sides.next(), sides.next()) {
+                (Some(inputs), Some(output), None) => (inputs, output),
+                _ => return Err(anyhow!("Invalid reaction {:?}", line)),
+            };
+            let (output, chemical) = parse_amount(output)?;
+            let inputs = inputs.split(',').map(parse_amount).collect::<Result<_>>()?;
+            if reactions
+                .insert(chemical, Reaction { output, inputs })
+                .is_some()
+            {
+                return Err(anyhow!("More than one reaction produces {}", chemical));
+            }
+        }
+        let order = Self::sort(&reactions)?;
+        Ok(Self { reactions, order })
+    }
+
+    /// Number of reactions.
+    pub fn len(&self) -> usize {
+        self.reactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reactions.is_empty()
+    }
+
+    /// Orders the chemicals from FUEL down to ORE by a depth-first search.
+    fn sort(reactions: &HashMap<&'s str, Reaction<'s>>) -> Result<Vec<&'s str>> {
+        fn visit<'s>(
+            chemical: &'s str,
+            reactions: &HashMap<&'s str, Reaction<'s>>,
+            visiting: &mut Vec<&'s str>,
+            order: &mut Vec<&'s str>,
+        ) -> Result<()> {
+            if order.contains(&chemical) {
+                return Ok(());
+            }
+            if visiting.contains(&chemical) {
+                return Err(anyhow!("{} is needed to produce itself", chemical));
+            }
+            visiting.push(chemical);
+            if chemical != ORE {
+                let reaction = reactions
+                    .get(chemical)
+                    .ok_or_else(|| anyhow!("No reaction produces {}", chemical))?;
+                for &(_, input) in &reaction.inputs {
+                    visit(input, reactions, visiting, order)?;
+                }
+            }
+            visiting.pop();
+            order.push(chemical);
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        visit(FUEL, reactions, &mut Vec::new(), &mut order)?;
+        order.reverse();
+        Ok(order)
+    }
+
+    /// The ORE needed for `fuel` FUEL and the chemicals left over.
+    ///
+    /// Going through the chemicals in topological order means all of the
+    /// need for one is known before its reaction runs, so every reaction
+    /// runs once with the fewest batches. Returns `None` if an amount does
+    /// not fit into a `u64`.
+    pub fn ore_for(&self, fuel: u64) -> Option<(u64, HashMap<&'s str, u64>)> {
+        let mut needed = HashMap::new();
+        let mut leftovers = HashMap::new();
+        needed.insert(FUEL, fuel);
+        for &chemical in &self.order {
+            let amount = needed.get(chemical).copied().unwrap_or(0);
+            let reaction = match self.reactions.get(chemical) {
+                Some(reaction) => reaction,
+                None => continue,
+            };
+            let batches = amount.div_ceil(reaction.output);
+            let left = batches.checked_mul(reaction.output)? - amount;
+            if left > 0 {
+                leftovers.insert(chemical, left);
+            }
+            for &(input_amount, input) in &reaction.inputs {
+                let total = needed.entry(input).or_insert(0);
+                *total = batches
+                    .checked_mul(input_amount)
+                    .and_then(|more| total.checked_add(more))?;
+            }
+        }
+        Some((needed.get(ORE).copied().unwrap_or(0), leftovers))
+    }
+
+    /// The chemicals left over after producing `fuel` FUEL.
+    pub fn leftovers(&self, fuel: u64) -> Option<HashMap<&'s str, u64>> {
+        self.ore_for(fuel).map(|(_, leftovers)| leftovers)
+    }
+
+    /// The most FUEL that can be produced from `ore` ORE.
+    pub fn max_fuel(&self, ore: u64) -> u64 {
+        max_satisfying(|fuel| matches!(self.ore_for(fuel), Some((needed, _)) if needed <= ore))
+    }
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(14)?;
+    let graph = ReactionGraph::parse(&input)?;
+    let (ore, leftovers) = graph
+        .ore_for(1)
+        .ok_or_else(|| anyhow!("One FUEL needs more ORE than fits a u64"))?;
+    answer(1, ore);
+    explain::emit(Event::Leftovers {
+        reactions: graph.len(),
+        chemicals: leftovers.len(),
+    });
+    answer(2, graph.max_fuel(1_000_000_000_000));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE1: &str = "10 ORE => 10 A
+1 ORE => 1 B
+7 A, 1 B => 1 C
+7 A, 1 C => 1 D
+7 A, 1 D => 1 E
+7 A, 1 E => 1 FUEL";
+
+    const EXAMPLE2: &str = "9 ORE => 2 A
+8 ORE => 3 B
+7 ORE => 5 C
+3 A, 4 B => 1 AB
+5 B, 7 C => 1 BC
+4 C, 1 A => 1 CA
+2 AB, 3 BC, 4 CA => 1 FUEL";
+
+    const EXAMPLE3: &str = "157 ORE => 5 NZVS
+165 ORE => 6 DCFZ
+44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
+12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ
+179 ORE => 7 PSHF
+177 ORE => 5 HKGWZ
+7 DCFZ, 7 PSHF => 2 XJWVT
+165 ORE => 2 GPVTF
+3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
+
+    const EXAMPLE4: &str = "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG
+17 NVRVD, 3 JNWZP => 8 VPVL
+53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL
+22 VJHF, 37 MNCFX => 5 FWMGM
+139 ORE => 4 NVRVD
+144 ORE => 7 JNWZP
+5 MNCFX, 7 RFSQX, 2 FWMGM, 2 VPVL, 19 CXFTF => 3 HVMC
+5 VJHF, 7 MNCFX, 9 VPVL, 37 CXFTF => 6 GNMV
+145 ORE => 6 MNCFX
+1 NVRVD => 8 CXFTF
+1 VJHF, 6 MNCFX => 4 RFSQX
+176 ORE => 6 VJHF";
+
+    const EXAMPLE5: &str = "171 ORE => 8 CNZTR
+7 ZLQW, 3 BMBT, 9 XCVML, 26 XMNCP, 1 WPTQ, 2 MZWV, 1 RJRHP => 4 PLWSL
+114 ORE => 4 BHXH
+14 VRPVC => 6 BMBT
+6 BHXH, 18 KTJDG, 12 WPTQ, 7 PLWSL, 31 FHTLT, 37 ZDVW => 1 FUEL
+6 WPTQ, 2 BMBT, 8 ZLQW, 18 KTJDG, 1 XMNCP, 6 MZWV, 1 RJRHP => 6 FHTLT
+15 XDBXC, 2 LTCX, 1 VRPVC => 6 ZLQW
+13 WPTQ, 10 LTCX, 3 RJRHP, 14 XMNCP, 2 MZWV, 1 ZLQW => 1 ZDVW
+5 BMBT => 4 WPTQ
+189 ORE => 9 KTJDG
+1 MZWV, 17 XDBXC, 3 XCVML => 2 XMNCP
+12 VRPVC, 27 CNZTR => 2 XDBXC
+15 KTJDG, 12 BHXH => 5 XCVML
+3 BHXH, 2 VRPVC => 7 MZWV
+121 ORE => 7 VRPVC
+7 XCVML => 6 RJRHP
+5 BHXH, 4 VRPVC => 5 LTCX";
+
+    #[test]
+    fn test_part1() -> Result<()> {
+        let graph = ReactionGraph::parse(EXAMPLE1)?;
+        let (ore, leftovers) = graph.ore_for(1).unwrap();
+        assert_eq!(ore, 31);
+        assert_eq!(leftovers.get("A"), Some(&2));
+        assert_eq!(graph.leftovers(1), Some(leftovers));
+        assert_eq!(graph.len(), 6);
+        for &(example, expected) in &[
+            (EXAMPLE2, 165),
+            (EXAMPLE3, 13312),
+            (EXAMPLE4, 180_697),
+            (EXAMPLE5, 2_210_736),
+        ] {
+            assert_eq!(
+                ReactionGraph::parse(example)?.ore_for(1).unwrap().0,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> Result<()> {
+        for &(example, expected) in &[
+            (EXAMPLE3, 82_892_753),
+            (EXAMPLE4, 5_586_022),
+            (EXAMPLE5, 460_664),
+        ] {
+            let graph = ReactionGraph::parse(example)?;
+            assert_eq!(graph.max_fuel(1_000_000_000_000), expected);
+        }
+        let graph = ReactionGraph::parse("2 ORE => 1 FUEL")?;
+        assert_eq!(graph.max_fuel(u64::MAX), u64::MAX / 2);
+        assert_eq!(graph.ore_for(u64::MAX), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(ReactionGraph::parse("1 A => 1 FUEL").is_err());
+        assert!(ReactionGraph::parse("1 FUEL => 1 FUEL").is_err());
+        assert!(ReactionGraph::parse("1 ORE -> 1 FUEL").is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::output;
+use crate::prelude::*;
+use crate::util::search::{dijkstra, Graph};
+use crate::vm::types::Value;
+use crate::vm::Memory;
+use anyhow::anyhow;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+
+/// The droid's movement commands: north, south, west and east.
+fn moves() -> [(Value, Vector); 4] {
+    [
+        (1, Vector::new(0, -1)),
+        (2, Vector::new(0, 1)),
+        (3, Vector::new(-1, 0)),
+        (4, Vector::new(1, 0)),
+    ]
+}
+
+/// The part of the ship the droid can reach, relative to where it started.
+#[derive(Debug, PartialEq)]
+pub struct Ship {
+    open: HashSet<Point>,
+    oxygen: Point,
+}
+
+impl Graph for Ship {
+    type Node = Point;
+
+    fn neighbors(&self, node: &Point) -> Vec<(Point, usize)> {
+        moves()
+            .iter()
+            .map(|(_, offset)| node + offset)
+            .filter(|point| self.open.contains(point))
+            .map(|point| (point, 1))
+            .collect()
+    }
+}
+
+impl Ship {
+    /// Maps the ship by a breadth-first search with the droid.
+    ///
+    /// Every reached position keeps a fork of the droid standing there, so
+    /// the droid never has to walk back.
+    pub fn explore<M: Memory<Value = Value> + Clone>(droid: Computer<M>) -> Result<Self> {
+        let origin = Point::origin();
+        let mut open = HashSet::new();
+        let mut walls = HashSet::new();
+        let mut oxygen = None;
+        open.insert(origin);
+        let mut queue = VecDeque::new();
+        queue.push_back((origin, droid));
+        while let Some((position, droid)) = queue.pop_front() {
+            for &(command, offset) in &moves() {
+                let next = position + offset;
+                if open.contains(&next) || walls.contains(&next) {
+                    continue;
+                }
+                let mut forked = droid.fork();
+                forked.push_input(command);
+                match forked.run_until_output()? {
+                    Some(0) => {
+                        walls.insert(next);
+                        continue;
+                    }
+                    Some(1) => (),
+                    Some(2) => oxygen = Some(next),
+                    Some(status) => return Err(anyhow!("Invalid status {}", status)),
+                    None => return Err(anyhow!("Droid halted")),
+                }
+                open.insert(next);
+                queue.push_back((next, forked));
+            }
+        }
+        let oxygen = oxygen.ok_or_else(|| anyhow!("Oxygen system not found"))?;
+        Ok(Self { open, oxygen })
+    }
+
+    /// Reads a map written by [`Ship::render`].
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut open = HashSet::new();
+        let mut start = None;
+        let mut oxygen = None;
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let point = Point::new(x as Value, y as Value);
+                match c {
+                    '#' => continue,
+                    '.' => (),
+                    'D' => start = Some(point),
+                    'O' => oxygen = Some(point),
+                    _ => return Err(anyhow!("Invalid tile {:?}", c)),
+                }
+                open.insert(point);
+            }
+        }
+        let start = start.ok_or_else(|| anyhow!("No droid on the map"))?;
+        let oxygen = oxygen.ok_or_else(|| anyhow!("Oxygen system not found"))?;
+        let origin = Point::origin();
+        Ok(Self {
+            open: open.iter().map(|point| origin + (point - start)).collect(),
+            oxygen: origin + (oxygen - start),
+        })
+    }
+
+    /// Draws the explored area with a border of walls: `D` where the droid
+    /// started, `O` for the oxygen system, `.` for open locations and `#` for
+    /// walls and anything the droid could not reach.
+    pub fn render(&self) -> String {
+        let xs = self.open.iter().map(|point| point.x);
+        let ys = self.open.iter().map(|point| point.y);
+        let (min_x, max_x) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+        let (min_y, max_y) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0));
+        let mut map = String::new();
+        for y in min_y - 1..=max_y + 1 {
+            for x in min_x - 1..=max_x + 1 {
+                let point = Point::new(x, y);
+                map.push(if point == Point::origin() {
+                    'D'
+                } else if point == self.oxygen {
+                    'O'
+                } else if self.open.contains(&point) {
+                    '.'
+                } else {
+                    '#'
+                });
+            }
+            map.push('\n');
+        }
+        map
+    }
+
+    pub fn distance_to_oxygen(&self) -> Result<usize> {
+        dijkstra(self, &Point::origin(), &self.oxygen)
+            .map(|path| path.cost)
+            .ok_or_else(|| anyhow!("Oxygen system unreachable"))
+    }
+
+    /// Minutes until the oxygen spread from the system fills every location.
+    pub fn fill_time(&self) -> usize {
+        let mut minutes = HashMap::new();
+        minutes.insert(self.oxygen, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(self.oxygen);
+        while let Some(point) = queue.pop_front() {
+            let minute = minutes[&point] + 1;
+            for (next, _) in self.neighbors(&point) {
+                if let Entry::Vacant(entry) = minutes.entry(next) {
+                    entry.insert(minute);
+                    queue.push_back(next);
+                }
+            }
+        }
+        minutes.values().copied().max().unwrap_or(0)
+    }
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(15)?;
+    let program: Program = input.parse()?;
+    let ship = Ship::explore(program.into_growable_computer())?;
+    if output::enabled() {
+        let path = output::path(15, "ship.txt")?;
+        fs::write(&path, ship.render())?;
+        explain::emit(Event::Wrote(path));
+    }
+    answer(1, ship.distance_to_oxygen()?);
+    explain::emit(Event::OxygenSystem {
+        position: ship.oxygen,
+        open: ship.open.len(),
+    });
+    answer(2, ship.fill_time());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::asm::assemble;
+
+    /// The example ship from part 2, with unknown locations as walls.
+    const SHIP: &str = " ##
+#..##
+#.#..#
+#.O.#
+ ###  ";
+
+    /// A droid program that moves through `ship`, starting at `start`.
+    fn droid(ship: &str, start: Point) -> Result<Program> {
+        let width = ship.lines().map(str::len).max().unwrap_or(0) as Value;
+        let grid = ship
+            .lines()
+            .flat_map(|line| {
+                let mut row = line
+                    .chars()
+                    .map(|c| match c {
+                        '.' => 1,
+                        'O' => 2,
+                        _ => 0,
+                    })
+                    .collect::<Vec<_>>();
+                row.resize(width as usize, 0);
+                row
+            })
+            .map(|cell| cell.to_string())
+            .collect::<Vec<_>>();
+        let source = format!(
+            "\
+var pos = {start}
+var command
+var target
+var cell
+var neg
+next:
+    in command
+    arb command
+    mov @moves, target
+    mul command, #-1, neg
+    arb neg
+    add pos, target, target
+    arb target
+    mov @grid, cell
+    mul target, #-1, neg
+    arb neg
+    jf cell, #report
+    mov target, pos
+report:
+    out cell
+    jmp next
+moves:
+    data 0, {north}, {width}, -1, 1
+grid:
+    data {grid}
+",
+            start = start.y * width + start.x,
+            north = -width,
+            width = width,
+            grid = grid.join(", ")
+        );
+        Ok(Program::new(assemble(&source)?))
+    }
+
+    #[test]
+    fn test_explore() -> Result<()> {
+        let ship = Ship::explore(droid(SHIP, Point::new(1, 1))?.into_growable_computer())?;
+        assert_eq!(ship.oxygen, Point::new(1, 2));
+        assert_eq!(ship.open.len(), 8);
+        assert_eq!(ship.distance_to_oxygen()?, 3);
+        assert_eq!(ship.fill_time(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let ship = Ship::explore(droid(SHIP, Point::new(1, 1))?.into_growable_computer())?;
+        let map = ship.render();
+        assert_eq!(map, "######\n#D.###\n#.#..#\n#.O.##\n######\n");
+        let reloaded = Ship::parse(&map)?;
+        assert_eq!(reloaded, ship);
+        assert_eq!(reloaded.distance_to_oxygen()?, 3);
+        assert!(Ship::parse("#.O#").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_oxygen() -> Result<()> {
+        let droid = droid(SHIP.replace('O', ".").as_str(), Point::new(1, 1))?;
+        assert!(Ship::explore(droid.into_growable_computer()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use crate::prelude::*;
+use anyhow::anyhow;
+
+const PHASES: usize = 100;
+const REPEATS: usize = 10_000;
+
+fn parse(input: &str) -> Result<Vec<i32>> {
+    input
+        .trim()
+        .chars()
+        .map(|c| {
+            c.to_digit(10)
+                .map(|digit| digit as i32)
+                .ok_or_else(|| anyhow!("Invalid digit {:?}", c))
+        })
+        .collect()
+}
+
+fn digits(signal: &[i32]) -> String {
+    signal.iter().map(|digit| digit.to_string()).collect()
+}
+
+/// Runs one phase of the FFT.
+///
+/// Output digit `i` adds and subtracts alternating blocks of `i + 1` input
+/// digits, so with prefix sums every block costs one subtraction and a
+/// phase takes O(n log n) instead of O(n²).
+fn phase(signal: &[i32]) -> Vec<i32> {
+    let mut prefix = vec![0; signal.len() + 1];
+    for (i, digit) in signal.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + digit;
+    }
+    let sum = |start: usize, len: usize| {
+        let end = (start + len).min(signal.len());
+        prefix[end] - prefix[start.min(end)]
+    };
+    (1..=signal.len())
+        .map(|len| {
+            let mut total = 0;
+            // The pattern starts with `len - 1` zeros after skipping one.
+            let mut start = len - 1;
+            while start < signal.len() {
+                total += sum(start, len) - sum(start + 2 * len, len);
+                start += 4 * len;
+            }
+            (total % 10).abs()
+        })
+        .collect()
+}
+
+/// A way to compute FFT phases, chosen at runtime through `&dyn Transform`.
+pub trait Transform {
+    /// The eight digits from `offset` on after `phases` phases over `signal`
+    /// repeated `repeats` times.
+    fn message(
+        &self,
+        signal: &[i32],
+        repeats: usize,
+        offset: usize,
+        phases: usize,
+    ) -> Result<Vec<i32>>;
+}
+
+/// Computes every digit of every phase with the full pattern. Works for any
+/// offset, but takes time for the whole signal.
+pub struct Naive;
+
+/// Only computes the digits from the offset on, which is valid in the second
+/// half of the signal: the pattern is zero before each digit there and one
+/// from it on, so every output digit is the sum of the input digits from its
+/// position to the end.
+pub struct SuffixSum;
+
+fn check_offset(len: usize, offset: usize) -> Result<()> {
+    match offset.checked_add(8) {
+        Some(end) if end <= len => Ok(()),
+        _ => Err(anyhow!(
+            "Offset {} leaves fewer than 8 of {} digits",
+            offset,
+            len
+        )),
+    }
+}
+
+impl Transform for Naive {
+    fn message(
+        &self,
+        signal: &[i32],
+        repeats: usize,
+        offset: usize,
+        phases: usize,
+    ) -> Result<Vec<i32>> {
+        let mut signal = signal.repeat(repeats);
+        check_offset(signal.len(), offset)?;
+        for _ in 0..phases {
+            signal = phase(&signal);
+        }
+        Ok(signal[offset..offset + 8].to_vec())
+    }
+}
+
+impl Transform for SuffixSum {
+    fn message(
+        &self,
+        signal: &[i32],
+        repeats: usize,
+        offset: usize,
+        phases: usize,
+    ) -> Result<Vec<i32>> {
+        let len = signal.len() * repeats;
+        check_offset(len, offset)?;
+        if offset < len / 2 {
+            return Err(anyhow!(
+                "Offset {} is not in the second half of {} digits",
+                offset,
+                len
+            ));
+        }
+        let mut suffix = (offset..len)
+            .map(|i| signal[i % signal.len()])
+            .collect::<Vec<_>>();
+        for _ in 0..phases {
+            let mut sum = 0;
+            for digit in suffix.iter_mut().rev() {
+                sum = (sum + *digit) % 10;
+                *digit = sum;
+            }
+        }
+        Ok(suffix[..8].to_vec())
+    }
+}
+
+fn part1(signal: &[i32]) -> Result<String> {
+    Naive
+        .message(signal, 1, 0, PHASES)
+        .map(|message| digits(&message))
+}
+
+/// Decodes the message in the repeated signal at the offset its first seven
+/// digits give.
+///
+/// Running the full pattern over 10,000 copies would take far too long, so
+/// this only works for offsets where the suffix sum shortcut applies.
+fn part2(signal: &[i32]) -> Result<String> {
+    if signal.len() < 7 {
+        return Err(anyhow!("The signal has no 7 digit message offset"));
+    }
+    let offset = signal[..7]
+        .iter()
+        .fold(0, |acc, &digit| acc * 10 + digit as usize);
+    let message = SuffixSum.message(signal, REPEATS, offset, PHASES)?;
+    Ok(digits(&message))
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(16)?;
+    let signal = parse(&input)?;
+    answer(1, part1(&signal)?);
+    answer(2, part2(&signal)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase() -> Result<()> {
+        let mut signal = parse("12345678")?;
+        for expected in &["48226158", "34040438", "03415518", "01029498"] {
+            signal = phase(&signal);
+            assert_eq!(digits(&signal), *expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_part1() -> Result<()> {
+        for &(input, expected) in &[
+            ("80871224585914546619083218645595", "24176176"),
+            ("19617804207202209144916044189917", "73745418"),
+            ("69317163492948606335995924319873", "52432133"),
+        ] {
+            assert_eq!(part1(&parse(input)?)?, expected);
+        }
+        assert!(part1(&parse("1234567")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> Result<()> {
+        for &(input, expected) in &[
+            ("03036732577212944063491565474664", "84462026"),
+            ("02935109699940807407585447034323", "78725270"),
+            ("03081770884921959731165446850517", "53553731"),
+        ] {
+            assert_eq!(part2(&parse(input)?)?, expected);
+        }
+        assert!(part2(&parse("12345678")?).is_err());
+        assert!(part2(&parse("123")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_backends_agree() -> Result<()> {
+        let signal = parse("80871224585914546619083218645595")?;
+        let backends: [&dyn Transform; 2] = [&Naive, &SuffixSum];
+        let messages = backends
+            .iter()
+            .map(|backend| backend.message(&signal, 3, 60, 5))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(messages[0], messages[1]);
+        assert!(SuffixSum.message(&signal, 3, 10, 5).is_err());
+        assert!(Naive.message(&signal, 1, 25, 5).is_err());
+        Ok(())
+    }
+}
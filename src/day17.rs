@@ -0,0 +1,307 @@
+use crate::output;
+use crate::prelude::*;
+use crate::vm::State;
+use anyhow::anyhow;
+use std::collections::HashSet;
+use std::fs;
+
+const MAX_FUNCTIONS: usize = 3;
+const MAX_LEN: usize = 20;
+
+/// The scaffold and the vacuum robot as seen by the camera.
+#[derive(Debug, PartialEq)]
+struct View {
+    scaffold: HashSet<Point>,
+    robot: Point,
+    heading: Vector,
+}
+
+/// The movement routines for the robot, each one line of input.
+#[derive(Debug, PartialEq)]
+struct Routines {
+    main: Vec<usize>,
+    functions: Vec<Vec<String>>,
+}
+
+impl View {
+    fn parse(text: &str) -> Result<Self> {
+        let mut scaffold = HashSet::new();
+        let mut robot = None;
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let point = Point::new(x as i64, y as i64);
+                let heading = match c {
+                    '#' => None,
+                    '^' => Some(Vector::new(0, -1)),
+                    'v' => Some(Vector::new(0, 1)),
+                    '<' => Some(Vector::new(-1, 0)),
+                    '>' => Some(Vector::new(1, 0)),
+                    _ => continue,
+                };
+                scaffold.insert(point);
+                if let Some(heading) = heading {
+                    robot = Some((point, heading));
+                }
+            }
+        }
+        let (robot, heading) = robot.ok_or_else(|| anyhow!("No robot in view"))?;
+        Ok(Self {
+            scaffold,
+            robot,
+            heading,
+        })
+    }
+
+    /// Draws the view the way the camera does, up to the last scaffold
+    /// row and column, so [`View::parse`] reads it back.
+    fn render(&self) -> String {
+        let width = self.scaffold.iter().map(|point| point.x).max().unwrap_or(0);
+        let height = self.scaffold.iter().map(|point| point.y).max().unwrap_or(0);
+        let mut view = String::new();
+        for y in 0..=height {
+            for x in 0..=width {
+                let point = Point::new(x, y);
+                view.push(if point == self.robot {
+                    match (self.heading.x, self.heading.y) {
+                        (0, -1) => '^',
+                        (0, _) => 'v',
+                        (-1, _) => '<',
+                        _ => '>',
+                    }
+                } else if self.scaffold.contains(&point) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            view.push('\n');
+        }
+        view
+    }
+
+    fn intersections(&self) -> impl Iterator<Item = &Point> + '_ {
+        self.scaffold.iter().filter(move |point| {
+            [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .iter()
+                .all(|&(x, y)| self.scaffold.contains(&(*point + Vector::new(x, y))))
+        })
+    }
+
+    fn alignment(&self) -> i64 {
+        self.intersections().map(|point| point.x * point.y).sum()
+    }
+
+    /// Turns and step counts like `R,8` that lead the robot over the whole
+    /// scaffold, going straight across intersections.
+    fn path(&self) -> Vec<String> {
+        let mut moves = Vec::new();
+        let (mut position, mut heading) = (self.robot, self.heading);
+        loop {
+            let left = Vector::new(heading.y, -heading.x);
+            let right = -left;
+            let turn = if self.scaffold.contains(&(position + left)) {
+                heading = left;
+                'L'
+            } else if self.scaffold.contains(&(position + right)) {
+                heading = right;
+                'R'
+            } else {
+                return moves;
+            };
+            let mut steps = 0;
+            while self.scaffold.contains(&(position + heading)) {
+                position += heading;
+                steps += 1;
+            }
+            moves.push(format!("{},{}", turn, steps));
+        }
+    }
+}
+
+fn fits(moves: &[String]) -> bool {
+    moves.join(",").len() <= MAX_LEN
+}
+
+/// Splits `moves` into calls of at most three functions, trying the longest
+/// function first wherever a new one starts.
+fn compress<'m>(
+    moves: &'m [String],
+    functions: &mut Vec<&'m [String]>,
+    main: &mut Vec<usize>,
+) -> bool {
+    if moves.is_empty() {
+        return true;
+    }
+    // Calls are separated by commas as well.
+    if main.len() * 2 + 1 > MAX_LEN {
+        return false;
+    }
+    for index in 0..functions.len() {
+        let function = functions[index];
+        if moves.starts_with(function) {
+            main.push(index);
+            if compress(&moves[function.len()..], functions, main) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+    if functions.len() < MAX_FUNCTIONS {
+        for len in (1..=moves.len()).rev().filter(|&len| fits(&moves[..len])) {
+            functions.push(&moves[..len]);
+            main.push(functions.len() - 1);
+            if compress(&moves[len..], functions, main) {
+                return true;
+            }
+            main.pop();
+            functions.pop();
+        }
+    }
+    false
+}
+
+impl Routines {
+    fn find(moves: &[String]) -> Result<Self> {
+        let (mut functions, mut main) = (Vec::new(), Vec::new());
+        if !compress(moves, &mut functions, &mut main) {
+            return Err(anyhow!(
+                "Path does not fit into {} functions",
+                MAX_FUNCTIONS
+            ));
+        }
+        let mut functions = functions
+            .into_iter()
+            .map(|function| function.to_vec())
+            .collect::<Vec<_>>();
+        // The robot always reads three functions; unused ones are never called.
+        if let Some(last) = functions.last().cloned() {
+            functions.resize(MAX_FUNCTIONS, last);
+        }
+        Ok(Self { main, functions })
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let main = self
+            .main
+            .iter()
+            .map(|&index| ((b'A' + index as u8) as char).to_string())
+            .collect::<Vec<_>>();
+        Some(main.join(","))
+            .into_iter()
+            .chain(self.functions.iter().map(|function| function.join(",")))
+            .collect()
+    }
+}
+
+fn view(program: &Program) -> Result<View> {
+    let mut camera = program.clone().into_growable_computer();
+    match camera.run_until_blocked() {
+        State::Halted => View::parse(&camera.take_ascii().text),
+        State::Faulted(err) => Err(err.into()),
+        state => Err(anyhow!("Camera stopped with {:?}", state)),
+    }
+}
+
+/// Wakes the robot and returns the dust it collected on `routines`.
+fn collect_dust(program: &Program, routines: &Routines) -> Result<i64> {
+    let mut robot = program.clone();
+    robot.patch(0, 2)?;
+    let mut robot = robot.into_growable_computer();
+    for line in routines.lines() {
+        robot.push_line(&line);
+    }
+    robot.push_line("n");
+    match robot.run_until_blocked() {
+        State::Halted => (),
+        State::Faulted(err) => return Err(err.into()),
+        state => return Err(anyhow!("Robot stopped with {:?}", state)),
+    }
+    robot
+        .take_ascii()
+        .answer()
+        .ok_or_else(|| anyhow!("Robot did not report the dust"))
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(17)?;
+    let program = input.parse()?;
+    let view = view(&program)?;
+    if output::enabled() {
+        let path = output::path(17, "view.txt")?;
+        fs::write(&path, view.render())?;
+        explain::emit(Event::Wrote(path));
+    }
+    answer(1, view.alignment());
+    let routines = Routines::find(&view.path())?;
+    explain::emit(Event::Routines(routines.lines()));
+    answer(2, collect_dust(&program, &routines)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE1: &str = "\
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..";
+
+    const EXAMPLE2: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......";
+
+    #[test]
+    fn test_part1() -> Result<()> {
+        let view = View::parse(EXAMPLE1)?;
+        assert_eq!(view.intersections().count(), 4);
+        assert_eq!(view.alignment(), 76);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        for example in &[EXAMPLE1, EXAMPLE2] {
+            let view = View::parse(example)?;
+            assert_eq!(view.render(), format!("{}\n", example));
+            assert_eq!(View::parse(&view.render())?, view);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> Result<()> {
+        let path = View::parse(EXAMPLE2)?.path();
+        assert_eq!(
+            path.join(","),
+            "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+        );
+        let routines = Routines::find(&path)?;
+        let expanded = routines
+            .main
+            .iter()
+            .flat_map(|&index| routines.functions[index].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(expanded, path);
+        assert!(routines.lines().iter().all(|line| line.len() <= MAX_LEN));
+        assert!(View::parse(".#.\n###").is_err());
+        Ok(())
+    }
+}
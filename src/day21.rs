@@ -0,0 +1,145 @@
+use crate::prelude::*;
+use crate::vm::types::Value;
+use crate::vm::State;
+use anyhow::anyhow;
+
+const MAX_INSTRUCTIONS: usize = 15;
+
+/// Jumps over any hole in the next three tiles if it can land on D.
+const WALK: &str = "\
+NOT A J
+NOT B T
+OR T J
+NOT C T
+OR T J
+AND D J
+WALK";
+
+/// Like `WALK`, but only jumps if the droid can either step on from D (E)
+/// or jump again right away (H).
+const RUN: &str = "\
+NOT A J
+NOT B T
+OR T J
+NOT C T
+OR T J
+AND D J
+NOT E T
+NOT T T
+OR H T
+AND T J
+RUN";
+
+/// Checks that `script` is a springscript program the droid accepts.
+fn check(script: &str) -> Result<()> {
+    let lines = script.lines().collect::<Vec<_>>();
+    let (mode, instructions) = lines
+        .split_last()
+        .ok_or_else(|| anyhow!("Empty springscript"))?;
+    let sensors = match *mode {
+        "WALK" => "ABCD",
+        "RUN" => "ABCDEFGHI",
+        _ => return Err(anyhow!("Script must end with WALK or RUN, not {:?}", mode)),
+    };
+    if instructions.len() > MAX_INSTRUCTIONS {
+        return Err(anyhow!("{} instructions are too many", instructions.len()));
+    }
+    for line in instructions {
+        match *line.split(' ').collect::<Vec<_>>() {
+            ["AND", x, y] | ["OR", x, y] | ["NOT", x, y]
+                if x.len() == 1
+                    && (sensors.contains(x) || x == "T" || x == "J")
+                    && (y == "T" || y == "J") => {}
+            _ => return Err(anyhow!("Invalid instruction {:?} for {}", line, mode)),
+        }
+    }
+    Ok(())
+}
+
+/// Runs the springdroid with `script` and returns the hull damage it
+/// reports, or the droid's last moments if it fell into space.
+fn survey(program: &Program, script: &str) -> Result<Value> {
+    check(script)?;
+    let mut droid = program.clone().into_growable_computer();
+    for line in script.lines() {
+        droid.push_line(line);
+    }
+    match droid.run_until_blocked() {
+        State::Halted => (),
+        State::Faulted(err) => return Err(err.into()),
+        state => return Err(anyhow!("Droid stopped with {:?}", state)),
+    }
+    let ascii = droid.take_ascii();
+    ascii
+        .answer()
+        .ok_or_else(|| anyhow!("Droid fell into space:\n{}", ascii.text))
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(21)?;
+    let program = input.parse()?;
+    answer(1, survey(&program, WALK)?);
+    answer(2, survey(&program, RUN)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether a droid running `script` crosses `hull` without falling.
+    fn crosses(script: &str, hull: &str) -> bool {
+        let hull = hull.as_bytes();
+        let ground = |position: usize| hull.get(position).is_none_or(|&tile| tile == b'#');
+        let mut position = 0;
+        while position < hull.len() {
+            let (mut t, mut j) = (false, false);
+            for line in script.lines() {
+                let parts = line.split(' ').collect::<Vec<_>>();
+                if parts.len() != 3 {
+                    continue;
+                }
+                let x = match parts[1] {
+                    "T" => t,
+                    "J" => j,
+                    sensor => ground(position + (sensor.as_bytes()[0] - b'A') as usize + 1),
+                };
+                let y = if parts[2] == "T" { &mut t } else { &mut j };
+                *y = match parts[0] {
+                    "AND" => x && *y,
+                    "OR" => x || *y,
+                    _ => !x,
+                };
+            }
+            position += if j { 4 } else { 1 };
+            if !ground(position) {
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn test_scripts() -> Result<()> {
+        check(WALK)?;
+        check(RUN)?;
+        assert!(check("NOT E J\nWALK").is_err());
+        assert!(check("NOT A A\nWALK").is_err());
+        assert!(check("NOT A J\nJUMP").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_crossing() {
+        for hull in &[
+            "#####.###########",
+            "#####..#.########",
+            "#####...#########",
+        ] {
+            assert!(crosses(WALK, hull), "{}", hull);
+            assert!(crosses(RUN, hull), "{}", hull);
+        }
+        assert!(!crosses(WALK, "#####.#.##..#####"));
+        assert!(crosses(RUN, "#####.#.##..#####"));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::prelude::*;
+use crate::vm::memory::GrowableMemory;
+use crate::vm::network::{Event, Network, NAT_ADDRESS};
+use crate::vm::types::Value;
+
+const SIZE: usize = 50;
+
+fn network(program: &Program) -> Network<GrowableMemory> {
+    Network::new(GrowableMemory::new(program.values().to_vec()), SIZE).with_nat(NAT_ADDRESS)
+}
+
+/// The `y` of the first packet sent to the NAT address.
+fn first_to_nat(program: &Program) -> Result<Value> {
+    let y = network(program).run_until(|event| match *event {
+        Event::Sent { to, packet, .. } if to == NAT_ADDRESS => Some(packet.y),
+        _ => None,
+    })?;
+    Ok(y)
+}
+
+/// The first `y` the NAT delivers to computer 0 twice in a row.
+fn first_repeated_y(program: &Program) -> Result<Value> {
+    let mut last = None;
+    let y = network(program).run_until(|event| match *event {
+        Event::Resumed(packet) if last == Some(packet.y) => Some(packet.y),
+        Event::Resumed(packet) => {
+            last = Some(packet.y);
+            None
+        }
+        _ => None,
+    })?;
+    Ok(y)
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(23)?;
+    let program = input.parse()?;
+    answer(1, first_to_nat(&program)?);
+    answer(2, first_repeated_y(&program)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::asm::assemble;
+
+    /// Computer 0 sends `(0, 0)` to computer 1. Every computer passes the
+    /// packets it receives on to the next address, adding one to `y` until
+    /// it reaches 60; the last one sends them to the NAT.
+    const RELAY: &str = "
+const LIMIT = 60
+var addr
+var next
+var x
+var y
+var t
+    in addr
+    add addr, #1, next
+    eq next, #50, t
+    jf t, #start
+    mov #255, next
+start:
+    jt addr, #loop
+    out next
+    out #0
+    out #0
+loop:
+    in x
+    eq x, #-1, t
+    jt t, #loop
+    in y
+    lt y, #LIMIT, t
+    jf t, #send
+    add y, #1, y
+send:
+    out next
+    out x
+    out y
+    jmp loop";
+
+    #[test]
+    fn test_network() -> Result<()> {
+        let program = Program::new(assemble(RELAY)?);
+        assert_eq!(first_to_nat(&program)?, 49);
+        assert_eq!(first_repeated_y(&program)?, 60);
+        Ok(())
+    }
+}
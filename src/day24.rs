@@ -0,0 +1,188 @@
+use crate::prelude::*;
+use anyhow::anyhow;
+use std::collections::HashSet;
+
+const SIZE: i32 = 5;
+const CENTER: i32 = 2;
+const MINUTES: usize = 200;
+
+/// One 5×5 level with a bit per tile, row by row from the top left.
+///
+/// Read as a number this is the biodiversity rating.
+type Grid = u32;
+
+fn bit(x: i32, y: i32) -> Grid {
+    1 << (y * SIZE + x)
+}
+
+fn is_bug(grid: Grid, x: i32, y: i32) -> bool {
+    grid & bit(x, y) != 0
+}
+
+fn parse(input: &str) -> Result<Grid> {
+    let lines = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if lines.len() != SIZE as usize {
+        return Err(anyhow!("Expected {} rows, got {}", SIZE, lines.len()));
+    }
+    let mut grid = 0;
+    for (y, line) in lines.iter().enumerate() {
+        if line.chars().count() != SIZE as usize {
+            return Err(anyhow!("Expected {} tiles in row {}", SIZE, y + 1));
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '#' => grid |= bit(x as i32, y as i32),
+                '.' => (),
+                _ => return Err(anyhow!("Invalid tile {:?}", c)),
+            }
+        }
+    }
+    Ok(grid)
+}
+
+/// Whether a tile with `bug` and `adjacent` bugs around it has a bug in the
+/// next minute.
+fn lives(bug: bool, adjacent: u32) -> bool {
+    adjacent == 1 || (!bug && adjacent == 2)
+}
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn step(grid: Grid) -> Grid {
+    let mut next = 0;
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let adjacent = DIRECTIONS
+                .iter()
+                .map(|&(dx, dy)| (x + dx, y + dy))
+                .filter(|&(x, y)| (0..SIZE).contains(&x) && (0..SIZE).contains(&y))
+                .filter(|&(x, y)| is_bug(grid, x, y))
+                .count() as u32;
+            if lives(is_bug(grid, x, y), adjacent) {
+                next |= bit(x, y);
+            }
+        }
+    }
+    next
+}
+
+fn first_repeated(grid: Grid) -> Grid {
+    let mut seen = HashSet::new();
+    let mut grid = grid;
+    while seen.insert(grid) {
+        grid = step(grid);
+    }
+    grid
+}
+
+/// Bugs adjacent to `(x, y)` when every centre tile holds the next level.
+fn adjacent_recursive(outer: Grid, grid: Grid, inner: Grid, x: i32, y: i32) -> u32 {
+    DIRECTIONS
+        .iter()
+        .map(|&(dx, dy)| {
+            let (nx, ny) = (x + dx, y + dy);
+            if !(0..SIZE).contains(&nx) || !(0..SIZE).contains(&ny) {
+                // Off the edge is the tile next to the centre of the outer level.
+                is_bug(outer, CENTER + dx, CENTER + dy) as u32
+            } else if (nx, ny) == (CENTER, CENTER) {
+                // Into the centre is the whole facing edge of the inner level.
+                (0..SIZE)
+                    .map(|i| match (dx, dy) {
+                        (0, -1) => (i, SIZE - 1),
+                        (0, 1) => (i, 0),
+                        (-1, 0) => (SIZE - 1, i),
+                        _ => (0, i),
+                    })
+                    .filter(|&(x, y)| is_bug(inner, x, y))
+                    .count() as u32
+            } else {
+                is_bug(grid, nx, ny) as u32
+            }
+        })
+        .sum()
+}
+
+/// Advances the levels from the outermost to the innermost by a minute,
+/// adding an empty level on either side for the bugs to spread into.
+fn step_recursive(levels: &[Grid]) -> Vec<Grid> {
+    let mut padded = vec![0];
+    padded.extend_from_slice(levels);
+    padded.push(0);
+    (0..padded.len())
+        .map(|depth| {
+            let outer = if depth > 0 { padded[depth - 1] } else { 0 };
+            let inner = padded.get(depth + 1).copied().unwrap_or(0);
+            let grid = padded[depth];
+            let mut next = 0;
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    if (x, y) == (CENTER, CENTER) {
+                        continue;
+                    }
+                    let adjacent = adjacent_recursive(outer, grid, inner, x, y);
+                    if lives(is_bug(grid, x, y), adjacent) {
+                        next |= bit(x, y);
+                    }
+                }
+            }
+            next
+        })
+        .collect()
+}
+
+fn bugs_after(grid: Grid, minutes: usize) -> u32 {
+    let mut levels = vec![grid & !bit(CENTER, CENTER)];
+    for _ in 0..minutes {
+        levels = step_recursive(&levels);
+    }
+    levels.iter().map(|level| level.count_ones()).sum()
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read(24)?;
+    let grid = parse(&input)?;
+    answer(1, first_repeated(grid));
+    answer(2, bugs_after(grid, MINUTES));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+....#
+#..#.
+#..##
+..#..
+#....";
+
+    #[test]
+    fn test_part1() -> Result<()> {
+        let grid = parse(EXAMPLE)?;
+        let after = parse(
+            "\
+#..#.
+####.
+###.#
+##.##
+.##..",
+        )?;
+        assert_eq!(step(grid), after);
+        assert_eq!(first_repeated(grid), 2_129_920);
+        Ok(())
+    }
+
+    #[test]
+    fn test_part2() -> Result<()> {
+        assert_eq!(bugs_after(parse(EXAMPLE)?, 10), 99);
+        assert!(parse("#...\n").is_err());
+        assert!(parse(&"#\n".repeat(25)).is_err());
+        assert!(parse("......\n....\n.....\n.....\n.....\n").is_err());
+        Ok(())
+    }
+}
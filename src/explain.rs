@@ -0,0 +1,119 @@
+use crate::util::geom::Point;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Something a day found on the way to its answers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Day 2 patched in `noun` and `verb` to produce `output`.
+    NounVerb { noun: i64, verb: i64, output: i64 },
+    /// Day 2 had to try every noun and verb.
+    BruteForce,
+    /// Two day 3 wires cross `count` times.
+    Crossings { wires: (usize, usize), count: usize },
+    /// The intersection closest to the port among `of` intersections.
+    ClosestIntersection { point: Point, of: usize },
+    /// The intersection the signal reaches first.
+    ShortestDelay { point: Point },
+    /// The number of bodies in the orbit map besides COM.
+    Bodies(usize),
+    /// The bodies passed when transferring between two orbits.
+    TransferPath(Vec<String>),
+    /// Day 14's reaction count and the chemicals left over after making fuel.
+    Leftovers { reactions: usize, chemicals: usize },
+    /// Where day 15's droid found the oxygen system.
+    OxygenSystem { position: Point, open: usize },
+    /// The movement routines given to day 17's vacuum robot.
+    Routines(Vec<String>),
+    /// An artifact written to disk.
+    Wrote(PathBuf),
+    /// Anything else, as text.
+    Note(String),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::NounVerb { noun, verb, output } => {
+                write!(f, "noun {} and verb {} produce {}", noun, verb, output)
+            }
+            Event::BruteForce => {
+                write!(f, "output is not affine in noun and verb, trying them all")
+            }
+            Event::Crossings {
+                wires: (i, j),
+                count,
+            } => write!(f, "Wires {} and {} cross {} times", i, j, count),
+            Event::ClosestIntersection { point, of } => write!(
+                f,
+                "Closest of {} intersections at ({}, {})",
+                of, point.x, point.y
+            ),
+            Event::ShortestDelay { point } => {
+                write!(f, "Shortest combined delay at ({}, {})", point.x, point.y)
+            }
+            Event::Bodies(count) => write!(f, "{} bodies orbit COM", count),
+            Event::TransferPath(path) => write!(f, "Transfers: {}", path.join(" -> ")),
+            Event::Leftovers {
+                reactions,
+                chemicals,
+            } => write!(
+                f,
+                "{} reactions, {} chemicals left over",
+                reactions, chemicals
+            ),
+            Event::OxygenSystem { position, open } => write!(
+                f,
+                "Oxygen system at ({}, {}), {} open locations",
+                position.x, position.y, open
+            ),
+            Event::Routines(lines) => write!(f, "Routines: {}", lines.join(" / ")),
+            Event::Wrote(path) => write!(f, "Wrote {}", path.display()),
+            Event::Note(text) => f.write_str(text),
+        }
+    }
+}
+
+/// Prints an event below the answers when `--explain` is given.
+pub fn emit(event: Event) {
+    if enabled() {
+        println!("    {}", event);
+    }
+}
+
+/// Emits a free-form `Event::Note`.
+#[macro_export]
+macro_rules! explain {
+    ($($arg:tt)*) => {
+        if $crate::explain::enabled() {
+            $crate::explain::emit($crate::explain::Event::Note(format!($($arg)*)));
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let event = Event::ClosestIntersection {
+            point: Point::new(3, -3),
+            of: 2,
+        };
+        assert_eq!(event.to_string(), "Closest of 2 intersections at (3, -3)");
+        let path = Event::TransferPath(vec!["K".to_owned(), "J".to_owned(), "E".to_owned()]);
+        assert_eq!(path.to_string(), "Transfers: K -> J -> E");
+    }
+}
@@ -0,0 +1,301 @@
+use crate::vm::memory::GrowableMemory;
+use crate::vm::types::Value;
+use crate::vm::{parse_program, Computer, Memory, State};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+// `include/intcode.h` declares this API; regenerate it with
+// `cargo xtask header` after changing it.
+//
+// A panic must not unwind into C, so every entry point that runs more than
+// a field access catches it and returns a failure value instead.
+
+/// Runs `f`, returning `fallback` if it panics.
+fn catch<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// An Intcode computer owned by C code.
+pub struct IntcodeComputer {
+    computer: Computer<GrowableMemory>,
+    outputs: VecDeque<Value>,
+    error: Option<CString>,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IntcodeState {
+    Running,
+    Halted,
+    NeedsInput,
+    OutputFull,
+    StepLimitExceeded,
+    DeadlineExceeded,
+    Watchpoint,
+    /// The message is available from `intcode_error`.
+    Faulted,
+}
+
+impl IntcodeComputer {
+    fn new(program: Vec<Value>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            computer: Computer::new(GrowableMemory::new(program)),
+            outputs: VecDeque::new(),
+            error: None,
+        }))
+    }
+
+    /// Runs `f` on the computer and translates the state it returns, keeping
+    /// the error message only while the computer is faulted.
+    fn execute(&mut self, f: impl FnOnce(&mut Computer<GrowableMemory>) -> State) -> IntcodeState {
+        match panic::catch_unwind(AssertUnwindSafe(|| f(&mut self.computer))) {
+            Ok(state) => self.report(state),
+            Err(payload) => {
+                self.error = CString::new(format!("Panicked: {}", panic_message(&*payload))).ok();
+                IntcodeState::Faulted
+            }
+        }
+    }
+
+    fn report(&mut self, state: State) -> IntcodeState {
+        self.outputs.extend(self.computer.take_outputs());
+        self.error = None;
+        match state {
+            State::Running => IntcodeState::Running,
+            State::Halted => IntcodeState::Halted,
+            State::NeedsInput => IntcodeState::NeedsInput,
+            State::OutputFull => IntcodeState::OutputFull,
+            State::StepLimitExceeded => IntcodeState::StepLimitExceeded,
+            State::DeadlineExceeded => IntcodeState::DeadlineExceeded,
+            State::Watchpoint { .. } => IntcodeState::Watchpoint,
+            State::Faulted(err) => {
+                // Error messages never contain NUL bytes.
+                self.error = CString::new(err.to_string()).ok();
+                IntcodeState::Faulted
+            }
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload
+            .downcast_ref::<String>()
+            .map_or("unknown panic", String::as_str),
+    }
+}
+
+/// Creates a computer for the `len` values at `program`.
+///
+/// # Safety
+///
+/// `program` must point to `len` readable values. The result must be
+/// released with `intcode_free`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_new(program: *const Value, len: usize) -> *mut IntcodeComputer {
+    catch(ptr::null_mut(), || {
+        let program = if len == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(program, len).to_vec()
+        };
+        IntcodeComputer::new(program)
+    })
+}
+
+/// Creates a computer for the comma separated program in `source`, or
+/// returns null if it does not parse.
+///
+/// # Safety
+///
+/// `source` must be a NUL terminated string. The result must be released
+/// with `intcode_free`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_parse(source: *const c_char) -> *mut IntcodeComputer {
+    catch(ptr::null_mut(), || {
+        let program = CStr::from_ptr(source)
+            .to_str()
+            .ok()
+            .and_then(|source| parse_program(source).ok());
+        match program {
+            Some(program) => IntcodeComputer::new(program),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// # Safety
+///
+/// `computer` must come from `intcode_new` or `intcode_parse` and must not
+/// be used afterwards. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_free(computer: *mut IntcodeComputer) {
+    if !computer.is_null() {
+        catch((), || drop(Box::from_raw(computer)));
+    }
+}
+
+/// Restores the initial memory and drops all queued inputs and outputs.
+///
+/// # Safety
+///
+/// `computer` must be a live computer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_reset(computer: *mut IntcodeComputer) {
+    let computer = &mut *computer;
+    catch((), || computer.computer.reset());
+    computer.outputs.clear();
+    computer.error = None;
+}
+
+/// # Safety
+///
+/// `computer` must be a live computer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_push_input(computer: *mut IntcodeComputer, value: Value) {
+    let computer = &mut *computer;
+    catch((), || computer.computer.push_input(value));
+}
+
+/// Stores the oldest output in `value` and returns true, or returns false
+/// if there is none.
+///
+/// # Safety
+///
+/// `computer` must be a live computer and `value` writable.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_pop_output(
+    computer: *mut IntcodeComputer,
+    value: *mut Value,
+) -> bool {
+    match (*computer).outputs.pop_front() {
+        Some(output) => {
+            *value = output;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Executes a single instruction.
+///
+/// # Safety
+///
+/// `computer` must be a live computer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_step(computer: *mut IntcodeComputer) -> IntcodeState {
+    (*computer).execute(|computer| computer.single_step())
+}
+
+/// Runs until the program blocks or `max_steps` instructions were executed.
+///
+/// # Safety
+///
+/// `computer` must be a live computer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_run(
+    computer: *mut IntcodeComputer,
+    max_steps: usize,
+) -> IntcodeState {
+    (*computer).execute(|computer| computer.run_with_limit(max_steps))
+}
+
+/// Stores the cell at `address` in `value`; returns false if it cannot be
+/// read.
+///
+/// # Safety
+///
+/// `computer` must be a live computer and `value` writable.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_read_memory(
+    computer: *const IntcodeComputer,
+    address: usize,
+    value: *mut Value,
+) -> bool {
+    match catch(None, || (*computer).computer.memory().read(address).ok()) {
+        Some(cell) => {
+            *value = cell;
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// `computer` must be a live computer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_ip(computer: *const IntcodeComputer) -> usize {
+    (*computer).computer.ip()
+}
+
+/// The message of the last fault, or null. The string is valid until the
+/// next call with `computer`.
+///
+/// # Safety
+///
+/// `computer` must be a live computer.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_error(computer: *const IntcodeComputer) -> *const c_char {
+    (*computer)
+        .error
+        .as_ref()
+        .map_or(ptr::null(), |error| error.as_ptr())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi() {
+        unsafe {
+            let computer = intcode_parse(b"3,9,8,9,10,9,4,9,99,-1,8\0".as_ptr().cast());
+            assert!(!computer.is_null());
+            assert_eq!(intcode_run(computer, 100), IntcodeState::NeedsInput);
+            intcode_push_input(computer, 8);
+            assert_eq!(intcode_step(computer), IntcodeState::Running);
+            assert_eq!(intcode_ip(computer), 2);
+            assert_eq!(intcode_run(computer, 100), IntcodeState::Halted);
+            let mut value = 0;
+            assert!(intcode_pop_output(computer, &mut value));
+            assert_eq!(value, 1);
+            assert!(!intcode_pop_output(computer, &mut value));
+            assert!(intcode_read_memory(computer, 10, &mut value));
+            assert_eq!(value, 8);
+            intcode_free(computer);
+
+            let program = [1105, 1, -1];
+            let computer = intcode_new(program.as_ptr(), program.len());
+            assert!(intcode_error(computer).is_null());
+            assert_eq!(intcode_run(computer, 100), IntcodeState::Faulted);
+            let error = CStr::from_ptr(intcode_error(computer));
+            assert!(error.to_str().unwrap().contains("negative address -1"));
+            intcode_free(computer);
+
+            assert!(intcode_parse(b"1,x\0".as_ptr().cast()).is_null());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let program = [3, 0, 4, 0, 99];
+            let computer = intcode_new(program.as_ptr(), program.len());
+            let state = (*computer).execute(|_| panic!("broken"));
+            assert_eq!(state, IntcodeState::Faulted);
+            let error = CStr::from_ptr(intcode_error(computer));
+            assert_eq!(error.to_str().unwrap(), "Panicked: broken");
+            assert_eq!(intcode_run(computer, 100), IntcodeState::NeedsInput);
+            assert!(intcode_error(computer).is_null());
+            intcode_free(computer);
+        }
+        assert!(!catch(false, || panic!("broken")));
+    }
+}
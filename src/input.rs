@@ -0,0 +1,115 @@
+use lazy_static::lazy_static;
+use std::fs::{read_to_string, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use thiserror::Error;
+
+static DEMO: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "Input for day {day} not found at {path}. Download it from \
+         https://adventofcode.com/2019/day/{day}/input while logged in and save it there."
+    )]
+    Missing { day: u32, path: PathBuf },
+    #[error("Failed to read {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("Day {day} has no official example to run in demo mode")]
+    NoExample { day: u32 },
+}
+
+/// The official example inputs that `--demo` runs the days on.
+///
+/// Days whose examples cannot produce both answers, such as day 2 whose
+/// example programs are too short to patch, have none.
+pub fn example(day: u32) -> Option<&'static str> {
+    match day {
+        1 => Some(include_str!("../data/examples/day01.txt")),
+        3 => Some(include_str!("../data/examples/day03.txt")),
+        5 => Some(include_str!("../data/examples/day05.txt")),
+        6 => Some(include_str!("../data/examples/day06.txt")),
+        12 => Some(include_str!("../data/examples/day12.txt")),
+        14 => Some(include_str!("../data/examples/day14.txt")),
+        16 => Some(include_str!("../data/examples/day16.txt")),
+        24 => Some(include_str!("../data/examples/day24.txt")),
+        _ => None,
+    }
+}
+
+/// Makes `read` return the official examples instead of the puzzle inputs.
+pub fn set_demo(demo: bool) {
+    DEMO.store(demo, Ordering::Relaxed);
+}
+
+pub fn demo() -> bool {
+    DEMO.load(Ordering::Relaxed)
+}
+
+/// Makes every day read `path` instead of its own input, until reset with
+/// `None`.
+pub fn set_override<P: AsRef<Path>>(path: Option<P>) {
+    *OVERRIDE.write().unwrap() = path.map(|path| path.as_ref().to_owned());
+}
+
+pub fn path(day: u32) -> PathBuf {
+    OVERRIDE
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("data/day{:02}.txt", day)))
+}
+
+fn map_err(day: u32, path: PathBuf, source: io::Error) -> Error {
+    match source.kind() {
+        // Inputs given on the command line are not the ones to download.
+        io::ErrorKind::NotFound if OVERRIDE.read().unwrap().is_none() => {
+            Error::Missing { day, path }
+        }
+        _ => Error::Io { path, source },
+    }
+}
+
+/// Opens the input file of `day`, which does not work in demo mode.
+pub fn open(day: u32) -> Result<File, Error> {
+    let path = path(day);
+    File::open(&path).map_err(|err| map_err(day, path, err))
+}
+
+pub fn read(day: u32) -> Result<String, Error> {
+    if demo() {
+        return example(day)
+            .map(str::to_owned)
+            .ok_or(Error::NoExample { day });
+    }
+    let path = path(day);
+    read_to_string(&path).map_err(|err| map_err(day, path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing() {
+        match read(99) {
+            Err(Error::Missing { day, path }) => {
+                assert_eq!(day, 99);
+                assert_eq!(path, PathBuf::from("data/day99.txt"));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_examples() {
+        assert!(example(1).unwrap().starts_with("12\n14\n"));
+        assert_eq!(example(2), None);
+    }
+}
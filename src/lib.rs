@@ -0,0 +1,25 @@
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day11;
+pub mod day12;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day21;
+pub mod day23;
+pub mod day24;
+pub mod explain;
+pub mod ffi;
+pub mod input;
+pub mod output;
+pub mod prelude;
+pub mod runner;
+pub mod util;
+pub mod vm;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,20 +1,242 @@
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod vm;
+use aoc2019::{
+    day01, day02, day03, day04, day05, day06, day11, day12, day14, day15, day16, day17, day21,
+    day23, day24, explain, input, output, runner,
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use runner::budget::Budgets;
+use runner::cache::{self, Cache};
+use runner::report::Report;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+const CACHE_PATH: &str = "target/aoc-answers.tsv";
+const CONFIG_PATH: &str = "aoc.toml";
+
+#[derive(Default)]
+struct Options {
+    force: bool,
+    day: Option<u32>,
+    inputs: Vec<PathBuf>,
+    report: Option<PathBuf>,
+    profile: bool,
+}
+
+/// Expands a glob pattern, keeping plain paths as they are so that missing
+/// files are reported by the day.
+fn expand(pattern: &str) -> Result<Vec<PathBuf>> {
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+    let mut paths = glob::glob(pattern)?.collect::<Result<Vec<_>, _>>()?;
+    if paths.is_empty() {
+        return Err(anyhow!("No input matches {}", pattern));
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => explain::set_enabled(true),
+            "--artifacts" => output::set_enabled(true),
+            "--output-dir" => {
+                output::set_dir(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--output-dir needs a path"))?,
+                );
+                output::set_enabled(true);
+            }
+            "--force" => options.force = true,
+            "--demo" => input::set_demo(true),
+            "--profile" => {
+                if !cfg!(feature = "profile") {
+                    return Err(anyhow!("--profile needs the profile feature"));
+                }
+                let day = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--profile needs a day"))?;
+                options.day = Some(day.trim_start_matches("day").parse()?);
+                options.profile = true;
+            }
+            "--report" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--report needs a path"))?;
+                options.report = Some(PathBuf::from(path));
+                runner::set_visuals_enabled(true);
+            }
+            "--day" => {
+                let day = args.next().ok_or_else(|| anyhow!("--day needs a day"))?;
+                options.day = Some(day.parse()?);
+            }
+            "--input" => {
+                while let Some(pattern) = args.next_if(|arg| !arg.starts_with("--")) {
+                    options.inputs.extend(expand(&pattern)?);
+                }
+                if options.inputs.is_empty() {
+                    return Err(anyhow!("--input needs at least one path"));
+                }
+            }
+            _ => return Err(anyhow!("Unknown argument {}", arg)),
+        }
+    }
+    if !options.inputs.is_empty() && options.day.is_none() {
+        return Err(anyhow!("--input needs --day"));
+    }
+    Ok(options)
+}
+
+/// Skips a day whose input has not been downloaded yet, or that has no
+/// example in demo mode, instead of stopping the remaining days.
+fn skip_missing(result: Result<()>) -> Result<()> {
+    match result {
+        Err(err) => match err.downcast_ref::<input::Error>() {
+            Some(missing @ input::Error::Missing { .. })
+            | Some(missing @ input::Error::NoExample { .. }) => {
+                eprintln!("{}", missing);
+                Ok(())
+            }
+            _ => Err(err),
+        },
+        ok => ok,
+    }
+}
+
+/// Runs a day unless the cache has its answers for the same input and code.
+///
+/// Explanations, artifacts and report pictures are only produced by running
+/// the day, so they bypass the cache.
+fn run_day(
+    day: u32,
+    solve: fn() -> Result<()>,
+    options: &Options,
+    cache: &mut Cache,
+) -> Result<()> {
+    let input = input::read(day)
+        .ok()
+        .map(|text| cache::hash(text.as_bytes()));
+    let use_cache = !options.force
+        && !input::demo()
+        && !explain::enabled()
+        && !output::enabled()
+        && options.report.is_none()
+        && !options.profile;
+    if let Some(answers) = input
+        .filter(|_| use_cache)
+        .and_then(|input| cache.get(day, input))
+    {
+        for answer in answers {
+            runner::print(answer.part, &answer.value);
+        }
+        return Ok(());
+    }
+    runner::start_day(day);
+    let result = skip_missing(solve());
+    let answers = runner::finish_day();
+    result?;
+    if let Some(input) = input {
+        for answer in &answers {
+            cache.insert(input, answer);
+        }
+    }
+    Ok(())
+}
+
+/// Runs a day on each of `inputs` and compares the answers and timings.
+fn compare(day: u32, solve: fn() -> Result<()>, inputs: &[PathBuf]) -> Result<()> {
+    let mut rows = Vec::new();
+    for path in inputs {
+        println!("{}:", path.display());
+        input::set_override(Some(path));
+        runner::start_day(day);
+        let result = solve();
+        let answers = runner::finish_day();
+        let mut row = vec![path.display().to_string()];
+        for part in 1..=2 {
+            row.push(
+                answers
+                    .iter()
+                    .find(|answer| answer.part == part)
+                    .map_or_else(String::new, |answer| answer.value.replace('\n', " ")),
+            );
+        }
+        let time = answers
+            .iter()
+            .map(|answer| answer.elapsed)
+            .sum::<Duration>();
+        row.push(format!("{:.3?}", time));
+        if let Err(err) = result {
+            row.push(err.to_string());
+        }
+        rows.push(row);
+    }
+    input::set_override(None::<PathBuf>);
+    print!(
+        "\n{}",
+        runner::table(&["Input", "Part 1", "Part 2", "Time", "Error"], &rows)
+    );
+    Ok(())
+}
 
 macro_rules! days {
     ( $($day:ident),* ) => {
         fn main() -> Result<()> {
-            $($day::main()?;)*
+            let options = parse_args()?;
+            let mut cache = Cache::load(CACHE_PATH, cache::code_version())?;
+            let budgets = Rc::new(RefCell::new(Budgets::load(CONFIG_PATH)?));
+            runner::add_hook(budgets.clone());
+            let report = Rc::new(RefCell::new(Report::default()));
+            runner::add_hook(report.clone());
+            #[cfg(feature = "profile")]
+            let profiler = Rc::new(RefCell::new(runner::profile::Profiler::new(
+                options.day.unwrap_or_default(),
+            )));
+            #[cfg(feature = "profile")]
+            if options.profile {
+                runner::add_hook(profiler.clone());
+            }
+            let days: &[(&str, fn() -> Result<()>)] = &[$((stringify!($day), $day::main)),*];
+            if let Some(day) = options.day {
+                if !days.iter().any(|&(name, _)| name == format!("day{:02}", day)) {
+                    return Err(anyhow!("Day {} is not solved", day));
+                }
+            }
+            for &(name, solve) in days {
+                let day = name.trim_start_matches("day").parse()?;
+                if options.day.map_or(false, |only| only != day) {
+                    continue;
+                }
+                if !options.inputs.is_empty() {
+                    compare(day, solve, &options.inputs)?;
+                    continue;
+                }
+                let result = run_day(day, solve, &options, &mut cache);
+                cache.save()?;
+                result?;
+            }
+            #[cfg(feature = "profile")]
+            for written in profiler.borrow().written() {
+                println!("{}", written);
+            }
+            if let Some(path) = &options.report {
+                fs::write(path, report.borrow().html())?;
+                println!("Wrote {}", path.display());
+            }
+            let budgets = budgets.borrow();
+            if !budgets.is_empty() {
+                print!("\n{}", budgets.summary());
+            }
             Ok(())
         }
     }
 }
 
-days! {day01, day02, day03, day04, day05, day06}
+days! {day01, day02, day03, day04, day05, day06, day11, day12, day14, day15, day16, day17, day21, day23, day24}
@@ -0,0 +1,38 @@
+use lazy_static::lazy_static;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref OUTPUT_DIR: RwLock<PathBuf> = RwLock::new(PathBuf::from("out"));
+}
+
+/// Whether days should write their artifacts, set by `--artifacts`.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_dir<P: AsRef<Path>>(dir: P) {
+    *OUTPUT_DIR.write().unwrap() = dir.as_ref().to_owned();
+}
+
+pub fn dir() -> PathBuf {
+    OUTPUT_DIR.read().unwrap().clone()
+}
+
+/// Path for an artifact of the given day, e.g. `out/day03-wires.svg`.
+///
+/// Creates the output directory if it does not exist yet.
+pub fn path(day: u32, name: &str) -> io::Result<PathBuf> {
+    let dir = dir();
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(format!("day{:02}-{}", day, name)))
+}
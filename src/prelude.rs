@@ -0,0 +1,6 @@
+pub use crate::explain::{self, Event};
+pub use crate::input;
+pub use crate::runner::answer;
+pub use crate::util::geom::{Point, Vector};
+pub use crate::vm::{parse_program, Computer, Program};
+pub use anyhow::Result;
@@ -0,0 +1,174 @@
+use super::{table, Answer, Hook};
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Parses durations like `5s`, `1.5s`, `250ms`, `80us` or `2m`.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .ok_or_else(|| anyhow!("Duration {:?} has no unit", text))?;
+    let (number, unit) = text.split_at(split);
+    let number = number
+        .parse::<f64>()
+        .with_context(|| format!("Invalid duration {:?}", text))?;
+    let seconds = match unit.trim() {
+        "us" | "µs" => number / 1e6,
+        "ms" => number / 1e3,
+        "s" => number,
+        "m" => number * 60.0,
+        unit => return Err(anyhow!("Unknown unit {:?} in {:?}", unit, text)),
+    };
+    Duration::try_from_secs_f64(seconds).with_context(|| format!("Invalid duration {:?}", text))
+}
+
+/// Soft time limits per day and part, read from tables like
+///
+/// ```toml
+/// [day18]
+/// part2 = "5s"
+/// ```
+///
+/// As a hook it notes every budgeted part that was run.
+#[derive(Debug, Default)]
+pub struct Budgets {
+    limits: BTreeMap<(u32, u32), Duration>,
+    timed: Vec<(Answer, Duration)>,
+}
+
+fn number(key: &str, prefix: &str) -> Result<u32> {
+    key.strip_prefix(prefix)
+        .and_then(|number| number.parse().ok())
+        .ok_or_else(|| anyhow!("Expected {}<n>, not {:?}", prefix, key))
+}
+
+impl Budgets {
+    pub fn parse(text: &str) -> Result<Self> {
+        let config = text.parse::<toml::Value>()?;
+        let mut limits = BTreeMap::new();
+        let days = config
+            .as_table()
+            .ok_or_else(|| anyhow!("Expected a table of days"))?;
+        for (key, parts) in days {
+            let day = number(key, "day")?;
+            let parts = parts
+                .as_table()
+                .ok_or_else(|| anyhow!("Expected a table of parts for {}", key))?;
+            for (key, budget) in parts {
+                let part = number(key, "part")?;
+                let budget = budget
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Budget for {}.{} must be a string", day, key))?;
+                limits.insert((day, part), parse_duration(budget)?);
+            }
+        }
+        Ok(Self {
+            limits,
+            timed: Vec::new(),
+        })
+    }
+
+    /// Loads the budgets from `path`, which need not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).with_context(|| format!("In {}", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<Duration> {
+        self.limits.get(&(day, part)).copied()
+    }
+
+    /// The parts that took longer than their budget.
+    pub fn exceeded(&self) -> impl Iterator<Item = &(Answer, Duration)> {
+        self.timed
+            .iter()
+            .filter(|(answer, budget)| answer.elapsed > *budget)
+    }
+
+    /// A table of the budgeted parts that were run, flagging those over
+    /// budget.
+    pub fn summary(&self) -> String {
+        let rows = self
+            .timed
+            .iter()
+            .map(|(answer, budget)| {
+                vec![
+                    answer.day.to_string(),
+                    answer.part.to_string(),
+                    format!("{:.3?}", answer.elapsed),
+                    format!("{:?}", budget),
+                    if answer.elapsed > *budget {
+                        "over budget".to_owned()
+                    } else {
+                        String::new()
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        table(&["Day", "Part", "Time", "Budget", "Status"], &rows)
+    }
+}
+
+impl Hook for Budgets {
+    fn after_part(&mut self, answer: &Answer) {
+        if let Some(budget) = self.get(answer.day, answer.part) {
+            self.timed.push((answer.clone(), budget));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() -> Result<()> {
+        assert_eq!(parse_duration("5s")?, Duration::from_secs(5));
+        assert_eq!(parse_duration("1.5s")?, Duration::from_millis(1500));
+        assert_eq!(parse_duration("250ms")?, Duration::from_millis(250));
+        assert_eq!(parse_duration("80us")?, Duration::from_micros(80));
+        assert_eq!(parse_duration("2m")?, Duration::from_secs(120));
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("5h").is_err());
+        assert!(parse_duration("99999999999999999999999s").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_budgets() -> Result<()> {
+        let mut budgets = Budgets::parse("day18.part2 = \"5s\"\n[day3]\npart1 = \"1ms\"\n")?;
+        assert_eq!(budgets.get(18, 2), Some(Duration::from_secs(5)));
+        assert_eq!(budgets.get(18, 1), None);
+        for &(part, millis) in &[(1, 2), (2, 2)] {
+            budgets.after_part(&Answer {
+                day: 3,
+                part,
+                value: String::new(),
+                elapsed: Duration::from_millis(millis),
+            });
+        }
+        assert_eq!(
+            budgets
+                .exceeded()
+                .map(|(answer, _)| (answer.day, answer.part))
+                .collect::<Vec<_>>(),
+            vec![(3, 1)]
+        );
+        assert!(budgets.summary().contains("over budget"));
+        assert!(Budgets::parse("day3.part1 = 5").is_err());
+        assert!(Budgets::parse("third.part1 = \"5s\"").is_err());
+        Ok(())
+    }
+}
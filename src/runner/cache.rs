@@ -0,0 +1,188 @@
+use super::Answer;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 64 bit FNV-1a, which unlike `DefaultHasher` is stable across builds.
+pub fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Identifies the code that computed an answer by hashing the running
+/// executable, so any rebuild with changes invalidates the cache.
+pub fn code_version() -> u64 {
+    env::current_exe()
+        .and_then(fs::read)
+        .map(|bytes| hash(&bytes))
+        .unwrap_or_else(|_| hash(env!("CARGO_PKG_VERSION").as_bytes()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    input: u64,
+    code: u64,
+    value: String,
+}
+
+/// Answers keyed by day and part, stored as tab separated lines of day,
+/// part, input hash, code version and the answer with escaped newlines.
+#[derive(Debug)]
+pub struct Cache {
+    path: PathBuf,
+    code: u64,
+    entries: HashMap<(u32, u32), Entry>,
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                result.push('\\');
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+impl Cache {
+    /// Loads the cache at `path`, which may not exist yet. Unreadable lines
+    /// are dropped.
+    pub fn load<P: AsRef<Path>>(path: P, code: u64) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let fields = line.splitn(5, '\t').collect::<Vec<_>>();
+                match *fields.as_slice() {
+                    [day, part, input, code, value] => Some((
+                        (day.parse().ok()?, part.parse().ok()?),
+                        Entry {
+                            input: u64::from_str_radix(input, 16).ok()?,
+                            code: u64::from_str_radix(code, 16).ok()?,
+                            value: unescape(value),
+                        },
+                    )),
+                    _ => None,
+                }
+            })
+            .collect();
+        Ok(Self {
+            path,
+            code,
+            entries,
+        })
+    }
+
+    /// The answers to both parts of `day` if they were computed by this code
+    /// from the same input.
+    pub fn get(&self, day: u32, input: u64) -> Option<Vec<Answer>> {
+        (1..=2)
+            .map(|part| {
+                self.entries
+                    .get(&(day, part))
+                    .filter(|entry| entry.input == input && entry.code == self.code)
+                    .map(|entry| Answer {
+                        day,
+                        part,
+                        value: entry.value.clone(),
+                        elapsed: Duration::default(),
+                    })
+            })
+            .collect()
+    }
+
+    pub fn insert(&mut self, input: u64, answer: &Answer) {
+        self.entries.insert(
+            (answer.day, answer.part),
+            Entry {
+                input,
+                code: self.code,
+                value: answer.value.clone(),
+            },
+        );
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut keys = self.entries.keys().collect::<Vec<_>>();
+        keys.sort();
+        let text = keys
+            .into_iter()
+            .map(|key| {
+                let entry = &self.entries[key];
+                format!(
+                    "{}\t{}\t{:016x}\t{:016x}\t{}\n",
+                    key.0,
+                    key.1,
+                    entry.input,
+                    entry.code,
+                    escape(&entry.value)
+                )
+            })
+            .collect::<String>();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(day: u32, part: u32, value: &str) -> Answer {
+        Answer {
+            day,
+            part,
+            value: value.to_owned(),
+            elapsed: Duration::default(),
+        }
+    }
+
+    #[test]
+    fn test_cache() -> io::Result<()> {
+        let path = env::temp_dir().join(format!("aoc-cache-{}.tsv", std::process::id()));
+        let mut cache = Cache::load(&path, 1)?;
+        assert_eq!(cache.get(11, 7), None);
+        cache.insert(7, &answer(11, 1, "2184"));
+        assert_eq!(cache.get(11, 7), None);
+        cache.insert(7, &answer(11, 2, "#.#\\\n.#.\n"));
+        cache.save()?;
+
+        let loaded = Cache::load(&path, 1)?;
+        let answers = loaded.get(11, 7).unwrap();
+        assert_eq!(answers[1].value, "#.#\\\n.#.\n");
+        assert_eq!(loaded.get(11, 8), None);
+        assert_eq!(Cache::load(&path, 2)?.get(11, 7), None);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
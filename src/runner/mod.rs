@@ -0,0 +1,265 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+pub mod budget;
+pub mod cache;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod report;
+
+/// An answer given by a day, with the time taken since the previous one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Answer {
+    pub day: u32,
+    pub part: u32,
+    pub value: String,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Picture {
+    Svg(String),
+    /// Text art such as a painted hull, shown in a monospace font.
+    Text(String),
+}
+
+/// A picture a day drew of its puzzle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visual {
+    pub day: u32,
+    pub name: String,
+    pub picture: Picture,
+}
+
+static VISUALS: AtomicBool = AtomicBool::new(false);
+
+struct Run {
+    day: u32,
+    mark: Instant,
+    answers: Vec<Answer>,
+}
+
+/// Instrumentation such as timing or reporting that attaches to the runner
+/// instead of to every day.
+pub trait Hook {
+    fn before_day(&mut self, _day: u32) {}
+    fn after_part(&mut self, _answer: &Answer) {}
+    fn visual(&mut self, _visual: &Visual) {}
+}
+
+/// Lets the caller keep a handle to a hook to read its results afterwards.
+impl<H: Hook> Hook for Rc<RefCell<H>> {
+    fn before_day(&mut self, day: u32) {
+        self.borrow_mut().before_day(day);
+    }
+
+    fn after_part(&mut self, answer: &Answer) {
+        self.borrow_mut().after_part(answer);
+    }
+
+    fn visual(&mut self, visual: &Visual) {
+        self.borrow_mut().visual(visual);
+    }
+}
+
+// The runner drives the days from one thread, and tests running days in
+// parallel must not record into each other's runs.
+thread_local! {
+    static CURRENT: RefCell<Option<Run>> = const { RefCell::new(None) };
+    static HOOKS: RefCell<Vec<Box<dyn Hook>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Attaches `hook` to the days run from this thread.
+pub fn add_hook<H: Hook + 'static>(hook: H) {
+    HOOKS.with(|hooks| hooks.borrow_mut().push(Box::new(hook)));
+}
+
+pub fn clear_hooks() {
+    HOOKS.with(|hooks| hooks.borrow_mut().clear());
+}
+
+/// Whether days should draw pictures for a report, which costs time.
+pub fn visuals_enabled() -> bool {
+    VISUALS.load(Ordering::Relaxed)
+}
+
+pub fn set_visuals_enabled(enabled: bool) {
+    VISUALS.store(enabled, Ordering::Relaxed);
+}
+
+/// Hands a picture of the current day to the hooks.
+pub fn visualize(name: &str, picture: Picture) {
+    let day = CURRENT.with(|current| current.borrow().as_ref().map(|run| run.day));
+    if let Some(day) = day {
+        let visual = Visual {
+            day,
+            name: name.to_owned(),
+            picture,
+        };
+        HOOKS.with(|hooks| {
+            for hook in hooks.borrow_mut().iter_mut() {
+                hook.visual(&visual);
+            }
+        });
+    }
+}
+
+/// Lays out `rows` below `headers` in left aligned columns.
+pub fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|header| header.len())
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let headers = headers.iter().map(|&header| header.to_owned()).collect();
+    let rule = widths.iter().map(|&width| "-".repeat(width)).collect();
+    let mut text = String::new();
+    for row in [headers, rule].iter().chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        text += line.trim_end();
+        text.push('\n');
+    }
+    text
+}
+
+/// Prints an answer, putting answers of several lines below the label.
+pub fn print(part: u32, value: &str) {
+    if value.contains('\n') {
+        print!("Part {}:\n{}", part, value);
+        if !value.ends_with('\n') {
+            println!();
+        }
+    } else {
+        println!("Part {}: {}", part, value);
+    }
+}
+
+/// Starts recording the answers of `day`.
+pub fn start_day(day: u32) {
+    HOOKS.with(|hooks| {
+        for hook in hooks.borrow_mut().iter_mut() {
+            hook.before_day(day);
+        }
+    });
+    CURRENT.with(|current| {
+        *current.borrow_mut() = Some(Run {
+            day,
+            mark: Instant::now(),
+            answers: Vec::new(),
+        })
+    });
+}
+
+/// Stops recording and returns the answers given since `start_day`.
+pub fn finish_day() -> Vec<Answer> {
+    CURRENT.with(|current| {
+        current
+            .borrow_mut()
+            .take()
+            .map_or_else(Vec::new, |run| run.answers)
+    })
+}
+
+/// Prints the answer to a part and records it for the runner.
+///
+/// Outside of a day started by the runner, such as in tests, the answer is
+/// only printed.
+pub fn answer<T: Display>(part: u32, value: T) {
+    let now = Instant::now();
+    let value = value.to_string();
+    print(part, &value);
+    let answer = CURRENT.with(|current| {
+        current.borrow_mut().as_mut().map(|run| {
+            let answer = Answer {
+                day: run.day,
+                part,
+                value,
+                elapsed: now - run.mark,
+            };
+            run.answers.push(answer.clone());
+            answer
+        })
+    });
+    if let Some(answer) = answer {
+        HOOKS.with(|hooks| {
+            for hook in hooks.borrow_mut().iter_mut() {
+                hook.after_part(&answer);
+            }
+        });
+    }
+    // Neither printing nor the hooks count towards the next answer's time.
+    CURRENT.with(|current| {
+        if let Some(run) = current.borrow_mut().as_mut() {
+            run.mark = Instant::now();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Calls(Vec<String>);
+
+    impl Hook for Calls {
+        fn before_day(&mut self, day: u32) {
+            self.0.push(format!("day {}", day));
+        }
+
+        fn after_part(&mut self, answer: &Answer) {
+            self.0
+                .push(format!("{}.{} = {}", answer.day, answer.part, answer.value));
+        }
+    }
+
+    #[test]
+    fn test_hooks() {
+        let calls = Rc::new(RefCell::new(Calls::default()));
+        add_hook(calls.clone());
+        answer(1, "outside of a day");
+        start_day(3);
+        answer(1, 159);
+        answer(2, 610);
+        let answers = finish_day();
+        clear_hooks();
+        start_day(4);
+        finish_day();
+
+        assert_eq!(calls.borrow().0, vec!["day 3", "3.1 = 159", "3.2 = 610"]);
+        assert_eq!(
+            answers.iter().map(|answer| answer.part).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(answers[1].value, "610");
+    }
+
+    #[test]
+    fn test_table() {
+        let rows = vec![
+            vec!["data/day01.txt".to_owned(), "3390830".to_owned()],
+            vec!["a.txt".to_owned(), "2".to_owned()],
+        ];
+        assert_eq!(
+            table(&["Input", "Part 1"], &rows),
+            "\
+Input           Part 1
+--------------  -------
+data/day01.txt  3390830
+a.txt           2
+"
+        );
+    }
+}
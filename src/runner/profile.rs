@@ -0,0 +1,73 @@
+use super::{Answer, Hook};
+use crate::output;
+use pprof::{ProfilerGuard, ProfilerGuardBuilder};
+use std::fs::File;
+
+const FREQUENCY: i32 = 1000;
+
+/// Samples one day and writes a flamegraph per part, such as
+/// `out/day12-part2-flamegraph.svg`.
+///
+/// Each part's graph covers the time since the previous answer, so parsing
+/// is part of the first.
+pub struct Profiler {
+    day: u32,
+    guard: Option<ProfilerGuard<'static>>,
+    written: Vec<String>,
+}
+
+impl Profiler {
+    pub fn new(day: u32) -> Self {
+        Self {
+            day,
+            guard: None,
+            written: Vec::new(),
+        }
+    }
+
+    /// The flamegraphs written so far, or the errors writing them.
+    pub fn written(&self) -> &[String] {
+        &self.written
+    }
+
+    fn start(&mut self) {
+        self.guard = ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(|err| self.written.push(format!("Profiler failed: {}", err)))
+            .ok();
+    }
+
+    fn write(&self, guard: &ProfilerGuard<'static>, part: u32) -> Result<String, String> {
+        let report = guard.report().build().map_err(|err| err.to_string())?;
+        let name = format!("part{}-flamegraph.svg", part);
+        let path = output::path(self.day, &name).map_err(|err| err.to_string())?;
+        let file = File::create(&path).map_err(|err| err.to_string())?;
+        report.flamegraph(file).map_err(|err| err.to_string())?;
+        Ok(path.display().to_string())
+    }
+}
+
+impl Hook for Profiler {
+    fn before_day(&mut self, day: u32) {
+        if day == self.day {
+            self.start();
+        }
+    }
+
+    fn after_part(&mut self, answer: &Answer) {
+        if answer.day != self.day {
+            return;
+        }
+        if let Some(guard) = self.guard.take() {
+            let written = self
+                .write(&guard, answer.part)
+                .unwrap_or_else(|err| format!("Flamegraph failed: {}", err));
+            self.written.push(written);
+            // Stop sampling before the next part starts its own graph.
+            drop(guard);
+            self.start();
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::{Answer, Hook, Picture, Visual};
+use std::fmt::Write;
+use std::time::Duration;
+
+const BAR_WIDTH: f64 = 400.0;
+const BAR_HEIGHT: usize = 18;
+
+/// Collects answers, timings and pictures into a self-contained HTML page.
+#[derive(Debug, Default)]
+pub struct Report {
+    answers: Vec<Answer>,
+    visuals: Vec<Visual>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Report {
+    /// Horizontal bars of the time per part, scaled to the slowest one.
+    fn chart(&self) -> String {
+        let slowest = self
+            .answers
+            .iter()
+            .map(|answer| answer.elapsed)
+            .max()
+            .unwrap_or_default()
+            .max(Duration::from_nanos(1));
+        let height = self.answers.len() * BAR_HEIGHT;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            BAR_WIDTH + 200.0,
+            height
+        );
+        for (i, answer) in self.answers.iter().enumerate() {
+            let y = i * BAR_HEIGHT;
+            let width = BAR_WIDTH * answer.elapsed.as_secs_f64() / slowest.as_secs_f64();
+            writeln!(
+                svg,
+                "<text x=\"0\" y=\"{}\">Day {} part {}</text>\
+                 <rect x=\"100\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"steelblue\"/>\
+                 <text x=\"{:.1}\" y=\"{}\">{:.3?}</text>",
+                y + 13,
+                answer.day,
+                answer.part,
+                y + 2,
+                width,
+                BAR_HEIGHT - 4,
+                width + 105.0,
+                y + 13,
+                answer.elapsed
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Advent of Code 2019</title>\n\
+             <style>body { font-family: sans-serif; } \
+             td, th { padding: 2px 8px; text-align: left; vertical-align: top; } \
+             pre { line-height: 1; }</style>\n\
+             </head>\n<body>\n<h1>Advent of Code 2019</h1>\n<h2>Answers</h2>\n\
+             <table>\n<tr><th>Day</th><th>Part</th><th>Answer</th><th>Time</th></tr>\n",
+        );
+        for answer in &self.answers {
+            let value = if answer.value.contains('\n') {
+                format!("<pre>{}</pre>", escape(&answer.value))
+            } else {
+                escape(&answer.value)
+            };
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.3?}</td></tr>",
+                answer.day, answer.part, value, answer.elapsed
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n<h2>Timings</h2>\n");
+        html.push_str(&self.chart());
+        if !self.visuals.is_empty() {
+            html.push_str("<h2>Visualizations</h2>\n");
+        }
+        for visual in &self.visuals {
+            writeln!(
+                html,
+                "<h3>Day {}: {}</h3>",
+                visual.day,
+                escape(&visual.name)
+            )
+            .unwrap();
+            match &visual.picture {
+                Picture::Svg(svg) => html.push_str(svg),
+                Picture::Text(text) => writeln!(html, "<pre>{}</pre>", escape(text)).unwrap(),
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+impl Hook for Report {
+    fn after_part(&mut self, answer: &Answer) {
+        self.answers.push(answer.clone());
+    }
+
+    fn visual(&mut self, visual: &Visual) {
+        self.visuals.push(visual.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        let mut report = Report::default();
+        report.after_part(&Answer {
+            day: 11,
+            part: 2,
+            value: "#..#\n<>\n".to_owned(),
+            elapsed: Duration::from_millis(12),
+        });
+        report.visual(&Visual {
+            day: 3,
+            name: "Wires".to_owned(),
+            picture: Picture::Svg("<svg></svg>".to_owned()),
+        });
+        let html = report.html();
+        assert!(html.contains("<td><pre>#..#\n&lt;&gt;\n</pre></td><td>12.000ms</td>"));
+        assert!(html.contains("<h3>Day 3: Wires</h3>\n<svg></svg>"));
+        assert!(html.contains("width=\"400.0\""));
+    }
+}
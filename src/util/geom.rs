@@ -0,0 +1,128 @@
+use nalgebra::{Point2, Vector2};
+use num::Integer;
+use std::cmp::Ordering;
+
+pub type Value = i64;
+pub type Point = Point2<Value>;
+pub type Vector = Vector2<Value>;
+
+/// Direction from `from` to `to`, divided by the gcd of its components.
+///
+/// Points on the same ray from `from` share the same reduced direction.
+/// Returns the zero vector if both points are equal.
+pub fn reduced_direction(from: &Point, to: &Point) -> Vector {
+    let offset = to - from;
+    let gcd = offset.x.gcd(&offset.y);
+    if gcd == 0 {
+        offset
+    } else {
+        offset / gcd
+    }
+}
+
+fn cross(a: &Vector, b: &Vector) -> Value {
+    a.x * b.y - a.y * b.x
+}
+
+/// Orders directions clockwise, starting at "up".
+///
+/// Uses screen coordinates, i.e. `y` grows downwards, so "up" is `(0, -1)`.
+/// Directions pointing the same way compare equal regardless of their length.
+/// The zero vector sorts before every other direction.
+#[derive(Debug, Copy, Clone)]
+pub struct Angle(pub Vector);
+
+impl Angle {
+    fn half(&self) -> u8 {
+        let (x, y) = (self.0.x, self.0.y);
+        if x == 0 && y == 0 {
+            0
+        } else if x > 0 || (x == 0 && y < 0) {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+impl Ord for Angle {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.half()
+            .cmp(&other.half())
+            .then_with(|| 0.cmp(&cross(&self.0, &other.0)))
+    }
+}
+
+impl PartialOrd for Angle {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Angle {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Angle {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduced_direction() {
+        let origin = Point::new(1, 1);
+        assert_eq!(
+            reduced_direction(&origin, &Point::new(7, 4)),
+            Vector::new(2, 1)
+        );
+        assert_eq!(
+            reduced_direction(&origin, &Point::new(-5, 1)),
+            Vector::new(-1, 0)
+        );
+        assert_eq!(
+            reduced_direction(&origin, &Point::new(-3, -7)),
+            Vector::new(-1, -2)
+        );
+        assert_eq!(reduced_direction(&origin, &origin), Vector::new(0, 0));
+    }
+
+    #[test]
+    fn test_clockwise_order() {
+        let mut angles = vec![
+            Vector::new(-1, -1),
+            Vector::new(-1, 0),
+            Vector::new(-1, 1),
+            Vector::new(0, 1),
+            Vector::new(1, 1),
+            Vector::new(1, 0),
+            Vector::new(1, -1),
+            Vector::new(1, -3),
+            Vector::new(0, -1),
+        ];
+        angles.sort_by_key(|&vector| Angle(vector));
+        assert_eq!(
+            angles,
+            vec![
+                Vector::new(0, -1),
+                Vector::new(1, -3),
+                Vector::new(1, -1),
+                Vector::new(1, 0),
+                Vector::new(1, 1),
+                Vector::new(0, 1),
+                Vector::new(-1, 1),
+                Vector::new(-1, 0),
+                Vector::new(-1, -1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_ray_is_equal() {
+        assert_eq!(Angle(Vector::new(2, -4)), Angle(Vector::new(1, -2)));
+        assert_ne!(Angle(Vector::new(-1, 2)), Angle(Vector::new(1, -2)));
+        assert!(Angle(Vector::new(0, 0)) < Angle(Vector::new(0, -1)));
+    }
+}
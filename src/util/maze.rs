@@ -0,0 +1,218 @@
+use super::geom::{Point, Vector};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+#[derive(Clone, Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("Label {0} at {1} is not next to an open tile")]
+    DanglingLabel(String, Point),
+    #[error("Maze contains no open tiles")]
+    Empty,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Side {
+    Inner,
+    Outer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub name: String,
+    /// The open tile the label is attached to.
+    pub position: Point,
+    pub side: Side,
+}
+
+/// An ASCII maze with two-letter labels next to some of its open (`.`) tiles.
+///
+/// Labels are read left-to-right or top-to-bottom. A label is `Outer` if its
+/// tile lies on the outer edge of the maze and `Inner` otherwise.
+#[derive(Debug, Clone)]
+pub struct LabeledMaze {
+    pub open: HashSet<Point>,
+    pub labels: Vec<Label>,
+}
+
+struct CharGrid<'a> {
+    lines: Vec<&'a [u8]>,
+}
+
+impl<'a> CharGrid<'a> {
+    fn get(&self, point: Point) -> u8 {
+        if point.x < 0 || point.y < 0 {
+            return b' ';
+        }
+        self.lines
+            .get(point.y as usize)
+            .and_then(|line| line.get(point.x as usize))
+            .cloned()
+            .unwrap_or(b' ')
+    }
+
+    fn points(&self) -> impl Iterator<Item = (Point, u8)> + '_ {
+        self.lines.iter().enumerate().flat_map(|(y, line)| {
+            line.iter()
+                .enumerate()
+                .map(move |(x, &c)| (Point::new(x as i64, y as i64), c))
+        })
+    }
+}
+
+impl LabeledMaze {
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let grid = CharGrid {
+            lines: input.lines().map(str::as_bytes).collect(),
+        };
+        let open: HashSet<Point> = grid
+            .points()
+            .filter(|&(_, c)| c == b'.')
+            .map(|(point, _)| point)
+            .collect();
+        let (min, max) = grid
+            .points()
+            .filter(|&(_, c)| c == b'.' || c == b'#')
+            .fold(None, |acc: Option<(Point, Point)>, (point, _)| {
+                Some(match acc {
+                    None => (point, point),
+                    Some((min, max)) => (
+                        Point::new(min.x.min(point.x), min.y.min(point.y)),
+                        Point::new(max.x.max(point.x), max.y.max(point.y)),
+                    ),
+                })
+            })
+            .ok_or(Error::Empty)?;
+        let mut labels = Vec::new();
+        for (first, c) in grid.points() {
+            if !c.is_ascii_uppercase() {
+                continue;
+            }
+            for &step in &[Vector::new(1, 0), Vector::new(0, 1)] {
+                let second = first + step;
+                if !grid.get(second).is_ascii_uppercase() {
+                    continue;
+                }
+                let name = String::from_utf8(vec![c, grid.get(second)]).unwrap();
+                let position = [first - step, second + step]
+                    .iter()
+                    .cloned()
+                    .find(|point| open.contains(point))
+                    .ok_or_else(|| Error::DanglingLabel(name.clone(), first))?;
+                let side = if position.x == min.x
+                    || position.y == min.y
+                    || position.x == max.x
+                    || position.y == max.y
+                {
+                    Side::Outer
+                } else {
+                    Side::Inner
+                };
+                labels.push(Label {
+                    name,
+                    position,
+                    side,
+                });
+            }
+        }
+        Ok(Self { open, labels })
+    }
+
+    pub fn find(&self, name: &str) -> impl Iterator<Item = &Label> {
+        let name = name.to_owned();
+        self.labels.iter().filter(move |label| label.name == name)
+    }
+
+    /// Maps each labeled tile to the tile carrying the same label on the
+    /// other side, together with the side of the tile it leaves from.
+    pub fn portals(&self) -> HashMap<Point, (Point, Side)> {
+        let mut by_name: HashMap<&str, Vec<&Label>> = HashMap::new();
+        for label in &self.labels {
+            by_name.entry(&label.name).or_default().push(label);
+        }
+        by_name
+            .values()
+            .filter(|labels| labels.len() == 2)
+            .flat_map(|labels| {
+                vec![
+                    (labels[0].position, (labels[1].position, labels[0].side)),
+                    (labels[1].position, (labels[0].position, labels[1].side)),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r"
+         A
+         A
+  #######.#########
+  #######.........#
+  #######.#######.#
+  #######.#######.#
+  #######.#######.#
+  #####  B    ###.#
+BC...##  C    ###.#
+  ##.##       ###.#
+  ##...DE  F  ###.#
+  #####    G  ###.#
+  #########.#####.#
+DE..#######...###.#
+  #.#########.###.#
+FG..#########.....#
+  ###########.#####
+             Z
+             Z
+";
+
+    fn label(name: &str, x: i64, y: i64, side: Side) -> Label {
+        Label {
+            name: name.to_owned(),
+            position: Point::new(x, y),
+            side,
+        }
+    }
+
+    #[test]
+    fn test_labels() -> Result<(), Error> {
+        let maze = LabeledMaze::parse(&EXAMPLE[1..])?;
+        let mut labels = maze.labels.clone();
+        labels.sort_by_key(|label| (label.name.clone(), label.position.x, label.position.y));
+        assert_eq!(
+            labels,
+            vec![
+                label("AA", 9, 2, Side::Outer),
+                label("BC", 2, 8, Side::Outer),
+                label("BC", 9, 6, Side::Inner),
+                label("DE", 2, 13, Side::Outer),
+                label("DE", 6, 10, Side::Inner),
+                label("FG", 2, 15, Side::Outer),
+                label("FG", 11, 12, Side::Inner),
+                label("ZZ", 13, 16, Side::Outer),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_portals() -> Result<(), Error> {
+        let maze = LabeledMaze::parse(&EXAMPLE[1..])?;
+        let portals = maze.portals();
+        assert_eq!(portals.len(), 6);
+        assert_eq!(portals[&Point::new(9, 6)], (Point::new(2, 8), Side::Inner));
+        assert_eq!(portals[&Point::new(2, 8)], (Point::new(9, 6), Side::Outer));
+        assert_eq!(maze.find("ZZ").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dangling() {
+        assert_eq!(
+            LabeledMaze::parse("#.#\nAB#").err(),
+            Some(Error::DanglingLabel("AB".to_owned(), Point::new(0, 1)))
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod geom;
+pub mod maze;
+pub mod search;
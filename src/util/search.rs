@@ -0,0 +1,316 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    /// Neighbours of `node` with the cost of moving there.
+    ///
+    /// The searches visit neighbours in the returned order, so returning them
+    /// in a fixed order keeps results reproducible.
+    fn neighbors(&self, node: &Self::Node) -> Vec<(Self::Node, usize)>;
+}
+
+pub trait Heuristic: Graph {
+    /// Lower bound for the cost from `node` to `goal`.
+    fn estimate(&self, node: &Self::Node, goal: &Self::Node) -> usize;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<N> {
+    pub cost: usize,
+    pub nodes: Vec<N>,
+}
+
+pub fn dijkstra<G: Graph>(graph: &G, start: &G::Node, goal: &G::Node) -> Option<Path<G::Node>> {
+    let mut nodes = vec![start.clone()];
+    let mut ids = HashMap::new();
+    ids.insert(start.clone(), 0);
+    let mut costs = vec![0];
+    let mut previous: Vec<Option<usize>> = vec![None];
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((0, 0)));
+    while let Some(Reverse((cost, id))) = queue.pop() {
+        if cost > costs[id] {
+            continue;
+        }
+        if nodes[id] == *goal {
+            let mut path = vec![nodes[id].clone()];
+            let mut current = id;
+            while let Some(prev) = previous[current] {
+                path.push(nodes[prev].clone());
+                current = prev;
+            }
+            path.reverse();
+            return Some(Path { cost, nodes: path });
+        }
+        for (neighbor, step) in graph.neighbors(&nodes[id]) {
+            let next_cost = cost + step;
+            let next_id = *ids.entry(neighbor.clone()).or_insert_with(|| {
+                nodes.push(neighbor);
+                costs.push(usize::MAX);
+                previous.push(None);
+                nodes.len() - 1
+            });
+            if next_cost < costs[next_id] {
+                costs[next_id] = next_cost;
+                previous[next_id] = Some(id);
+                queue.push(Reverse((next_cost, next_id)));
+            }
+        }
+    }
+    None
+}
+
+enum Bound {
+    Found(usize),
+    Next(Option<usize>),
+}
+
+fn ida_search<G: Heuristic>(
+    graph: &G,
+    path: &mut Vec<G::Node>,
+    cost: usize,
+    threshold: usize,
+    goal: &G::Node,
+) -> Bound {
+    let node = path.last().unwrap().clone();
+    let total = cost + graph.estimate(&node, goal);
+    if total > threshold {
+        return Bound::Next(Some(total));
+    }
+    if node == *goal {
+        return Bound::Found(cost);
+    }
+    let mut next_threshold: Option<usize> = None;
+    for (neighbor, step) in graph.neighbors(&node) {
+        if path.contains(&neighbor) {
+            continue;
+        }
+        path.push(neighbor);
+        match ida_search(graph, path, cost + step, threshold, goal) {
+            Bound::Found(cost) => return Bound::Found(cost),
+            Bound::Next(Some(bound)) => {
+                next_threshold = Some(next_threshold.map_or(bound, |curr| curr.min(bound)))
+            }
+            Bound::Next(None) => (),
+        }
+        path.pop();
+    }
+    Bound::Next(next_threshold)
+}
+
+/// Iterative-deepening A*: only keeps the current path in memory.
+///
+/// The heuristic must never overestimate for the result to be optimal.
+pub fn ida_star<G: Heuristic>(graph: &G, start: &G::Node, goal: &G::Node) -> Option<Path<G::Node>> {
+    let mut threshold = graph.estimate(start, goal);
+    let mut path = vec![start.clone()];
+    loop {
+        match ida_search(graph, &mut path, 0, threshold, goal) {
+            Bound::Found(cost) => return Some(Path { cost, nodes: path }),
+            Bound::Next(Some(bound)) => threshold = bound,
+            Bound::Next(None) => return None,
+        }
+    }
+}
+
+fn walk_back<N: Clone + Eq + Hash>(parents: &HashMap<N, Option<N>>, node: &N) -> Vec<N> {
+    let mut path = vec![node.clone()];
+    let mut current = node;
+    while let Some(Some(parent)) = parents.get(current) {
+        path.push(parent.clone());
+        current = parent;
+    }
+    path
+}
+
+/// Breadth-first search from both ends, meeting in the middle.
+///
+/// Ignores edge costs and assumes every edge can be walked in both
+/// directions. The returned cost is the number of edges.
+pub fn bidirectional_bfs<G: Graph>(
+    graph: &G,
+    start: &G::Node,
+    goal: &G::Node,
+) -> Option<Path<G::Node>> {
+    let mut parents = [HashMap::new(), HashMap::new()];
+    let mut frontiers = [vec![start.clone()], vec![goal.clone()]];
+    parents[0].insert(start.clone(), None);
+    parents[1].insert(goal.clone(), None);
+    if start == goal {
+        return Some(Path {
+            cost: 0,
+            nodes: vec![start.clone()],
+        });
+    }
+    while !frontiers[0].is_empty() && !frontiers[1].is_empty() {
+        let side = if frontiers[0].len() <= frontiers[1].len() {
+            0
+        } else {
+            1
+        };
+        let mut next = Vec::new();
+        let mut meeting = None;
+        let mut seen = HashSet::new();
+        for node in &frontiers[side] {
+            for (neighbor, _) in graph.neighbors(node) {
+                if parents[side].contains_key(&neighbor) {
+                    continue;
+                }
+                parents[side].insert(neighbor.clone(), Some(node.clone()));
+                if parents[1 - side].contains_key(&neighbor) {
+                    let length = walk_back(&parents[0], &neighbor).len()
+                        + walk_back(&parents[1], &neighbor).len();
+                    if meeting.as_ref().is_none_or(|&(_, best)| length < best) {
+                        meeting = Some((neighbor.clone(), length));
+                    }
+                }
+                if seen.insert(neighbor.clone()) {
+                    next.push(neighbor);
+                }
+            }
+        }
+        if let Some((node, _)) = meeting {
+            let mut nodes = walk_back(&parents[0], &node);
+            nodes.reverse();
+            nodes.extend(walk_back(&parents[1], &node).into_iter().skip(1));
+            return Some(Path {
+                cost: nodes.len() - 1,
+                nodes,
+            });
+        }
+        frontiers[side] = next;
+    }
+    None
+}
+
+/// The largest `n` for which `fits(n)` holds, assuming it holds for 0 and
+/// fails for every `n` past the first one it fails for.
+///
+/// Doubles an upper bound before the binary search, so the answer needs no
+/// known limit.
+pub fn max_satisfying<F: FnMut(u64) -> bool>(mut fits: F) -> u64 {
+    let (mut low, mut high) = (0, 1);
+    while fits(high) {
+        if high == u64::MAX {
+            return high;
+        }
+        low = high;
+        high = high.saturating_mul(2);
+    }
+    // `fits(low)` holds and `fits(high)` does not.
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if fits(middle) {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::geom::{Point, Vector};
+
+    struct Grid {
+        open: HashSet<Point>,
+    }
+
+    impl Grid {
+        fn parse(input: &str) -> Self {
+            let open = input
+                .lines()
+                .enumerate()
+                .flat_map(|(y, line)| {
+                    line.chars()
+                        .enumerate()
+                        .filter(|&(_, c)| c == '.')
+                        .map(move |(x, _)| Point::new(x as i64, y as i64))
+                })
+                .collect();
+            Self { open }
+        }
+    }
+
+    impl Graph for Grid {
+        type Node = Point;
+
+        fn neighbors(&self, node: &Point) -> Vec<(Point, usize)> {
+            [
+                Vector::new(0, -1),
+                Vector::new(1, 0),
+                Vector::new(0, 1),
+                Vector::new(-1, 0),
+            ]
+            .iter()
+            .map(|offset| node + offset)
+            .filter(|point| self.open.contains(point))
+            .map(|point| (point, 1))
+            .collect()
+        }
+    }
+
+    impl Heuristic for Grid {
+        fn estimate(&self, node: &Point, goal: &Point) -> usize {
+            ((goal.x - node.x).abs() + (goal.y - node.y).abs()) as usize
+        }
+    }
+
+    const MAZE: &str = "\
+.....#....
+.###.#.##.
+.#...#..#.
+.#.####.#.
+.#......#.
+.########.
+..........";
+
+    fn assert_valid(grid: &Grid, path: &Path<Point>, start: Point, goal: Point) {
+        assert_eq!(path.nodes.first(), Some(&start));
+        assert_eq!(path.nodes.last(), Some(&goal));
+        assert_eq!(path.nodes.len(), path.cost + 1);
+        for pair in path.nodes.windows(2) {
+            assert!(grid.neighbors(&pair[0]).contains(&(pair[1], 1)));
+        }
+    }
+
+    #[test]
+    fn test_searches_agree() {
+        let grid = Grid::parse(MAZE);
+        let start = Point::new(0, 0);
+        for &goal in &[Point::new(6, 0), Point::new(2, 2), Point::new(9, 6)] {
+            let expected = dijkstra(&grid, &start, &goal).unwrap();
+            assert_valid(&grid, &expected, start, goal);
+            for path in &[
+                ida_star(&grid, &start, &goal).unwrap(),
+                bidirectional_bfs(&grid, &start, &goal).unwrap(),
+            ] {
+                assert_eq!(path.cost, expected.cost);
+                assert_valid(&grid, path, start, goal);
+            }
+        }
+        assert_eq!(dijkstra(&grid, &start, &Point::new(6, 0)).unwrap().cost, 20);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let grid = Grid::parse("..#..");
+        let (start, goal) = (Point::new(0, 0), Point::new(4, 0));
+        assert_eq!(dijkstra(&grid, &start, &goal), None);
+        assert_eq!(ida_star(&grid, &start, &goal), None);
+        assert_eq!(bidirectional_bfs(&grid, &start, &goal), None);
+    }
+
+    #[test]
+    fn test_max_satisfying() {
+        assert_eq!(max_satisfying(|n| n * n <= 1000), 31);
+        assert_eq!(max_satisfying(|n| n == 0), 0);
+        assert_eq!(max_satisfying(|_| true), u64::MAX);
+        assert_eq!(max_satisfying(|n| n < u64::MAX), u64::MAX - 1);
+    }
+}
@@ -0,0 +1,73 @@
+use super::types::Value;
+use super::{Computer, Memory};
+
+/// Outputs split into text and the values that are not ASCII.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ascii {
+    pub text: String,
+    pub values: Vec<Value>,
+}
+
+impl Ascii {
+    /// The non-ASCII value output last, usually the puzzle answer.
+    pub fn answer(&self) -> Option<Value> {
+        self.values.last().copied()
+    }
+}
+
+/// The characters of `line` followed by a newline.
+pub fn encode_line(line: &str) -> impl Iterator<Item = Value> + '_ {
+    line.bytes().chain(Some(b'\n')).map(Value::from)
+}
+
+/// Collects ASCII values into text; anything outside `0..128` is kept as is.
+pub fn decode<I: IntoIterator<Item = Value>>(values: I) -> Ascii {
+    let mut ascii = Ascii::default();
+    for value in values {
+        match value {
+            0..=127 => ascii.text.push(value as u8 as char),
+            _ => ascii.values.push(value),
+        }
+    }
+    ascii
+}
+
+impl<M: Memory<Value = Value>> Computer<M> {
+    /// Queues `line` as input, terminated by a newline.
+    pub fn push_line(&mut self, line: &str) {
+        self.extend_inputs(encode_line(line));
+    }
+
+    /// Removes the buffered outputs and decodes them.
+    pub fn take_ascii(&mut self) -> Ascii {
+        decode(self.take_outputs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::State;
+
+    #[test]
+    fn test_decode() {
+        let ascii = decode(encode_line("#.\n.#").chain(vec![1234, -1]));
+        assert_eq!(ascii.text, "#.\n.#\n");
+        assert_eq!(ascii.values, vec![1234, -1]);
+        assert_eq!(ascii.answer(), Some(-1));
+    }
+
+    #[test]
+    fn test_echo() {
+        // Echoes input until a newline, then outputs 1000.
+        let program = vec![
+            3, 15, 4, 15, 1008, 15, 10, 16, 1006, 16, 0, 104, 1000, 99, 0, 0, 0,
+        ];
+        let mut comp = Computer::new(program);
+        comp.push_line("WALK");
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let ascii = comp.take_ascii();
+        assert_eq!(ascii.text, "WALK\n");
+        assert_eq!(ascii.answer(), Some(1000));
+    }
+}
@@ -0,0 +1,656 @@
+use super::op::OpCode;
+use super::types::Value;
+use super::DEBUG_PRINT;
+use std::collections::HashMap;
+use thiserror::Error;
+
+const MAX_MACRO_DEPTH: usize = 32;
+
+#[derive(Clone, Error, Debug, PartialEq)]
+pub enum AsmError {
+    #[error("Unknown instruction {name:?} at line {line}")]
+    UnknownInstruction { name: String, line: usize },
+    #[error("{name} expects {expected} operands but got {found} at line {line}")]
+    OperandCount {
+        name: String,
+        expected: usize,
+        found: usize,
+        line: usize,
+    },
+    #[error("Invalid operand {operand:?} at line {line}")]
+    InvalidOperand { operand: String, line: usize },
+    #[error("Cannot write to an immediate operand at line {line}")]
+    ImmediateWrite { line: usize },
+    #[error("Undefined name {name:?} at line {line}")]
+    Undefined { name: String, line: usize },
+    #[error("{name:?} is defined again at line {line}")]
+    Duplicate { name: String, line: usize },
+    #[error("Missing {end} for the block starting at line {line}")]
+    Unterminated { end: &'static str, line: usize },
+    #[error("Unexpected {keyword} at line {line}")]
+    Unexpected { keyword: String, line: usize },
+    #[error("Macros nested too deeply at line {line}")]
+    MacroDepth { line: usize },
+}
+
+type Result<T> = std::result::Result<T, AsmError>;
+
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    text: String,
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Line>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(Value),
+    Name(String, Value),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Position(Expr),
+    Immediate(Expr),
+    Relative(Expr),
+}
+
+/// Assembles Intcode source into a program.
+///
+/// Every line holds an optional `label:` followed by an instruction or a
+/// directive; `;` starts a comment. Instructions use the mnemonics `add`,
+/// `mul`, `in`, `out`, `jt`, `jf`, `lt`, `eq`, `arb` and `halt` with comma
+/// separated operands: `#x` is immediate, `@x` relative to the relative base
+/// and a plain `x` is an address. Operands are numbers or names, optionally
+/// with an offset like `table+2`. Additionally:
+///
+/// - `const NAME = 5` defines a constant,
+/// - `var name` or `var name = 5` allocates a cell after the code,
+/// - `data 1, 2, name` emits raw values,
+/// - `mov src, dst` copies a value and `jmp label` always jumps,
+/// - `dbg x` prints `x` when run with the [`DebugPrint`](super::DebugPrint)
+///   extension,
+/// - `macro name a, b` ... `endm` defines a macro; labels inside the body
+///   are local to each expansion,
+/// - `sub name` ... `endsub` defines a subroutine that returns with `ret` and
+///   is invoked with `call name`. The return address is stored in the
+///   subroutine's own cell, so subroutines are not reentrant.
+pub fn assemble(source: &str) -> Result<Vec<Value>> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| Line {
+            number: index + 1,
+            text: text.split(';').next().unwrap_or("").trim().to_owned(),
+        })
+        .filter(|line| !line.text.is_empty())
+        .collect::<Vec<_>>();
+    let (lines, macros) = collect_macros(lines)?;
+    let mut counter = 0;
+    let lines = expand(lines, &macros, 0, &mut counter)?;
+    let mut assembler = Assembler::default();
+    for line in &lines {
+        assembler.statement(line)?;
+    }
+    assembler.finish()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Splits `label: rest` into the label and the rest.
+fn split_label(text: &str) -> (Option<&str>, &str) {
+    match text.find(':') {
+        Some(pos) if is_identifier(text[..pos].trim()) => {
+            (Some(text[..pos].trim()), text[pos + 1..].trim())
+        }
+        _ => (None, text),
+    }
+}
+
+/// Splits `word a, b` into the word and its comma separated arguments.
+fn split_statement(text: &str) -> (&str, Vec<&str>) {
+    let (word, args) = match text.find(char::is_whitespace) {
+        Some(pos) => (&text[..pos], text[pos..].trim()),
+        None => (text, ""),
+    };
+    if args.is_empty() {
+        (word, Vec::new())
+    } else {
+        (word, args.split(',').map(str::trim).collect())
+    }
+}
+
+fn replace_words(text: &str, replacements: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut word = String::new();
+    for c in text.chars().chain(Some(' ')) {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            word.push(c);
+            continue;
+        }
+        result.push_str(replacements.get(&word).unwrap_or(&word));
+        word.clear();
+        result.push(c);
+    }
+    result.pop();
+    result
+}
+
+fn collect_macros(lines: Vec<Line>) -> Result<(Vec<Line>, HashMap<String, Macro>)> {
+    let mut rest = Vec::new();
+    let mut macros = HashMap::new();
+    let mut lines = lines.into_iter();
+    while let Some(line) = lines.next() {
+        let (word, args) = split_statement(&line.text);
+        match word {
+            "macro" => {
+                // The name and the first parameter are only separated by whitespace.
+                let mut head = args.first().copied().unwrap_or("").split_whitespace();
+                let name = head.next().unwrap_or("").to_owned();
+                let params = head
+                    .chain(args.iter().skip(1).copied())
+                    .map(str::to_owned)
+                    .collect();
+                if !is_identifier(&name) {
+                    return Err(AsmError::InvalidOperand {
+                        operand: name,
+                        line: line.number,
+                    });
+                }
+                let mut body = Vec::new();
+                let mut terminated = false;
+                for inner in lines.by_ref() {
+                    if inner.text == "endm" {
+                        terminated = true;
+                        break;
+                    }
+                    body.push(inner);
+                }
+                if !terminated {
+                    return Err(AsmError::Unterminated {
+                        end: "endm",
+                        line: line.number,
+                    });
+                }
+                if macros
+                    .insert(name.clone(), Macro { params, body })
+                    .is_some()
+                {
+                    return Err(AsmError::Duplicate {
+                        name,
+                        line: line.number,
+                    });
+                }
+            }
+            "endm" => {
+                return Err(AsmError::Unexpected {
+                    keyword: word.to_owned(),
+                    line: line.number,
+                })
+            }
+            _ => rest.push(line),
+        }
+    }
+    Ok((rest, macros))
+}
+
+fn expand(
+    lines: Vec<Line>,
+    macros: &HashMap<String, Macro>,
+    depth: usize,
+    counter: &mut usize,
+) -> Result<Vec<Line>> {
+    let mut result = Vec::new();
+    for line in lines {
+        let (label, rest) = split_label(&line.text);
+        let (word, args) = split_statement(rest);
+        let definition = match macros.get(word) {
+            Some(definition) => definition,
+            None => {
+                result.push(line);
+                continue;
+            }
+        };
+        if depth >= MAX_MACRO_DEPTH {
+            return Err(AsmError::MacroDepth { line: line.number });
+        }
+        if args.len() != definition.params.len() {
+            return Err(AsmError::OperandCount {
+                name: word.to_owned(),
+                expected: definition.params.len(),
+                found: args.len(),
+                line: line.number,
+            });
+        }
+        if let Some(label) = label {
+            result.push(Line {
+                number: line.number,
+                text: format!("{}:", label),
+            });
+        }
+        let mut replacements = definition
+            .params
+            .iter()
+            .cloned()
+            .zip(args.iter().map(|arg| arg.to_string()))
+            .collect::<HashMap<_, _>>();
+        for inner in &definition.body {
+            if let (Some(local), _) = split_label(&inner.text) {
+                replacements.insert(local.to_owned(), format!("{}.{}", local, counter));
+            }
+        }
+        *counter += 1;
+        let body = definition
+            .body
+            .iter()
+            .map(|inner| Line {
+                number: line.number,
+                text: replace_words(&inner.text, &replacements),
+            })
+            .collect();
+        result.extend(expand(body, macros, depth + 1, counter)?);
+    }
+    Ok(result)
+}
+
+fn parse_expr(text: &str, line: usize) -> Result<Expr> {
+    let text = text.trim();
+    if let Ok(value) = text.parse() {
+        return Ok(Expr::Number(value));
+    }
+    let split = text
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| c == '+' || c == '-');
+    let (name, offset) = match split {
+        Some((pos, sign)) => {
+            let offset = text[pos + 1..].trim().parse::<Value>().ok();
+            let offset = if sign == '-' {
+                offset.map(|offset| -offset)
+            } else {
+                offset
+            };
+            (text[..pos].trim(), offset)
+        }
+        None => (text, Some(0)),
+    };
+    match offset {
+        Some(offset) if is_identifier(name) => Ok(Expr::Name(name.to_owned(), offset)),
+        _ => Err(AsmError::InvalidOperand {
+            operand: text.to_owned(),
+            line,
+        }),
+    }
+}
+
+fn parse_operand(text: &str, line: usize) -> Result<Operand> {
+    Ok(if let Some(rest) = text.strip_prefix('#') {
+        Operand::Immediate(parse_expr(rest, line)?)
+    } else if let Some(rest) = text.strip_prefix('@') {
+        Operand::Relative(parse_expr(rest, line)?)
+    } else {
+        Operand::Position(parse_expr(text, line)?)
+    })
+}
+
+fn expect(word: &str, args: &[&str], expected: usize, line: usize) -> Result<()> {
+    if args.len() == expected {
+        Ok(())
+    } else {
+        Err(AsmError::OperandCount {
+            name: word.to_owned(),
+            expected,
+            found: args.len(),
+            line,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Assembler {
+    words: Vec<(Expr, usize)>,
+    symbols: HashMap<String, Value>,
+    vars: Vec<(String, Value, usize)>,
+    sub: Option<(String, usize)>,
+}
+
+impl Assembler {
+    fn here(&self) -> Value {
+        self.words.len() as Value
+    }
+
+    fn define(&mut self, name: &str, value: Value, line: usize) -> Result<()> {
+        if !is_identifier(name) {
+            return Err(AsmError::InvalidOperand {
+                operand: name.to_owned(),
+                line,
+            });
+        }
+        if self.symbols.insert(name.to_owned(), value).is_some() {
+            return Err(AsmError::Duplicate {
+                name: name.to_owned(),
+                line,
+            });
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, op: OpCode, operands: Vec<Operand>, line: usize) -> Result<()> {
+        if op.writes() {
+            if let Some(Operand::Immediate(_)) = operands.last() {
+                return Err(AsmError::ImmediateWrite { line });
+            }
+        }
+        self.emit_code(op.code(), operands, line);
+        Ok(())
+    }
+
+    fn emit_code(&mut self, code: Value, operands: Vec<Operand>, line: usize) {
+        let mut word = code;
+        let mut factor = 100;
+        for operand in &operands {
+            word += factor
+                * match operand {
+                    Operand::Position(_) => 0,
+                    Operand::Immediate(_) => 1,
+                    Operand::Relative(_) => 2,
+                };
+            factor *= 10;
+        }
+        self.words.push((Expr::Number(word), line));
+        for operand in operands {
+            let (Operand::Position(expr) | Operand::Immediate(expr) | Operand::Relative(expr)) =
+                operand;
+            self.words.push((expr, line));
+        }
+    }
+
+    fn unexpected(keyword: &str, line: usize) -> AsmError {
+        AsmError::Unexpected {
+            keyword: keyword.to_owned(),
+            line,
+        }
+    }
+
+    fn statement(&mut self, line: &Line) -> Result<()> {
+        let number = line.number;
+        let (label, rest) = split_label(&line.text);
+        if let Some(label) = label {
+            self.define(label, self.here(), number)?;
+        }
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let (word, args) = split_statement(rest);
+        let name = |text: &str| {
+            if is_identifier(text) {
+                Ok(text.to_owned())
+            } else {
+                Err(AsmError::InvalidOperand {
+                    operand: text.to_owned(),
+                    line: number,
+                })
+            }
+        };
+        let immediate = |value| Operand::Immediate(Expr::Number(value));
+        match word {
+            "const" | "var" => {
+                expect(word, &args, 1, number)?;
+                let mut parts = args[0].splitn(2, '=');
+                let target = name(parts.next().unwrap_or("").trim())?;
+                let value = match parts.next() {
+                    Some(value) => value.trim().parse().map_err(|_| AsmError::InvalidOperand {
+                        operand: value.trim().to_owned(),
+                        line: number,
+                    })?,
+                    None if word == "var" => 0,
+                    None => return Err(Self::unexpected(args[0], number)),
+                };
+                if word == "const" {
+                    self.define(&target, value, number)?;
+                } else {
+                    self.vars.push((target, value, number));
+                }
+            }
+            "data" => {
+                for arg in args {
+                    let expr = parse_expr(arg, number)?;
+                    self.words.push((expr, number));
+                }
+            }
+            "mov" => {
+                expect(word, &args, 2, number)?;
+                let operands = vec![
+                    parse_operand(args[0], number)?,
+                    immediate(0),
+                    parse_operand(args[1], number)?,
+                ];
+                self.emit(OpCode::Add, operands, number)?;
+            }
+            "jmp" => {
+                expect(word, &args, 1, number)?;
+                let target = Operand::Immediate(parse_expr(args[0], number)?);
+                self.emit(OpCode::JumpIfTrue, vec![immediate(1), target], number)?;
+            }
+            "dbg" => {
+                expect(word, &args, 1, number)?;
+                let operand = parse_operand(args[0], number)?;
+                self.emit_code(DEBUG_PRINT, vec![operand], number);
+            }
+            "sub" => {
+                expect(word, &args, 1, number)?;
+                if self.sub.is_some() {
+                    return Err(Self::unexpected(word, number));
+                }
+                let target = name(args[0])?;
+                self.define(&target, self.here(), number)?;
+                self.vars.push((format!("{}.ret", target), 0, number));
+                self.sub = Some((target, number));
+            }
+            "endsub" => {
+                expect(word, &args, 0, number)?;
+                if self.sub.take().is_none() {
+                    return Err(Self::unexpected(word, number));
+                }
+            }
+            "ret" => {
+                expect(word, &args, 0, number)?;
+                let target = match &self.sub {
+                    Some((target, _)) => format!("{}.ret", target),
+                    None => return Err(Self::unexpected(word, number)),
+                };
+                let operands = vec![immediate(1), Operand::Position(Expr::Name(target, 0))];
+                self.emit(OpCode::JumpIfTrue, operands, number)?;
+            }
+            "call" => {
+                expect(word, &args, 1, number)?;
+                let target = name(args[0])?;
+                // The return address is right after this add and the following jump.
+                let operands = vec![
+                    immediate(self.here() + 7),
+                    immediate(0),
+                    Operand::Position(Expr::Name(format!("{}.ret", target), 0)),
+                ];
+                self.emit(OpCode::Add, operands, number)?;
+                let operands = vec![immediate(1), Operand::Immediate(Expr::Name(target, 0))];
+                self.emit(OpCode::JumpIfTrue, operands, number)?;
+            }
+            _ => {
+                let op = OpCode::ALL
+                    .iter()
+                    .copied()
+                    .find(|op| op.mnemonic() == word)
+                    .ok_or_else(|| AsmError::UnknownInstruction {
+                        name: word.to_owned(),
+                        line: number,
+                    })?;
+                expect(word, &args, op.parameters(), number)?;
+                let operands = args
+                    .iter()
+                    .map(|arg| parse_operand(arg, number))
+                    .collect::<Result<Vec<_>>>()?;
+                self.emit(op, operands, number)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<Value>> {
+        if let Some((_, line)) = self.sub {
+            return Err(AsmError::Unterminated {
+                end: "endsub",
+                line,
+            });
+        }
+        for (name, value, line) in std::mem::take(&mut self.vars) {
+            self.define(&name, self.here(), line)?;
+            self.words.push((Expr::Number(value), line));
+        }
+        let symbols = &self.symbols;
+        self.words
+            .iter()
+            .map(|(expr, line)| match expr {
+                Expr::Number(value) => Ok(*value),
+                Expr::Name(name, offset) => {
+                    let value = symbols.get(name).ok_or_else(|| AsmError::Undefined {
+                        name: name.clone(),
+                        line: *line,
+                    })?;
+                    value
+                        .checked_add(*offset)
+                        .ok_or_else(|| AsmError::InvalidOperand {
+                            operand: format!("{}{:+}", name, offset),
+                            line: *line,
+                        })
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Computer, State};
+
+    fn run(source: &str, inputs: &[Value]) -> Vec<Value> {
+        let mut computer = Computer::new(assemble(source).unwrap());
+        computer.extend_inputs(inputs.iter().copied());
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        computer.take_outputs()
+    }
+
+    #[test]
+    fn test_loop() {
+        let source = "
+            const START = 3
+            var n
+                mov #START, n
+            loop:
+                out n            ; counts down to one
+                add n, #-1, n
+                jt n, #loop
+                halt";
+        assert_eq!(run(source, &[]), vec![3, 2, 1]);
+        assert_eq!(
+            assemble("arb #5\nout @0\nhalt\ndata 42"),
+            Ok(vec![109, 5, 204, 0, 99, 42])
+        );
+        assert_eq!(assemble("dbg @1\nhalt"), Ok(vec![298, 1, 99]));
+    }
+
+    #[test]
+    fn test_macro() {
+        let source = "
+            macro countdown counter
+                again:
+                out counter
+                add counter, #-1, counter
+                jt counter, #again
+            endm
+            var a = 2
+            var b = 1
+                countdown a
+                countdown b
+                halt";
+        assert_eq!(run(source, &[]), vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn test_call() {
+        let source = "
+            var x
+                in x
+                call double
+                call double
+                out x
+                halt
+            sub double
+                mul x, #2, x
+                ret
+            endsub";
+        assert_eq!(run(source, &[3]), vec![12]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            assemble("add x, #1, #2"),
+            Err(AsmError::ImmediateWrite { line: 1 })
+        );
+        assert_eq!(
+            assemble("halt\nout y"),
+            Err(AsmError::Undefined {
+                name: "y".to_owned(),
+                line: 2
+            })
+        );
+        assert_eq!(
+            assemble("frob 1"),
+            Err(AsmError::UnknownInstruction {
+                name: "frob".to_owned(),
+                line: 1
+            })
+        );
+        assert_eq!(
+            assemble("a: halt\na: halt"),
+            Err(AsmError::Duplicate {
+                name: "a".to_owned(),
+                line: 2
+            })
+        );
+        assert_eq!(
+            assemble("macro m\nhalt"),
+            Err(AsmError::Unterminated {
+                end: "endm",
+                line: 1
+            })
+        );
+        assert_eq!(
+            assemble("macro m a\nout a\nendm\nm 1, 2"),
+            Err(AsmError::OperandCount {
+                name: "m".to_owned(),
+                expected: 1,
+                found: 2,
+                line: 4
+            })
+        );
+        assert_eq!(
+            assemble("const BIG = 9223372036854775807\ndata BIG+1"),
+            Err(AsmError::InvalidOperand {
+                operand: "BIG+1".to_owned(),
+                line: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use super::errors::{Error, Result};
+use super::types::Value;
+use super::{Computer, Memory, State};
+use futures::channel::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use futures::{SinkExt, StreamExt};
+use std::future::Future;
+
+pub trait AsyncInput {
+    fn read(&mut self) -> impl Future<Output = Result<Value>>;
+}
+
+pub trait AsyncOutput {
+    fn write(&mut self, value: Value) -> impl Future<Output = Result<()>>;
+}
+
+impl AsyncInput for Receiver<Value> {
+    async fn read(&mut self) -> Result<Value> {
+        self.next().await.ok_or(Error::InputClosed)
+    }
+}
+
+impl AsyncInput for UnboundedReceiver<Value> {
+    async fn read(&mut self) -> Result<Value> {
+        self.next().await.ok_or(Error::InputClosed)
+    }
+}
+
+impl AsyncOutput for Sender<Value> {
+    async fn write(&mut self, value: Value) -> Result<()> {
+        self.send(value).await.map_err(|_| Error::OutputClosed)
+    }
+}
+
+impl AsyncOutput for UnboundedSender<Value> {
+    async fn write(&mut self, value: Value) -> Result<()> {
+        self.unbounded_send(value).map_err(|_| Error::OutputClosed)
+    }
+}
+
+impl<M: Memory<Value = Value>> Computer<M> {
+    /// Runs until the program halts, awaiting input and output.
+    ///
+    /// Queued inputs are used before reading from `input`. The computer only
+    /// yields to the executor while `input` or `output` is pending, so many
+    /// computers can share a single thread.
+    pub async fn run_async<I, O>(&mut self, input: &mut I, output: &mut O) -> Result<()>
+    where
+        I: AsyncInput,
+        O: AsyncOutput,
+    {
+        loop {
+            let mut produced = None;
+            let state = self.with_queued_inputs(|comp, read| {
+                comp.step(read, &mut |value| {
+                    produced = Some(value);
+                    Ok(())
+                })
+            })?;
+            if let Some(value) = produced {
+                output.write(value).await?;
+            }
+            match state {
+                State::Halted => return Ok(()),
+                State::NeedsInput => {
+                    let value = input.read().await?;
+                    self.push_input(value);
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc::unbounded;
+    use futures::executor::block_on;
+    use futures::future::{try_join, try_join_all};
+
+    #[test]
+    fn test_feedback_single_thread() -> Result<()> {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let phases = [9, 8, 7, 6, 5];
+        let (senders, mut receivers): (Vec<_>, Vec<_>) = phases.iter().map(|_| unbounded()).unzip();
+        let (result_sender, mut result) = unbounded();
+        let mut computers = phases
+            .iter()
+            .map(|&phase| {
+                let mut computer = Computer::new(program.clone());
+                computer.push_input(phase);
+                computer
+            })
+            .collect::<Vec<_>>();
+        senders[0].unbounded_send(0).unwrap();
+        // The last amplifier writes to its own channel, forwarded below.
+        let mut outputs = senders[1..].to_vec();
+        outputs.push(result_sender);
+
+        let forward = async {
+            let mut last = None;
+            while let Some(value) = result.next().await {
+                last = Some(value);
+                // The first amplifier may already have halted.
+                let _ = senders[0].unbounded_send(value);
+            }
+            Ok(last)
+        };
+        let amplifiers = async {
+            try_join_all(
+                computers
+                    .iter_mut()
+                    .zip(receivers.iter_mut())
+                    .zip(outputs.iter_mut())
+                    .map(|((computer, input), output)| computer.run_async(input, output)),
+            )
+            .await?;
+            outputs.clear();
+            Ok(())
+        };
+        let ((), last) = block_on(try_join(amplifiers, forward))?;
+        assert_eq!(last, Some(139_629_729));
+        Ok(())
+    }
+
+    #[test]
+    fn test_input_closed() {
+        let (sender, mut input) = unbounded::<Value>();
+        drop(sender);
+        let (mut output, _receiver) = unbounded();
+        let mut computer = Computer::new(vec![3, 0, 99]);
+        assert_eq!(
+            block_on(computer.run_async(&mut input, &mut output)),
+            Err(Error::InputClosed)
+        );
+    }
+}
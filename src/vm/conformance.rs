@@ -0,0 +1,103 @@
+use super::ascii::decode;
+use super::asm::assemble;
+use super::memory::{GrowableMemory, SharedMemory, SparseMemory};
+use super::types::Value;
+use super::{parse_program, Computer, Engine, Memory, State};
+
+// Test programs from `fixtures/intcode`. The `.int` files are programs
+// written by others: the quine from day 9 and the sum of primes and
+// Ackermann function programs shared on the Advent of Code subreddit. The
+// `.asm` files are written in the assembler and cover text output, the same
+// sieve and long division.
+const QUINE: &str = include_str!("../../fixtures/intcode/quine.int");
+const SUM_OF_PRIMES: &str = include_str!("../../fixtures/intcode/sum_of_primes.int");
+const ACKERMANN: &str = include_str!("../../fixtures/intcode/ackermann.int");
+const HELLO: &str = include_str!("../../fixtures/intcode/hello.asm");
+const SUM_OF_PRIMES_ASM: &str = include_str!("../../fixtures/intcode/sum_of_primes.asm");
+const FACTOR: &str = include_str!("../../fixtures/intcode/factor.asm");
+
+fn outputs<M: Memory<Value = Value> + Clone>(
+    memory: M,
+    engine: Engine,
+    inputs: &[Value],
+) -> Vec<Value> {
+    let mut computer = Computer::new(memory);
+    computer.set_engine(engine);
+    computer.extend_inputs(inputs.iter().copied());
+    assert_eq!(computer.run_until_blocked(), State::Halted);
+    computer.take_outputs()
+}
+
+/// Runs `program` on every memory and engine and checks that they agree.
+fn run(program: &[Value], inputs: &[Value]) -> Vec<Value> {
+    let mut results = Vec::new();
+    for engine in [Engine::Interpreter, Engine::Decoded] {
+        results.push(outputs(
+            GrowableMemory::new(program.to_vec()),
+            engine,
+            inputs,
+        ));
+        results.push(outputs(
+            SparseMemory::from(program.to_vec()),
+            engine,
+            inputs,
+        ));
+        results.push(outputs(
+            SharedMemory::from(program.to_vec()),
+            engine,
+            inputs,
+        ));
+    }
+    assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+    results.remove(0)
+}
+
+#[test]
+fn test_quine() {
+    let program = parse_program(QUINE).unwrap();
+    assert_eq!(run(&program, &[]), program);
+}
+
+/// Sums the primes below the input.
+#[test]
+fn test_sum_of_primes() {
+    let program = parse_program(SUM_OF_PRIMES).unwrap();
+    assert_eq!(run(&program, &[10]), vec![17]);
+    assert_eq!(run(&program, &[100]), vec![1060]);
+    assert_eq!(run(&program, &[10000]), vec![5736396]);
+}
+
+/// Computes the Ackermann function A(m, n) of the inputs m and n, with its
+/// call stack in memory past the program.
+#[test]
+fn test_ackermann() {
+    let program = parse_program(ACKERMANN).unwrap();
+    assert_eq!(run(&program, &[0, 7]), vec![8]);
+    assert_eq!(run(&program, &[2, 4]), vec![11]);
+    assert_eq!(run(&program, &[3, 3]), vec![61]);
+}
+
+#[test]
+fn test_hello_world() {
+    let program = assemble(HELLO).unwrap();
+    assert_eq!(decode(run(&program, &[])).text, "Hello, World!\n");
+}
+
+#[test]
+fn test_assembled_sum_of_primes() {
+    let program = assemble(SUM_OF_PRIMES_ASM).unwrap();
+    assert_eq!(run(&program, &[2]), vec![0]);
+    assert_eq!(run(&program, &[10]), vec![17]);
+    assert_eq!(run(&program, &[10000]), vec![5736396]);
+}
+
+#[test]
+fn test_factorization() {
+    let program = assemble(FACTOR).unwrap();
+    assert!(run(&program, &[1]).is_empty());
+    assert_eq!(run(&program, &[1_000_003]), vec![1_000_003]);
+    assert_eq!(
+        run(&program, &[19_338_240]),
+        vec![2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 5, 1259]
+    );
+}
@@ -0,0 +1,487 @@
+use super::instruction::Instruction;
+use super::mode::Mode;
+use super::op::OpCode;
+use super::types::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+pub(super) enum Jump {
+    Never,
+    Always,
+    Conditional,
+}
+
+/// Decodes an instruction the VM could execute without faulting.
+fn decode(program: &[Value], address: usize) -> Option<Instruction> {
+    let instruction = Instruction::decode(|address| program.get(address).copied(), address)?;
+    let writes_immediate = instruction.op.writes()
+        && instruction.params.last().map(|&(mode, _)| mode) == Some(Mode::Immediate);
+    if writes_immediate {
+        None
+    } else {
+        Some(instruction)
+    }
+}
+
+impl Instruction {
+    pub(super) fn jump(&self) -> Option<Jump> {
+        let nonzero = match self.op {
+            OpCode::JumpIfTrue => true,
+            OpCode::JumpIfFalse => false,
+            _ => return None,
+        };
+        Some(match self.params[0] {
+            (Mode::Immediate, value) if (value != 0) == nonzero => Jump::Always,
+            (Mode::Immediate, _) => Jump::Never,
+            _ => Jump::Conditional,
+        })
+    }
+
+    /// The jump target if it is known without running the program.
+    pub(super) fn target(&self) -> Option<usize> {
+        match (self.jump()?, self.params[1]) {
+            (Jump::Never, _) => None,
+            (_, (Mode::Immediate, target)) => usize::try_from(target).ok(),
+            _ => None,
+        }
+    }
+
+    /// The condition under which the jump is taken.
+    fn condition(&self, taken: bool) -> String {
+        let equal = (self.op == OpCode::JumpIfFalse) == taken;
+        format!(
+            "{} {} 0",
+            operand(self.params[0]),
+            if equal { "==" } else { "!=" }
+        )
+    }
+
+    pub(super) fn falls_through(&self) -> bool {
+        match self.jump() {
+            Some(Jump::Always) => false,
+            Some(_) => true,
+            None => self.op != OpCode::Halt,
+        }
+    }
+}
+
+fn operand((mode, value): (Mode, Value)) -> String {
+    match mode {
+        Mode::Position => format!("m[{}]", value),
+        Mode::Immediate => value.to_string(),
+        Mode::Relative if value < 0 => format!("m[rb-{}]", -value),
+        Mode::Relative => format!("m[rb+{}]", value),
+    }
+}
+
+fn assembly((mode, value): (Mode, Value)) -> String {
+    match mode {
+        Mode::Position => value.to_string(),
+        Mode::Immediate => format!("#{}", value),
+        Mode::Relative => format!("@{}", value),
+    }
+}
+
+/// Decodes every instruction reachable from address 0 through fall through
+/// and jumps to immediate targets.
+pub(super) fn reachable(program: &[Value]) -> BTreeMap<usize, Instruction> {
+    let mut instructions = BTreeMap::new();
+    let mut pending = vec![0];
+    while let Some(address) = pending.pop() {
+        if instructions.contains_key(&address) {
+            continue;
+        }
+        let instruction = match decode(program, address) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        if instruction.falls_through() {
+            pending.push(instruction.next());
+        }
+        pending.extend(instruction.target());
+        instructions.insert(address, instruction);
+    }
+    instructions
+}
+
+enum Item {
+    Code(Instruction),
+    Data(usize, Vec<Value>),
+}
+
+impl Item {
+    fn address(&self) -> usize {
+        match self {
+            Item::Code(instruction) => instruction.address,
+            Item::Data(address, _) => *address,
+        }
+    }
+}
+
+/// Splits the program into reachable instructions and the data between them.
+fn items(program: &[Value]) -> Vec<Item> {
+    let mut instructions = reachable(program);
+    let mut items = Vec::new();
+    let mut address = 0;
+    while address < program.len() {
+        match instructions.remove(&address) {
+            Some(instruction) => {
+                address = instruction.next();
+                items.push(Item::Code(instruction));
+            }
+            None => {
+                // Instructions starting inside another one are dropped.
+                let end = instructions
+                    .range(address..)
+                    .map(|(&start, _)| start)
+                    .next()
+                    .unwrap_or(program.len());
+                items.push(Item::Data(address, program[address..end].to_vec()));
+                address = end;
+            }
+        }
+    }
+    items
+}
+
+fn assembly_line(instruction: &Instruction) -> String {
+    let params = instruction
+        .params
+        .iter()
+        .map(|&param| assembly(param))
+        .collect::<Vec<_>>();
+    format!("{} {}", instruction.op.mnemonic(), params.join(", "))
+        .trim_end()
+        .to_owned()
+}
+
+/// Disassembles the instruction at the start of `words` and returns it with
+/// its length.
+pub fn disassemble_one(words: &[Value]) -> Option<(String, usize)> {
+    let instruction = decode(words, 0)?;
+    Some((assembly_line(&instruction), instruction.next()))
+}
+
+/// Lists the reachable instructions in assembler syntax; everything else is
+/// shown as `data`.
+pub fn disassemble(program: &[Value]) -> String {
+    let mut result = String::new();
+    for item in items(program) {
+        let text = match &item {
+            Item::Code(instruction) => assembly_line(instruction),
+            Item::Data(_, values) => {
+                let values = values.iter().map(Value::to_string).collect::<Vec<_>>();
+                format!("data {}", values.join(", "))
+            }
+        };
+        writeln!(result, "{:5}: {}", item.address(), text).unwrap();
+    }
+    result
+}
+
+enum Entry {
+    Label(usize),
+    Line(usize, String),
+}
+
+struct Decompiler {
+    items: Vec<Item>,
+    end: usize,
+    entries: Vec<Entry>,
+    labeled: HashSet<usize>,
+    gotos: HashSet<usize>,
+}
+
+impl Decompiler {
+    /// Index of the item starting at `address`, or the item count for the
+    /// end of the program.
+    fn index_of(&self, address: usize) -> Option<usize> {
+        if address == self.end {
+            return Some(self.items.len());
+        }
+        self.items
+            .binary_search_by_key(&address, Item::address)
+            .ok()
+    }
+
+    fn code(&self, index: usize) -> Option<&Instruction> {
+        match self.items.get(index) {
+            Some(Item::Code(instruction)) => Some(instruction),
+            _ => None,
+        }
+    }
+
+    fn line(&mut self, depth: usize, text: String) {
+        self.entries.push(Entry::Line(depth, text));
+    }
+
+    /// Index of the last jump in `start + 1..end` back to the item at `start`.
+    fn back_jump(&self, start: usize, end: usize) -> Option<usize> {
+        let address = self.items[start].address();
+        (start + 1..end)
+            .rev()
+            .find(|&index| self.code(index).and_then(Instruction::target) == Some(address))
+    }
+
+    /// Index of the target of a forward jump at `index` that stays within `end`.
+    fn forward_target(&self, index: usize, end: usize) -> Option<usize> {
+        let target = self.index_of(self.code(index)?.target()?)?;
+        if target > index && target <= end {
+            Some(target)
+        } else {
+            None
+        }
+    }
+
+    fn is_unconditional(&self, index: usize) -> bool {
+        matches!(
+            self.code(index).and_then(Instruction::jump),
+            Some(Jump::Always)
+        )
+    }
+
+    fn emit(&mut self, start: usize, end: usize, depth: usize) {
+        let mut index = start;
+        while index < end {
+            let address = self.items[index].address();
+            if self.labeled.insert(address) {
+                self.entries.push(Entry::Label(address));
+            }
+            index = self.structure(index, end, depth).unwrap_or_else(|| {
+                self.statement(index, depth);
+                index + 1
+            });
+        }
+    }
+
+    /// Emits a loop or conditional starting at `index` and returns the index
+    /// following it.
+    fn structure(&mut self, index: usize, end: usize, depth: usize) -> Option<usize> {
+        let instruction = self.code(index)?.clone();
+        if let Some(back) = self.back_jump(index, end) {
+            let exit = self.forward_target(index, end);
+            if self.is_unconditional(back) && exit == Some(back + 1) {
+                self.line(depth, format!("while {} {{", instruction.condition(false)));
+                self.emit(index + 1, back, depth + 1);
+            } else if self.is_unconditional(back) {
+                self.line(depth, "loop {".to_owned());
+                self.emit_body(index, back, depth + 1);
+            } else {
+                let condition = self.code(back)?.condition(true);
+                self.line(depth, "do {".to_owned());
+                self.emit_body(index, back, depth + 1);
+                self.line(depth, format!("}} while {}", condition));
+                return Some(back + 1);
+            }
+            self.line(depth, "}".to_owned());
+            return Some(back + 1);
+        }
+        if let Some(Jump::Conditional) = instruction.jump() {
+            let target = self.forward_target(index, end)?;
+            self.line(depth, format!("if {} {{", instruction.condition(false)));
+            let otherwise = match self.forward_target(target - 1, end) {
+                Some(after) if target - 1 > index && self.is_unconditional(target - 1) => {
+                    Some(after).filter(|&after| after > target)
+                }
+                _ => None,
+            };
+            match otherwise {
+                Some(after) => {
+                    self.emit(index + 1, target - 1, depth + 1);
+                    self.line(depth, "} else {".to_owned());
+                    self.emit(target, after, depth + 1);
+                    self.line(depth, "}".to_owned());
+                    return Some(after);
+                }
+                None => {
+                    self.emit(index + 1, target, depth + 1);
+                    self.line(depth, "}".to_owned());
+                    return Some(target);
+                }
+            }
+        }
+        None
+    }
+
+    /// Emits a loop body starting with the loop head itself, which must not
+    /// be detected as the same loop again.
+    fn emit_body(&mut self, start: usize, end: usize, depth: usize) {
+        self.statement(start, depth);
+        self.emit(start + 1, end, depth);
+    }
+
+    fn statement(&mut self, index: usize, depth: usize) {
+        let instruction = match &self.items[index] {
+            Item::Code(instruction) => instruction.clone(),
+            Item::Data(_, values) => {
+                let values = values.iter().map(Value::to_string).collect::<Vec<_>>();
+                let text = format!("data [{}]", values.join(", "));
+                self.line(depth, text);
+                return;
+            }
+        };
+        let p = |index: usize| operand(instruction.params[index]);
+        let text = match instruction.op {
+            OpCode::Add => format!("{} = {} + {}", p(2), p(0), p(1)),
+            OpCode::Multiply => format!("{} = {} * {}", p(2), p(0), p(1)),
+            OpCode::LessThan => format!("{} = {} < {}", p(2), p(0), p(1)),
+            OpCode::Equals => format!("{} = {} == {}", p(2), p(0), p(1)),
+            OpCode::Input => format!("{} = input()", p(0)),
+            OpCode::Output => format!("output({})", p(0)),
+            OpCode::AdjustRelativeBase => format!("rb += {}", p(0)),
+            OpCode::Halt => "halt".to_owned(),
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => {
+                let goto = match instruction.target() {
+                    Some(target) => {
+                        self.gotos.insert(target);
+                        format!("goto L{}", target)
+                    }
+                    None => format!("goto *{}", p(1)),
+                };
+                match instruction.jump() {
+                    Some(Jump::Never) => "nop".to_owned(),
+                    Some(Jump::Always) => goto,
+                    _ => format!("if {} {}", instruction.condition(true), goto),
+                }
+            }
+        };
+        self.line(depth, text);
+    }
+}
+
+/// Reconstructs loops and conditionals from the jumps of the reachable code
+/// and prints the program as pseudocode.
+///
+/// `m[x]` is memory, `rb` the relative base. Jumps that do not fit a
+/// structure are shown as `goto` with a label. Code that only becomes valid
+/// after the program modifies itself is shown as data.
+pub fn decompile(program: &[Value]) -> String {
+    let items = items(program);
+    let mut decompiler = Decompiler {
+        end: program.len(),
+        items,
+        entries: Vec::new(),
+        labeled: HashSet::new(),
+        gotos: HashSet::new(),
+    };
+    let count = decompiler.items.len();
+    decompiler.emit(0, count, 0);
+    let mut result = String::new();
+    for entry in &decompiler.entries {
+        match entry {
+            Entry::Label(address) if decompiler.gotos.contains(address) => {
+                writeln!(result, "L{}:", address).unwrap()
+            }
+            Entry::Label(_) => (),
+            Entry::Line(depth, text) => {
+                writeln!(result, "{}{}", "    ".repeat(*depth), text).unwrap()
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::asm::assemble;
+
+    #[test]
+    fn test_disassemble() {
+        let program = vec![1002, 7, 3, 7, 4, 7, 99, 33];
+        assert_eq!(
+            disassemble(&program),
+            "    0: mul 7, #3, 7\n    4: out 7\n    6: halt\n    7: data 33\n"
+        );
+        assert_eq!(disassemble_one(&[204, -2]), Some(("out @-2".to_owned(), 2)));
+        assert_eq!(disassemble_one(&[1101, 1]), None);
+    }
+
+    #[test]
+    fn test_while() {
+        let program = assemble(
+            "
+            var n
+                in n
+            loop:
+                jf n, #done
+                out n
+                add n, #-1, n
+                jmp loop
+            done:
+                halt",
+        )
+        .unwrap();
+        assert_eq!(
+            decompile(&program),
+            "\
+m[15] = input()
+while m[15] != 0 {
+    output(m[15])
+    m[15] = m[15] + -1
+}
+halt
+data [0]
+"
+        );
+    }
+
+    #[test]
+    fn test_if_else() {
+        let program = assemble(
+            "
+            var x
+                in x
+                jt x, #nonzero
+                out #0
+                jmp end
+            nonzero:
+                out #1
+            end:
+                halt",
+        )
+        .unwrap();
+        assert_eq!(
+            decompile(&program),
+            "\
+m[13] = input()
+if m[13] == 0 {
+    output(0)
+} else {
+    output(1)
+}
+halt
+data [0]
+"
+        );
+    }
+
+    #[test]
+    fn test_do_while_and_goto() {
+        // Counts down, then jumps back into the middle of the loop forever.
+        let program = assemble(
+            "
+            var n = 2
+            loop:
+                out n
+            middle:
+                add n, #-1, n
+                jt n, #loop
+                jmp middle",
+        )
+        .unwrap();
+        assert_eq!(
+            decompile(&program),
+            "\
+do {
+    output(m[12])
+L2:
+    m[12] = m[12] + -1
+} while m[12] != 0
+goto L2
+data [2]
+"
+        );
+    }
+}
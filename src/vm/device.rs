@@ -0,0 +1,167 @@
+use super::errors::{Error, Result};
+use super::types::Value;
+use super::{Computer, Memory};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+/// Handles data reads and writes to a range of addresses mapped with
+/// `Computer::map_device`.
+///
+/// Offsets are relative to the start of the range. Instructions are always
+/// fetched from memory, so code cannot run from a device.
+pub trait Device<V = Value>: Send {
+    fn read(&mut self, offset: usize) -> Result<V>;
+
+    fn write(&mut self, offset: usize, value: V) -> Result<()>;
+}
+
+pub(super) type Mapping<V> = (Range<usize>, Arc<Mutex<dyn Device<V>>>);
+
+/// Reads the milliseconds since it was created; writes restart it.
+pub struct Clock {
+    start: Instant,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Device for Clock {
+    fn read(&mut self, _offset: usize) -> Result<Value> {
+        Ok(Value::try_from(self.start.elapsed().as_millis()).unwrap_or(Value::MAX))
+    }
+
+    fn write(&mut self, _offset: usize, _value: Value) -> Result<()> {
+        self.start = Instant::now();
+        Ok(())
+    }
+}
+
+/// Writes every value stored to it to `writer` as a byte.
+pub struct Console<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> Console<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+}
+
+impl<W: Write + Send> Device for Console<W> {
+    fn read(&mut self, _offset: usize) -> Result<Value> {
+        Err(Error::ReadingNotSupported)
+    }
+
+    fn write(&mut self, _offset: usize, value: Value) -> Result<()> {
+        let byte = u8::try_from(value).map_err(|_| Error::InvalidByte(value))?;
+        self.writer
+            .write_all(&[byte])
+            .map_err(|_| Error::OutputClosed)
+    }
+}
+
+impl<M: Memory> Computer<M> {
+    /// Sends data accesses to `range` to `device` instead of the memory.
+    ///
+    /// The device is shared between clones of the computer. Fails if the
+    /// range overlaps one that is already mapped.
+    pub fn map_device<D: Device<M::Value> + 'static>(
+        &mut self,
+        range: Range<usize>,
+        device: D,
+    ) -> Result<Arc<Mutex<D>>> {
+        let overlaps = self
+            .devices
+            .iter()
+            .any(|(mapped, _)| mapped.start < range.end && range.start < mapped.end);
+        if overlaps || range.start >= range.end {
+            return Err(Error::InvalidMapping(range));
+        }
+        let device = Arc::new(Mutex::new(device));
+        self.devices.push((range, device.clone()));
+        Ok(device)
+    }
+
+    pub fn unmap_devices(&mut self) {
+        self.devices.clear();
+    }
+
+    /// The mapping covering `address`, if any.
+    pub(super) fn device_at(&self, address: usize) -> Option<&Mapping<M::Value>> {
+        self.devices
+            .iter()
+            .find(|(range, _)| range.contains(&address))
+    }
+}
+
+pub(super) fn read<V>((range, device): &Mapping<V>, address: usize) -> Result<V> {
+    device
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .read(address - range.start)
+}
+
+pub(super) fn write<V>((range, device): &Mapping<V>, address: usize, value: V) -> Result<()> {
+    device
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .write(address - range.start, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::State;
+
+    /// Remembers the last value written to each of its cells.
+    struct Registers([Value; 2]);
+
+    impl Device for Registers {
+        fn read(&mut self, offset: usize) -> Result<Value> {
+            Ok(self.0[offset])
+        }
+
+        fn write(&mut self, offset: usize, value: Value) -> Result<()> {
+            self.0[offset] = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_devices() -> Result<()> {
+        // Prints "Hi", copies a register to 17 and sets the other one.
+        let program = vec![
+            1101, 72, 0, 100, 1101, 105, 0, 100, 1001, 201, 1, 17, 1101, 5, 0, 200, 99, 0,
+        ];
+        let mut computer = Computer::new(program);
+        let console = computer.map_device(100..101, Console::new(Vec::new()))?;
+        let registers = computer.map_device(200..202, Registers([0, 41]))?;
+        assert_eq!(
+            computer.map_device(201..300, Clock::new()).err(),
+            Some(Error::InvalidMapping(201..300))
+        );
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert_eq!(console.lock().unwrap().writer(), &b"Hi".to_vec());
+        assert_eq!(computer.memory().read(17), Ok(42));
+        assert_eq!(registers.lock().unwrap().0, [5, 41]);
+        Ok(())
+    }
+}
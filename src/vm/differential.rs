@@ -0,0 +1,161 @@
+use super::types::Value;
+use super::{Computer, Memory, State};
+use std::ops::Range;
+
+/// The first thing two computers running the same program disagreed on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference<V = Value> {
+    State(State, State),
+    Ip(usize, usize),
+    RelativeBase(V, V),
+    Outputs(Vec<V>, Vec<V>),
+    /// A cell that differs once both stopped; `None` if it could not be read.
+    Memory {
+        address: usize,
+        left: Option<V>,
+        right: Option<V>,
+    },
+}
+
+/// Where two computers diverged. `step` counts instructions from zero; for
+/// memory differences it is the step both computers stopped at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence<V = Value> {
+    pub step: usize,
+    pub ip: usize,
+    pub difference: Difference<V>,
+}
+
+/// Runs two computers instruction by instruction and checks that they stay
+/// in the same state, e.g. to compare memories or engines.
+///
+/// After every instruction the states, ips, relative bases and outputs must
+/// match; once both stop, so must the memory in `cells`. Queue the inputs on
+/// both computers beforehand. Stops with `State::StepLimitExceeded` after
+/// `max_steps` instructions.
+pub fn lockstep<A, B>(
+    left: &mut Computer<A>,
+    right: &mut Computer<B>,
+    cells: Range<usize>,
+    max_steps: usize,
+) -> Result<State, Divergence<A::Value>>
+where
+    A: Memory,
+    B: Memory<Value = A::Value>,
+{
+    for step in 0..max_steps {
+        let ip = left.ip();
+        let diverged = |difference| Divergence {
+            step,
+            ip,
+            difference,
+        };
+        let (left_state, right_state) = (left.single_step(), right.single_step());
+        if left_state != right_state {
+            return Err(diverged(Difference::State(left_state, right_state)));
+        }
+        if left.ip() != right.ip() {
+            return Err(diverged(Difference::Ip(left.ip(), right.ip())));
+        }
+        if left.relative_base() != right.relative_base() {
+            let bases = (left.relative_base(), right.relative_base());
+            return Err(diverged(Difference::RelativeBase(bases.0, bases.1)));
+        }
+        let (left_outputs, right_outputs) = (left.take_outputs(), right.take_outputs());
+        if left_outputs != right_outputs {
+            return Err(diverged(Difference::Outputs(left_outputs, right_outputs)));
+        }
+        match left_state {
+            State::Running | State::Watchpoint { .. } => (),
+            state => return compare_memory(left, right, cells, step).map(|()| state),
+        }
+    }
+    compare_memory(left, right, cells, max_steps).map(|()| State::StepLimitExceeded)
+}
+
+fn compare_memory<A, B>(
+    left: &Computer<A>,
+    right: &Computer<B>,
+    cells: Range<usize>,
+    step: usize,
+) -> Result<(), Divergence<A::Value>>
+where
+    A: Memory,
+    B: Memory<Value = A::Value>,
+{
+    for address in cells {
+        let (left_cell, right_cell) = (left.memory().read(address), right.memory().read(address));
+        if left_cell != right_cell {
+            return Err(Divergence {
+                step,
+                ip: left.ip(),
+                difference: Difference::Memory {
+                    address,
+                    left: left_cell.ok(),
+                    right: right_cell.ok(),
+                },
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::memory::{GrowableMemory, SparseMemory};
+    use crate::vm::Engine;
+
+    #[test]
+    fn test_engines_in_lockstep() {
+        let programs: Vec<Vec<Value>> = vec![
+            vec![
+                109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+            ],
+            // Counts down by rewriting the operand of its output instruction.
+            vec![1001, 5, -1, 5, 104, 3, 1005, 5, 0, 99],
+            vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+        ];
+        for program in programs {
+            let mut left = Computer::new(GrowableMemory::new(program.clone()));
+            let mut right = Computer::new(SparseMemory::from(program.clone()));
+            right.set_engine(Engine::Decoded);
+            left.push_input(8);
+            right.push_input(8);
+            let len = program.len();
+            assert_eq!(
+                lockstep(&mut left, &mut right, 0..len, 1000),
+                Ok(State::Halted)
+            );
+        }
+    }
+
+    #[test]
+    fn test_divergence() {
+        let mut left = Computer::new(vec![104, 1, 1101, 1, 1, 7, 99, 0]);
+        let mut right = Computer::new(vec![104, 1, 1101, 1, 2, 7, 99, 0]);
+        let divergence = lockstep(&mut left, &mut right, 0..8, 10).unwrap_err();
+        assert_eq!(
+            divergence,
+            Divergence {
+                step: 2,
+                ip: 6,
+                difference: Difference::Memory {
+                    address: 4,
+                    left: Some(1),
+                    right: Some(2),
+                },
+            }
+        );
+        let mut left = Computer::new(vec![104, 1, 99]);
+        let mut right = Computer::new(vec![104, 2, 99]);
+        assert_eq!(
+            lockstep(&mut left, &mut right, 0..0, 10),
+            Err(Divergence {
+                step: 0,
+                ip: 0,
+                difference: Difference::Outputs(vec![1], vec![2]),
+            })
+        );
+    }
+}
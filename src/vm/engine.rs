@@ -0,0 +1,299 @@
+use super::errors::{Error, Result};
+use super::instruction::Instruction;
+use super::mode::Mode;
+use super::op::OpCode;
+use super::types::{add, mul, Int};
+use super::{to_address, Computer, Memory, State};
+use num::{One, Zero};
+use std::cmp::Ordering;
+
+/// Instructions at or past this address are always interpreted, so jumping
+/// far into a sparse memory does not allocate a slot for every address
+/// before it.
+const MAX_SLOTS: usize = 1 << 20;
+
+/// How a computer executes instructions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Engine {
+    /// Decodes every instruction each time it runs.
+    Interpreter,
+    /// Decodes each instruction once and runs the cached form afterwards.
+    /// Instructions the program overwrites are interpreted from then on.
+    Decoded,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Decoded<V> {
+    op: OpCode,
+    params: [(Mode, V); 3],
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Slot<V> {
+    Empty,
+    Decoded(Decoded<V>),
+    /// The program wrote to one of the instruction's words.
+    Modified,
+}
+
+impl<V: Int> Decoded<V> {
+    fn new(instruction: &Instruction<V>) -> Option<Self> {
+        if instruction.op.writes() && instruction.params.last()?.0 == Mode::Immediate {
+            return None;
+        }
+        let mut params = [
+            (Mode::Immediate, V::zero()),
+            (Mode::Immediate, V::zero()),
+            (Mode::Immediate, V::zero()),
+        ];
+        params[..instruction.params.len()].clone_from_slice(&instruction.params);
+        Some(Self {
+            op: instruction.op,
+            params,
+            len: 1 + instruction.params.len(),
+        })
+    }
+}
+
+impl<M: Memory> Computer<M> {
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.decoded = match engine {
+            Engine::Interpreter => None,
+            Engine::Decoded => Some(Vec::new()),
+        };
+    }
+
+    pub fn engine(&self) -> Engine {
+        match self.decoded {
+            Some(_) => Engine::Decoded,
+            None => Engine::Interpreter,
+        }
+    }
+
+    /// Forgets all decoded instructions, e.g. after replacing the memory.
+    pub(super) fn clear_decoded(&mut self) {
+        if let Some(slots) = &mut self.decoded {
+            slots.clear();
+        }
+    }
+
+    /// Whether the program wrote into one of its decoded instructions since
+    /// the last reset.
+    ///
+    /// Only the decoded engine knows which words are code, so this is always
+    /// false with the interpreter. Writes through `patch` do not count.
+    pub fn self_modified(&self) -> bool {
+        self.self_modified
+    }
+
+    /// Marks instructions covering `address` as modified and returns whether
+    /// there were any.
+    pub(super) fn invalidate(&mut self, address: usize) -> bool {
+        let slots = match &mut self.decoded {
+            Some(slots) => slots,
+            None => return false,
+        };
+        let mut modified = false;
+        for start in address.saturating_sub(3)..slots.len().min(address + 1) {
+            match &slots[start] {
+                Slot::Decoded(decoded) if start + decoded.len > address => {
+                    slots[start] = Slot::Modified;
+                    modified = true;
+                }
+                _ => (),
+            }
+        }
+        modified
+    }
+
+    fn decoded_at(&mut self, address: usize) -> Option<Decoded<M::Value>> {
+        let memory = &self.memory;
+        let slots = self.decoded.as_mut()?;
+        match slots.get(address) {
+            Some(Slot::Decoded(decoded)) => return Some(decoded.clone()),
+            Some(Slot::Modified) => return None,
+            Some(Slot::Empty) | None => (),
+        }
+        // Only grow the slots for addresses that hold an instruction, so
+        // wild jumps cannot allocate them.
+        if address >= MAX_SLOTS {
+            return None;
+        }
+        let instruction = Instruction::decode(|a| memory.read(a).ok(), address)?;
+        let decoded = Decoded::new(&instruction)?;
+        if address >= slots.len() {
+            slots.resize(address + 1, Slot::Empty);
+        }
+        slots[address] = Slot::Decoded(decoded.clone());
+        Some(decoded)
+    }
+
+    /// Executes the instruction at the ip with the configured engine.
+    pub(super) fn execute_next<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<State>
+    where
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
+    {
+        match self.decoded_at(self.ip) {
+            Some(decoded) => self.execute_decoded(decoded, read, write),
+            None => self.execute_instruction(read, write),
+        }
+    }
+
+    fn execute_decoded<I, O>(
+        &mut self,
+        decoded: Decoded<M::Value>,
+        read: &mut I,
+        write: &mut O,
+    ) -> Result<State>
+    where
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
+    {
+        let [a, b, c] = decoded.params;
+        let mut ip = self.ip + decoded.len;
+        match decoded.op {
+            OpCode::Add => {
+                let value = add(self.operand(a)?, self.operand(b)?)?;
+                self.store(self.target(c)?, value)?;
+            }
+            OpCode::Multiply => {
+                let value = mul(self.operand(a)?, self.operand(b)?)?;
+                self.store(self.target(c)?, value)?;
+            }
+            OpCode::Input => match read()? {
+                Some(value) => self.store(self.target(a)?, value)?,
+                None => return Ok(State::NeedsInput),
+            },
+            OpCode::Output => write(self.operand(a)?)?,
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => {
+                let nonzero = !self.operand(a)?.is_zero();
+                let target = self.operand(b)?;
+                if nonzero == (decoded.op == OpCode::JumpIfTrue) {
+                    ip = to_address(target)?;
+                }
+            }
+            OpCode::LessThan | OpCode::Equals => {
+                let order = self.operand(a)?.cmp(&self.operand(b)?);
+                let expected = match decoded.op {
+                    OpCode::LessThan => Ordering::Less,
+                    _ => Ordering::Equal,
+                };
+                let value = if order == expected {
+                    M::Value::one()
+                } else {
+                    M::Value::zero()
+                };
+                self.store(self.target(c)?, value)?;
+            }
+            OpCode::AdjustRelativeBase => {
+                self.relative_base = add(self.relative_base.clone(), self.operand(a)?)?
+            }
+            OpCode::Halt => return Ok(State::Halted),
+        }
+        self.ip = ip;
+        Ok(State::Running)
+    }
+
+    fn operand(&mut self, (mode, value): (Mode, M::Value)) -> Result<M::Value> {
+        match mode {
+            Mode::Position => self.load(to_address(value)?),
+            Mode::Immediate => Ok(value),
+            Mode::Relative => self.load(to_address(add(self.relative_base.clone(), value)?)?),
+        }
+    }
+
+    fn target(&self, (mode, value): (Mode, M::Value)) -> Result<usize> {
+        match mode {
+            Mode::Position => to_address(value),
+            Mode::Immediate => Err(Error::InvalidWriteMode(mode)),
+            Mode::Relative => to_address(add(self.relative_base.clone(), value)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::memory::SparseMemory;
+    use crate::vm::types::Value;
+
+    fn run(program: Vec<Value>, engine: Engine, inputs: &[Value]) -> (State, Vec<Value>) {
+        let mut computer = Computer::new(program);
+        computer.set_engine(engine);
+        computer.extend_inputs(inputs.iter().copied());
+        (computer.run_until_blocked(), computer.take_outputs())
+    }
+
+    #[test]
+    fn test_engines_agree() {
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut memory = quine.clone();
+        memory.resize(102, 0);
+        let (state, outputs) = run(memory.clone(), Engine::Decoded, &[]);
+        assert_eq!(state, State::Halted);
+        assert_eq!(outputs, quine);
+        assert_eq!(run(memory, Engine::Interpreter, &[]), (state, outputs));
+
+        // Compares the input with 8.
+        let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        for &input in &[7, 8] {
+            assert_eq!(
+                run(program.clone(), Engine::Decoded, &[input]),
+                run(program.clone(), Engine::Interpreter, &[input])
+            );
+        }
+    }
+
+    #[test]
+    fn test_self_modification() {
+        // Outputs 5 in a loop, but rewrites the operand to 7 after the first.
+        let program = vec![104, 5, 1101, 0, 7, 1, 1105, 1, 0];
+        let mut computer = Computer::new(program);
+        computer.set_engine(Engine::Decoded);
+        assert_eq!(computer.run_with_limit(7), State::StepLimitExceeded);
+        assert_eq!(computer.take_outputs(), vec![5, 7, 7]);
+        assert_eq!(computer.engine(), Engine::Decoded);
+        assert!(computer.self_modified());
+        computer.reset();
+        assert!(!computer.self_modified());
+    }
+
+    #[test]
+    fn test_not_self_modified() -> Result<()> {
+        // Writes only to data after the code.
+        let program = vec![1101, 2, 3, 7, 4, 7, 99, 0];
+        let mut computer = Computer::new(program);
+        computer.set_engine(Engine::Decoded);
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert!(!computer.self_modified());
+        computer.patch(0, 1102)?;
+        assert!(!computer.self_modified());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wild_jump() {
+        let (state, _) = run(vec![1105, 1, 1 << 60], Engine::Decoded, &[]);
+        match state {
+            State::Faulted(err) => assert_eq!(err.root_cause(), &Error::SegFault(1 << 60)),
+            state => panic!("unexpected state {:?}", state),
+        }
+    }
+
+    #[test]
+    fn test_far_code() -> Result<()> {
+        // Jumps to a halt far into sparse memory.
+        let far: Value = 1 << 38;
+        let mut memory = SparseMemory::from(vec![1105, 1, far]);
+        memory.write(far as usize, 99)?;
+        let mut computer = Computer::new(memory);
+        computer.set_engine(Engine::Decoded);
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert_eq!(computer.ip(), far as usize);
+        Ok(())
+    }
+}
@@ -1,21 +1,97 @@
 use super::mode::Mode;
 use super::types::Value;
+use std::convert::TryFrom;
+use std::ops::Range;
 use thiserror::Error;
 
 #[derive(Clone, Error, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     #[error("Invalid OpCode {0}")]
     InvalidOpCode(Value),
     #[error("Tried to read out of bounds address {0}")]
     SegFault(usize),
+    #[error("Tried to access negative address {0}")]
+    NegativeAddress(Value),
+    #[error("Address {requested} exceeds the memory cap of {cap} cells")]
+    OutOfMemory { requested: usize, cap: usize },
     #[error("Reading is not supported")]
     ReadingNotSupported,
     #[error("Writing is not supported")]
     WritingNotSupported,
+    #[error("Input channel closed")]
+    InputClosed,
+    #[error("Program needs input but none is queued")]
+    NeedsInput,
+    #[error("Output channel closed")]
+    OutputClosed,
+    #[error("More than {0} outputs buffered")]
+    OutputBufferFull(usize),
+    #[error("Arithmetic overflow")]
+    Overflow,
     #[error("Invalid Parameter Mode {0}")]
     InvalidMode(Value),
     #[error("Invalid Write Mode {0:?}")]
     InvalidWriteMode(Mode),
+    #[error("{0} is not a byte")]
+    InvalidByte(Value),
+    #[error("Cannot map a device to {0:?}")]
+    InvalidMapping(Range<usize>),
+    #[error("Computer {from} sent a packet to unknown address {to}")]
+    UnknownAddress { from: usize, to: Value },
+    #[error("{source} at ip {ip}{}", describe(.instruction, .modes))]
+    At {
+        ip: usize,
+        instruction: Option<Value>,
+        modes: [Option<Mode>; 3],
+        source: Box<Error>,
+    },
+}
+
+fn describe(instruction: &Option<Value>, modes: &[Option<Mode>; 3]) -> String {
+    match instruction {
+        Some(instruction) => format!(" (instruction {}, modes {:?})", instruction, modes),
+        None => String::new(),
+    }
+}
+
+impl Error {
+    /// Wraps errors raised by the program with the instruction that caused them.
+    ///
+    /// Errors from the input and output endpoints are passed through as is.
+    pub fn at(ip: usize, instruction: Option<Value>) -> impl Fn(Error) -> Error {
+        move |source| match source {
+            Error::InputClosed
+            | Error::NeedsInput
+            | Error::OutputClosed
+            | Error::OutputBufferFull(_)
+            | Error::At { .. } => source,
+            source => {
+                let mut modes = [None; 3];
+                if let Some(instruction) = instruction {
+                    let mut digits = instruction / 100;
+                    for mode in modes.iter_mut() {
+                        *mode = Mode::try_from(digits % 10).ok();
+                        digits /= 10;
+                    }
+                }
+                Error::At {
+                    ip,
+                    instruction,
+                    modes,
+                    source: Box::new(source),
+                }
+            }
+        }
+    }
+
+    /// The error without any instruction context.
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::At { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
 }
 
 pub(super) type Result<T> = ::std::result::Result<T, Error>;
@@ -0,0 +1,178 @@
+use super::errors::{Error, Result};
+use super::types::Value;
+use super::{Computer, Memory};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::panic;
+use std::thread;
+
+/// A computer after its thread finished.
+pub struct Finished<M: Memory> {
+    pub computer: Computer<M>,
+    /// Outputs of a computer whose output was not connected.
+    pub outputs: Vec<Value>,
+}
+
+struct Node<M: Memory> {
+    computer: Computer<M>,
+    input: (Sender<Value>, Receiver<Value>),
+    output: Option<Sender<Value>>,
+}
+
+/// Runs every computer on its own thread, connected by bounded channels.
+///
+/// Each computer reads its queued inputs first, then its input channel.
+/// When a computer halts or fails it closes its channels, so computers
+/// waiting on it stop with `Error::InputClosed` or `Error::OutputClosed`
+/// instead of blocking forever.
+pub struct Executor<M: Memory> {
+    nodes: Vec<Node<M>>,
+    capacity: usize,
+}
+
+impl<M: Memory<Value = Value> + Send + 'static> Executor<M> {
+    /// Channels buffer up to `capacity` values before the sender blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Adds a computer and returns its index.
+    pub fn add(&mut self, computer: Computer<M>) -> usize {
+        self.nodes.push(Node {
+            computer,
+            input: bounded(self.capacity),
+            output: None,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Sends the outputs of computer `from` to computer `to`.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        self.nodes[from].output = Some(self.nodes[to].input.0.clone());
+    }
+
+    /// A sender for the input of computer `to`. The computer only sees its
+    /// input closed once every such sender is dropped.
+    pub fn input(&self, to: usize) -> Sender<Value> {
+        self.nodes[to].input.0.clone()
+    }
+
+    /// A receiver for the outputs of computer `from`.
+    pub fn output(&mut self, from: usize) -> Receiver<Value> {
+        let (sender, receiver) = bounded(self.capacity);
+        self.nodes[from].output = Some(sender);
+        receiver
+    }
+
+    /// Runs all computers until they halt.
+    ///
+    /// If any computer fails, returns the error that started the shutdown
+    /// rather than the closed channels it caused.
+    pub fn run(self) -> Result<Vec<Finished<M>>> {
+        let handles = self
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let Node {
+                    mut computer,
+                    input: (_, receiver),
+                    output,
+                } = node;
+                thread::spawn(move || {
+                    let read = || receiver.recv().map_err(|_| Error::InputClosed);
+                    let mut outputs = Vec::new();
+                    let mut result = Ok(());
+                    for value in computer.outputs(read) {
+                        result = value.and_then(|value| match &output {
+                            Some(sender) => sender.send(value).map_err(|_| Error::OutputClosed),
+                            None => {
+                                outputs.push(value);
+                                Ok(())
+                            }
+                        });
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                    result.map(|()| Finished { computer, outputs })
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect::<Vec<_>>();
+        let cause = |err: &Error| !matches!(err, Error::InputClosed | Error::OutputClosed);
+        match results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .find(|err| cause(err))
+        {
+            Some(err) => Err(err.clone()),
+            None => results.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback() -> Result<()> {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let mut executor = Executor::new(1);
+        for &phase in &[9, 8, 7, 6, 5] {
+            let mut computer = Computer::new(program.clone());
+            computer.push_input(phase);
+            executor.add(computer);
+        }
+        for from in 0..4 {
+            executor.connect(from, from + 1);
+        }
+        let output = executor.output(4);
+        let input = executor.input(0);
+        input.send(0).unwrap();
+        // Feed the last amplifier back into the first from the outside.
+        let relay = thread::spawn(move || {
+            let mut last = None;
+            for value in output {
+                last = Some(value);
+                let _ = input.send(value);
+            }
+            last
+        });
+        executor.run()?;
+        assert_eq!(relay.join().unwrap(), Some(139_629_729));
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let mut executor = Executor::new(0);
+        // Waits for input that never comes from the faulting computer.
+        let waiting = executor.add(Computer::new(vec![3, 0, 99]));
+        let faulty = executor.add(Computer::new(vec![104, 1, 42]));
+        executor.connect(faulty, waiting);
+        let result = executor.run();
+        assert!(matches!(result, Err(err) if *err.root_cause() == Error::InvalidOpCode(42)));
+    }
+
+    #[test]
+    fn test_unconnected_outputs() -> Result<()> {
+        let mut executor = Executor::new(0);
+        executor.add(Computer::new(vec![104, 7, 99]));
+        let finished = executor.run()?;
+        assert_eq!(finished[0].outputs, vec![7]);
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use super::errors::{Error, Result};
+use super::instruction::opcode_digits;
+use super::mode::Mode;
+use super::types::{add, Int, Value};
+use super::{to_address, Computer, Memory, State};
+use std::any::Any;
+use std::convert::TryFrom;
+use std::io::Write;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Handles opcodes the VM does not know instead of failing with
+/// `Error::InvalidOpCode`.
+///
+/// Extensions are shared between clones of a computer, so any state they
+/// keep needs interior mutability. Opcodes are passed as `Value` since they
+/// are only two digits, whatever the integer type of the computer.
+pub trait Extension<V = Value>: Any + Send + Sync {
+    /// The number of parameters of `opcode`, or `None` if the extension
+    /// does not handle it.
+    fn parameters(&self, opcode: Value) -> Option<usize>;
+
+    /// Executes `opcode`. When this returns `State::Running` the ip moves
+    /// past the parameters, unless the handler called [`Context::jump`];
+    /// for any other state it stays on the instruction.
+    fn execute(&self, opcode: Value, context: &mut Context<'_, V>) -> Result<State>;
+}
+
+/// What an [`Extension`] can see of the computer while executing an opcode.
+pub struct Context<'a, V = Value> {
+    machine: &'a mut dyn Machine<V>,
+    ip: usize,
+    modes: Value,
+    jump: Option<usize>,
+}
+
+/// The parts of a computer an extension may touch, independent of its
+/// memory type.
+trait Machine<V> {
+    fn fetch(&self, address: usize) -> Result<V>;
+    fn load(&mut self, address: usize) -> Result<V>;
+    fn store(&mut self, address: usize, value: V) -> Result<()>;
+    fn relative_base(&self) -> V;
+    fn set_relative_base(&mut self, relative_base: V);
+}
+
+impl<M: Memory> Machine<M::Value> for Computer<M> {
+    fn fetch(&self, address: usize) -> Result<M::Value> {
+        Computer::fetch(self, address)
+    }
+
+    fn load(&mut self, address: usize) -> Result<M::Value> {
+        Computer::load(self, address)
+    }
+
+    fn store(&mut self, address: usize, value: M::Value) -> Result<()> {
+        Computer::store(self, address, value)
+    }
+
+    fn relative_base(&self) -> M::Value {
+        self.relative_base.clone()
+    }
+
+    fn set_relative_base(&mut self, relative_base: M::Value) {
+        self.relative_base = relative_base;
+    }
+}
+
+impl<V: Int> Context<'_, V> {
+    /// Address of the instruction being executed.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> V {
+        self.machine.relative_base()
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: V) {
+        self.machine.set_relative_base(relative_base);
+    }
+
+    /// The mode of parameter `index`, counting from zero.
+    pub fn mode(&self, index: usize) -> Result<Mode> {
+        // Parameters past the encoded modes are in position mode.
+        let digit = u32::try_from(index)
+            .ok()
+            .and_then(|index| 10_i64.checked_pow(index))
+            .map_or(0, |scale| self.modes / scale % 10);
+        Mode::try_from(digit)
+    }
+
+    /// The raw value of parameter `index` as stored after the opcode.
+    pub fn raw(&self, index: usize) -> Result<V> {
+        self.machine.fetch(self.ip + 1 + index)
+    }
+
+    /// Reads parameter `index` according to its mode.
+    pub fn param(&mut self, index: usize) -> Result<V> {
+        let value = self.raw(index)?;
+        match self.mode(index)? {
+            Mode::Position => self.machine.load(to_address(value)?),
+            Mode::Immediate => Ok(value),
+            Mode::Relative => {
+                let address = to_address(add(self.machine.relative_base(), value)?)?;
+                self.machine.load(address)
+            }
+        }
+    }
+
+    /// Writes to the address parameter `index` refers to.
+    pub fn set_param(&mut self, index: usize, value: V) -> Result<()> {
+        let address = match self.mode(index)? {
+            Mode::Position => to_address(self.raw(index)?)?,
+            mode @ Mode::Immediate => return Err(Error::InvalidWriteMode(mode)),
+            Mode::Relative => to_address(add(self.machine.relative_base(), self.raw(index)?)?)?,
+        };
+        self.machine.store(address, value)
+    }
+
+    pub fn read(&mut self, address: usize) -> Result<V> {
+        self.machine.load(address)
+    }
+
+    pub fn write(&mut self, address: usize, value: V) -> Result<()> {
+        self.machine.store(address, value)
+    }
+
+    /// Continues at `address` instead of the next instruction.
+    pub fn jump(&mut self, address: usize) {
+        self.jump = Some(address);
+    }
+}
+
+impl<M: Memory> Computer<M> {
+    pub fn set_extension<E: Extension<M::Value> + 'static>(&mut self, extension: E) {
+        self.extension = Some(Arc::new(extension));
+    }
+
+    /// The extension set with [`Computer::set_extension`], if it is an `E`.
+    pub fn extension<E: Extension<M::Value>>(&self) -> Option<&E> {
+        let extension: &dyn Any = self.extension.as_deref()?;
+        extension.downcast_ref()
+    }
+
+    pub fn remove_extension(&mut self) {
+        self.extension = None;
+    }
+
+    /// Runs the instruction at the ip through the extension, failing with
+    /// `Error::InvalidOpCode` if there is none or it rejects the opcode.
+    pub(super) fn execute_extension(&mut self, instruction: &M::Value) -> Result<State> {
+        let digits = opcode_digits(instruction);
+        let opcode = digits % 100;
+        let extension = match &self.extension {
+            Some(extension) => Arc::clone(extension),
+            None => return Err(Error::InvalidOpCode(opcode)),
+        };
+        let parameters = extension
+            .parameters(opcode)
+            .ok_or(Error::InvalidOpCode(opcode))?;
+        let ip = self.ip;
+        let mut context = Context {
+            machine: self,
+            ip,
+            modes: digits / 100,
+            jump: None,
+        };
+        let state = extension.execute(opcode, &mut context)?;
+        let jump = context.jump;
+        if state == State::Running {
+            self.ip = jump.unwrap_or(ip + 1 + parameters);
+        }
+        Ok(state)
+    }
+}
+
+/// Opcode of the instruction handled by [`DebugPrint`].
+pub const DEBUG_PRINT: Value = 98;
+
+/// Adds the opcode `98` with one parameter, which writes the current ip and
+/// the parameter's value to a sink and otherwise does nothing.
+///
+/// The assembler emits it for `dbg x`. Without this extension the opcode
+/// is invalid.
+pub struct DebugPrint<W> {
+    sink: Mutex<W>,
+}
+
+impl<W: Write + Send> DebugPrint<W> {
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink: Mutex::new(sink),
+        }
+    }
+
+    /// The sink, e.g. to read what was printed so far while the extension
+    /// is still installed.
+    pub fn sink(&self) -> MutexGuard<'_, W> {
+        self.sink.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: Write + Send + 'static, V: Int> Extension<V> for DebugPrint<W> {
+    fn parameters(&self, opcode: Value) -> Option<usize> {
+        if opcode == DEBUG_PRINT {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    fn execute(&self, _opcode: Value, context: &mut Context<'_, V>) -> Result<State> {
+        let value = context.param(0)?;
+        let mut sink = self.sink();
+        // Debug output must not change what the program does, so a broken
+        // sink is ignored.
+        let _ = writeln!(sink, "ip {}: {}", context.ip(), value);
+        Ok(State::Running)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `swap a, b` exchanges two cells and `jz a, target` jumps if `a` is zero.
+    struct Custom;
+
+    impl Extension for Custom {
+        fn parameters(&self, opcode: Value) -> Option<usize> {
+            match opcode {
+                50 => Some(2),
+                51 => Some(2),
+                _ => None,
+            }
+        }
+
+        fn execute(&self, opcode: Value, context: &mut Context<'_>) -> Result<State> {
+            let (a, b) = (context.param(0)?, context.param(1)?);
+            if opcode == 50 {
+                context.set_param(0, b)?;
+                context.set_param(1, a)?;
+            } else if a == 0 {
+                context.jump(to_address(b)?);
+            }
+            Ok(State::Running)
+        }
+    }
+
+    fn fault(state: State) -> Option<Error> {
+        match state {
+            State::Faulted(err) => Some(err.root_cause().clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_extension() {
+        let program = vec![50, 9, 10, 1051, 10, 8, 104, 1, 99, 0, 7];
+        let mut computer = Computer::new(program.clone());
+        assert_eq!(
+            fault(computer.run_until_blocked()),
+            Some(Error::InvalidOpCode(50))
+        );
+        let mut computer = Computer::new(program);
+        computer.set_extension(Custom);
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert_eq!(computer.memory().read_slice(9..11), Ok(vec![7, 0]));
+        assert!(computer.take_outputs().is_empty());
+
+        let mut computer = Computer::new(vec![52, 99]);
+        computer.set_extension(Custom);
+        assert_eq!(
+            fault(computer.run_until_blocked()),
+            Some(Error::InvalidOpCode(52))
+        );
+    }
+
+    #[test]
+    fn test_debug_print() {
+        // Prints the counter on every iteration of a countdown from 2.
+        let program = vec![98, 12, 1001, 12, -1, 12, 1005, 12, 0, 104, 9, 99, 2];
+        let mut computer = Computer::new(program);
+        computer.set_extension(DebugPrint::new(Vec::new()));
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert_eq!(computer.take_outputs(), vec![9]);
+        assert!(computer.extension::<Custom>().is_none());
+        let debug = computer.extension::<DebugPrint<Vec<u8>>>().unwrap();
+        assert_eq!(
+            String::from_utf8(debug.sink().clone()).unwrap(),
+            "ip 0: 2\nip 0: 1\n"
+        );
+    }
+}
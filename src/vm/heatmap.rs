@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+const SHADES: &[u8] = b".:-=+*#%@";
+const ROW: usize = 64;
+
+/// Read and write counts per address, collected by
+/// [`Computer::enable_heatmap`].
+///
+/// Only data accesses are counted; fetching instructions and their
+/// parameters is not.
+///
+/// [`Computer::enable_heatmap`]: super::Computer::enable_heatmap
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heatmap {
+    reads: BTreeMap<usize, u64>,
+    writes: BTreeMap<usize, u64>,
+}
+
+impl Heatmap {
+    pub(super) fn read(&mut self, address: usize) {
+        *self.reads.entry(address).or_insert(0) += 1;
+    }
+
+    pub(super) fn write(&mut self, address: usize) {
+        *self.writes.entry(address).or_insert(0) += 1;
+    }
+
+    pub fn reads(&self, address: usize) -> u64 {
+        self.reads.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn writes(&self, address: usize) -> u64 {
+        self.writes.get(&address).copied().unwrap_or(0)
+    }
+
+    /// Every accessed address with its read and write counts, in order.
+    pub fn accesses(&self) -> impl Iterator<Item = (usize, u64, u64)> + '_ {
+        let mut addresses = self
+            .reads
+            .keys()
+            .chain(self.writes.keys())
+            .collect::<Vec<_>>();
+        addresses.sort();
+        addresses.dedup();
+        addresses
+            .into_iter()
+            .map(move |&address| (address, self.reads(address), self.writes(address)))
+    }
+}
+
+/// Renders 64 addresses per row, one character each: a space for untouched
+/// addresses, then `.` to `@` by total accesses relative to the busiest
+/// address. Rows without any accesses are left out, and `...` marks where
+/// rows were skipped.
+impl fmt::Display for Heatmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let totals = self
+            .accesses()
+            .map(|(address, reads, writes)| (address, reads + writes))
+            .collect::<BTreeMap<_, _>>();
+        let max = match totals.values().max() {
+            Some(&max) => max,
+            None => return Ok(()),
+        };
+        let mut rows = BTreeMap::<usize, Vec<(usize, u64)>>::new();
+        for (&address, &total) in &totals {
+            rows.entry(address / ROW)
+                .or_default()
+                .push((address, total));
+        }
+        let mut previous = None;
+        for (index, cells) in rows {
+            if previous.is_some_and(|previous| previous + 1 < index) {
+                writeln!(f, "{:>6}", "...")?;
+            }
+            previous = Some(index);
+            let start = index * ROW;
+            let mut row = String::new();
+            for (address, total) in cells {
+                let offset = address - start;
+                row.extend(std::iter::repeat_n(' ', offset - row.len()));
+                let shade = (total * (SHADES.len() as u64 - 1)).div_ceil(max);
+                row.push(SHADES[shade as usize] as char);
+            }
+            writeln!(f, "{:6}: {}", start, row)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use super::mode::Mode;
+use super::op::OpCode;
+use super::types::{Int, Value};
+use std::convert::TryFrom;
+
+/// A decoded instruction with its parameters as stored in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction<V = Value> {
+    pub address: usize,
+    pub op: OpCode,
+    pub params: Vec<(Mode, V)>,
+}
+
+impl<V: Int> Instruction<V> {
+    /// Decodes the instruction at `address`, reading words through `read`.
+    pub fn decode<F>(read: F, address: usize) -> Option<Self>
+    where
+        F: Fn(usize) -> Option<V>,
+    {
+        let word = opcode_digits(&read(address)?);
+        let op = OpCode::try_from(word % 100).ok()?;
+        let mut modes = word / 100;
+        let params = (1..=op.parameters())
+            .map(|offset| {
+                let mode = Mode::try_from(modes % 10).ok()?;
+                modes /= 10;
+                Some((mode, read(address + offset)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            address,
+            op,
+            params,
+        })
+    }
+
+    /// Address of the following instruction.
+    pub fn next(&self) -> usize {
+        self.address + 1 + self.params.len()
+    }
+}
+
+/// The opcode and parameter modes of an instruction word, which are all the
+/// VM looks at. Any higher digits are dropped so the result fits a `Value`.
+pub(super) fn opcode_digits<V: Int>(word: &V) -> Value {
+    if let Some(word) = word.to_i64() {
+        return word % 100_000;
+    }
+    // Words beyond a Value have more than five digits, so the last five
+    // are the remainder, with the word's sign.
+    let text = word.to_string();
+    let digits = text[text.len() - 5..]
+        .parse::<Value>()
+        .expect("five digits fit into a Value");
+    if text.starts_with('-') {
+        -digits
+    } else {
+        digits
+    }
+}
@@ -0,0 +1,265 @@
+use super::errors::{Error, Result};
+use super::types::{Int, Value};
+use super::Memory;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default limit for the number of cells a growable memory may use.
+pub const DEFAULT_CAP: usize = 1 << 24;
+
+/// Vec-backed memory that grows on demand; unwritten cells read as zero.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrowableMemory<V = Value> {
+    cells: Vec<V>,
+    cap: usize,
+}
+
+impl<V: Int> GrowableMemory<V> {
+    pub fn new(program: Vec<V>) -> Self {
+        Self::with_cap(program, DEFAULT_CAP)
+    }
+
+    /// Memory that fails with `OutOfMemory` when accessing `cap` or beyond.
+    pub fn with_cap(program: Vec<V>, cap: usize) -> Self {
+        Self {
+            cells: program,
+            cap,
+        }
+    }
+
+    pub fn cap(&self) -> usize {
+        self.cap
+    }
+
+    fn check(&self, address: usize) -> Result<()> {
+        if address >= self.cap {
+            Err(Error::OutOfMemory {
+                requested: address,
+                cap: self.cap,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<V: Int> From<Vec<V>> for GrowableMemory<V> {
+    fn from(program: Vec<V>) -> Self {
+        Self::new(program)
+    }
+}
+
+impl<V: Int> Memory for GrowableMemory<V> {
+    type Value = V;
+
+    fn read(&self, address: usize) -> Result<V> {
+        self.check(address)?;
+        Ok(self.cells.get(address).cloned().unwrap_or_else(V::zero))
+    }
+
+    fn write(&mut self, address: usize, value: V) -> Result<()> {
+        self.check(address)?;
+        if address >= self.cells.len() {
+            self.cells.resize(address + 1, V::zero());
+        }
+        self.cells[address] = value;
+        Ok(())
+    }
+
+    fn write_slice(&mut self, address: usize, values: &[V]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        // An overflowing range is out of memory whatever the cap.
+        let last = address.saturating_add(values.len() - 1);
+        self.check(last)?;
+        let end = last + 1;
+        if end > self.cells.len() {
+            self.cells.resize(end, V::zero());
+        }
+        self.cells[address..end].clone_from_slice(values);
+        Ok(())
+    }
+}
+
+/// HashMap-backed memory for programs touching a few far apart addresses.
+///
+/// Unwritten cells read as zero and only non-zero cells are stored.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparseMemory<V = Value> {
+    cells: HashMap<usize, V>,
+}
+
+impl<V: Int> SparseMemory<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V: Int> Default for SparseMemory<V> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<V: Int> From<Vec<V>> for SparseMemory<V> {
+    fn from(program: Vec<V>) -> Self {
+        Self {
+            cells: program
+                .into_iter()
+                .enumerate()
+                .filter(|(_, value)| !value.is_zero())
+                .collect(),
+        }
+    }
+}
+
+impl<V: Int> Memory for SparseMemory<V> {
+    type Value = V;
+
+    fn read(&self, address: usize) -> Result<V> {
+        Ok(self.cells.get(&address).cloned().unwrap_or_else(V::zero))
+    }
+
+    fn write(&mut self, address: usize, value: V) -> Result<()> {
+        if value.is_zero() {
+            self.cells.remove(&address);
+        } else {
+            self.cells.insert(address, value);
+        }
+        Ok(())
+    }
+}
+
+/// Copy-on-write memory that shares its initial image between clones.
+///
+/// Only cells that differ from the image are stored per instance, so cloning
+/// is cheap no matter how large the program is. Cells past the image read as
+/// zero.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SharedMemory<V = Value> {
+    base: Arc<[V]>,
+    changes: HashMap<usize, V>,
+}
+
+impl<V: Int> SharedMemory<V> {
+    pub fn new(base: Arc<[V]>) -> Self {
+        Self {
+            base,
+            changes: HashMap::new(),
+        }
+    }
+
+    pub fn base(&self) -> &Arc<[V]> {
+        &self.base
+    }
+
+    /// Number of cells that differ from the shared image.
+    pub fn changed(&self) -> usize {
+        self.changes.len()
+    }
+
+    fn base_value(&self, address: usize) -> V {
+        self.base.get(address).cloned().unwrap_or_else(V::zero)
+    }
+}
+
+impl<V: Int> From<Vec<V>> for SharedMemory<V> {
+    fn from(program: Vec<V>) -> Self {
+        Self::new(program.into())
+    }
+}
+
+impl<V: Int> Memory for SharedMemory<V> {
+    type Value = V;
+
+    fn read(&self, address: usize) -> Result<V> {
+        Ok(match self.changes.get(&address) {
+            Some(value) => value.clone(),
+            None => self.base_value(address),
+        })
+    }
+
+    fn write(&mut self, address: usize, value: V) -> Result<()> {
+        if value == self.base_value(address) {
+            self.changes.remove(&address);
+        } else {
+            self.changes.insert(address, value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grow() -> Result<()> {
+        let mut memory = GrowableMemory::<Value>::new(vec![1, 2]);
+        assert_eq!(memory.read(1)?, 2);
+        assert_eq!(memory.read(1000)?, 0);
+        memory.write(1000, 5)?;
+        assert_eq!(memory.read(1000)?, 5);
+        assert_eq!(memory.read(999)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse() -> Result<()> {
+        let mut memory = SparseMemory::<Value>::from(vec![1, 0, 3]);
+        assert_eq!(memory.read(0)?, 1);
+        assert_eq!(memory.read(1)?, 0);
+        memory.write(1 << 40, 7)?;
+        assert_eq!(memory.read(1 << 40)?, 7);
+        memory.write(0, 0)?;
+        assert_eq!(memory.cells.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cap() -> Result<()> {
+        let mut memory = GrowableMemory::<Value>::with_cap(vec![], 16);
+        assert_eq!(memory.write(15, 1), Ok(()));
+        assert_eq!(
+            memory.write(16, 1),
+            Err(Error::OutOfMemory {
+                requested: 16,
+                cap: 16
+            })
+        );
+        assert!(memory.write_slice(14, &[1, 2, 3]).is_err());
+        assert_eq!(memory.read_slice(14..16)?, vec![0, 1]);
+        assert!(memory.write_slice(usize::MAX, &[1, 2]).is_err());
+        memory.write_slice(13, &[4, 5])?;
+        assert_eq!(memory.read_slice(12..16)?, vec![0, 4, 5, 1]);
+        assert_eq!(
+            memory.read(usize::MAX),
+            Err(Error::OutOfMemory {
+                requested: usize::MAX,
+                cap: 16
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared() -> Result<()> {
+        let mut memory = SharedMemory::<Value>::from(vec![1, 2, 3]);
+        let copy = memory.clone();
+        memory.write(1, 5)?;
+        memory.write(10, 7)?;
+        assert_eq!(memory.read_slice(0..3)?, vec![1, 5, 3]);
+        assert_eq!(memory.read(10)?, 7);
+        assert_eq!(copy.read(1)?, 2);
+        assert!(Arc::ptr_eq(memory.base(), copy.base()));
+        memory.write(1, 2)?;
+        assert_eq!(memory.changed(), 1);
+        Ok(())
+    }
+}
@@ -1,22 +1,102 @@
+pub mod ascii;
+pub mod asm;
+mod asynchronous;
+#[cfg(test)]
+mod conformance;
+pub mod decompile;
+pub mod device;
+pub mod differential;
+mod engine;
 pub(crate) mod errors;
+pub mod executor;
+mod extension;
+mod heatmap;
+mod instruction;
+pub mod memory;
 mod mode;
+pub mod network;
 mod op;
+pub mod optimize;
+mod parse;
+pub mod pipeline;
+mod pool;
+mod profile;
+mod program;
+mod promoting;
+#[cfg(test)]
+mod properties;
+mod replay;
+pub mod stream;
+mod trace;
 pub(crate) mod types;
 
-use self::errors::{Error, Result};
-use self::mode::Mode;
-use self::op::OpCode;
-use self::types::Value;
+pub use self::asynchronous::{AsyncInput, AsyncOutput};
+pub use self::engine::Engine;
+use self::engine::Slot;
+pub use self::errors::Error;
+use self::errors::Result;
+pub use self::extension::{Context, DebugPrint, Extension, DEBUG_PRINT};
+pub use self::heatmap::Heatmap;
+use self::instruction::opcode_digits;
+pub use self::instruction::Instruction;
+pub use self::mode::Mode;
+pub use self::op::OpCode;
+pub use self::parse::{parse_program, ParseError};
+pub use self::pool::ComputerPool;
+pub use self::profile::Profile;
+pub use self::program::Program;
+pub use self::promoting::Promoting;
+pub use self::replay::{Event, Recorder, Recording, ReplayError};
+use self::trace::TracerSlot;
+pub use self::trace::{Effects, JsonTracer, Tracer};
+pub use self::types::Int;
+use self::types::{add, mul, saturate, Value};
 
+use num::{One, ToPrimitive, Zero};
+use std::any::Any;
 use std::cmp::Ordering;
-use std::convert::TryInto;
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::{TryFrom, TryInto};
+use std::io;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub trait Memory {
-    fn read(&self, address: usize) -> Result<Value>;
-    fn write(&mut self, address: usize, value: Value) -> Result<()>;
+    /// The integer type of the cells.
+    type Value: Int;
+
+    fn read(&self, address: usize) -> Result<Self::Value>;
+    fn write(&mut self, address: usize, value: Self::Value) -> Result<()>;
+
+    fn read_slice(&self, range: Range<usize>) -> Result<Vec<Self::Value>> {
+        range.map(|address| self.read(address)).collect()
+    }
+
+    /// Writes `values` starting at `address`, all or nothing.
+    ///
+    /// The default implementation assumes the accessible addresses form a
+    /// prefix of the address space, so it only checks that the last cell can
+    /// be read before writing.
+    fn write_slice(&mut self, address: usize, values: &[Self::Value]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let last = address
+            .checked_add(values.len() - 1)
+            .ok_or(Error::SegFault(address))?;
+        self.read(last)?;
+        values
+            .iter()
+            .enumerate()
+            .try_for_each(|(offset, value)| self.write(address + offset, value.clone()))
+    }
 }
 
 impl Memory for Vec<Value> {
+    type Value = Value;
+
     fn read(&self, address: usize) -> Result<Value> {
         self.get(address).cloned().ok_or(Error::SegFault(address))
     }
@@ -26,35 +106,456 @@ impl Memory for Vec<Value> {
             .map(|val| *val = value)
             .ok_or(Error::SegFault(address))
     }
+
+    fn read_slice(&self, range: Range<usize>) -> Result<Vec<Value>> {
+        if range.start >= range.end {
+            return Ok(Vec::new());
+        }
+        let start = range.start;
+        self.get(range)
+            .map(<[Value]>::to_vec)
+            .ok_or_else(|| Error::SegFault(start.max(self.len())))
+    }
+
+    fn write_slice(&mut self, address: usize, values: &[Value]) -> Result<()> {
+        let len = self.len();
+        address
+            .checked_add(values.len())
+            .and_then(|end| self.get_mut(address..end))
+            .map(|slice| slice.copy_from_slice(values))
+            .ok_or_else(|| Error::SegFault(address.max(len)))
+    }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Computer<M: Memory> {
+    memory: M,
+    initial: M,
+    ip: usize,
+    relative_base: M::Value,
+    halted: bool,
+    state: State,
+    inputs: VecDeque<M::Value>,
+    outputs: Vec<M::Value>,
+    output_limit: OutputLimit,
+    watchpoints: BTreeSet<usize>,
+    watch_hit: Option<(usize, Access)>,
+    profile: Option<Profile>,
+    heatmap: Option<Heatmap>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    tracer: TracerSlot<M::Value>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    effects: Option<Effects<M::Value>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    decoded: Option<Vec<Slot<M::Value>>>,
+    self_modified: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    extension: Option<Arc<dyn Extension<M::Value>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    devices: Vec<device::Mapping<M::Value>>,
+}
+
+/// Execution state captured by `Computer::snapshot`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot<M: Memory> {
     memory: M,
     ip: usize,
+    relative_base: M::Value,
+    halted: bool,
+    inputs: VecDeque<M::Value>,
+    outputs: Vec<M::Value>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     Running,
     Halted,
+    NeedsInput,
+    OutputFull,
+    StepLimitExceeded,
+    DeadlineExceeded,
+    /// The last instruction accessed a watched address.
+    Watchpoint {
+        address: usize,
+        access: Access,
+    },
+    Faulted(Error),
 }
 
-fn writing_not_supported(_: Value) -> Result<()> {
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Number of instructions between clock checks in `run_with_deadline`.
+const CLOCK_INTERVAL: usize = 256;
+
+/// What to do when the output buffer already holds the given number of values.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputLimit {
+    Unbounded,
+    /// Fail with `Error::OutputBufferFull`.
+    Fail(usize),
+    /// Pause with `State::OutputFull` until the buffer is drained.
+    Block(usize),
+}
+
+fn writing_not_supported<V>(_: V) -> Result<()> {
     Err(Error::WritingNotSupported)
 }
 
-fn reading_not_supported() -> Result<Value> {
+fn reading_not_supported<V>() -> Result<V> {
     Err(Error::ReadingNotSupported)
 }
 
-impl<M: Memory> Computer<M> {
+/// Converts a value to an address; the only place this conversion happens.
+fn to_address<V: Int>(value: V) -> Result<usize> {
+    value.to_usize().ok_or_else(|| {
+        if value < V::zero() {
+            Error::NegativeAddress(saturate(&value))
+        } else {
+            Error::SegFault(usize::MAX)
+        }
+    })
+}
+
+impl<M: Memory + Clone> Computer<M> {
     pub fn new(memory: M) -> Self {
-        Self { ip: 0, memory }
+        Self {
+            ip: 0,
+            initial: memory.clone(),
+            memory,
+            relative_base: M::Value::zero(),
+            halted: false,
+            state: State::Running,
+            inputs: VecDeque::new(),
+            outputs: Vec::new(),
+            output_limit: OutputLimit::Unbounded,
+            watchpoints: BTreeSet::new(),
+            watch_hit: None,
+            profile: None,
+            heatmap: None,
+            tracer: TracerSlot::default(),
+            effects: None,
+            decoded: None,
+            self_modified: false,
+            extension: None,
+            devices: Vec::new(),
+        }
+    }
+
+    /// Restores the memory the computer was created with and clears the
+    /// state of the run: the ip, relative base, queued inputs, buffered
+    /// outputs and the last state.
+    ///
+    /// Everything set on the computer survives: the engine, the output limit,
+    /// watchpoints, the tracer, profile and heatmap, the extension and mapped
+    /// devices. Use `reset_all` to drop those as well.
+    pub fn reset(&mut self) {
+        self.memory.clone_from(&self.initial);
+        self.clear_decoded();
+        self.self_modified = false;
+        self.ip = 0;
+        self.relative_base = M::Value::zero();
+        self.halted = false;
+        self.state = State::Running;
+        self.watch_hit = None;
+        self.inputs.clear();
+        self.outputs.clear();
+    }
+
+    /// Like `reset`, but also drops everything set on the computer, leaving
+    /// it as `Computer::new` created it while keeping its buffers.
+    pub fn reset_all(&mut self) {
+        self.reset();
+        self.set_engine(Engine::Interpreter);
+        self.output_limit = OutputLimit::Unbounded;
+        self.watchpoints.clear();
+        self.profile = None;
+        self.heatmap = None;
+        self.tracer = TracerSlot::default();
+        self.extension = None;
+        self.devices.clear();
+    }
+
+    pub fn snapshot(&self) -> Snapshot<M> {
+        Snapshot {
+            memory: self.memory.clone(),
+            ip: self.ip,
+            relative_base: self.relative_base.clone(),
+            halted: self.halted,
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot<M>) {
+        self.memory.clone_from(&snapshot.memory);
+        self.clear_decoded();
+        self.ip = snapshot.ip;
+        self.relative_base = snapshot.relative_base.clone();
+        self.halted = snapshot.halted;
+        self.state = State::Running;
+        self.inputs.clone_from(&snapshot.inputs);
+        self.outputs.clone_from(&snapshot.outputs);
+    }
+
+    /// An independent copy that continues from the current state.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl<M: Memory> Computer<M> {
+    pub fn patch(&mut self, address: usize, value: M::Value) -> Result<()> {
+        self.invalidate(address);
+        self.memory.write(address, value)
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> M::Value {
+        self.relative_base.clone()
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// The memory for arbitrary changes. Decoded instructions are dropped,
+    /// as they may no longer match it.
+    pub fn memory_mut(&mut self) -> &mut M {
+        self.clear_decoded();
+        &mut self.memory
+    }
+
+    /// The state the last run or instruction left the computer in, including
+    /// interruptions such as `StepLimitExceeded`; `Running` before the first
+    /// one and after a reset.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Makes the runners stop with `State::Watchpoint` after an instruction
+    /// reads or writes `address` as data.
+    ///
+    /// `run` and `execute` ignore watchpoints.
+    pub fn add_watchpoint(&mut self, address: usize) {
+        self.watchpoints.insert(address);
+    }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address)
+    }
+
+    /// Calls `tracer` around every executed instruction. Forks and clones
+    /// start without a tracer.
+    pub fn set_tracer<T: Tracer<M::Value>>(&mut self, tracer: T) {
+        self.tracer.0 = Some(Box::new(tracer));
+    }
+
+    /// The current tracer if it is a `T`.
+    pub fn tracer<T: Tracer<M::Value>>(&self) -> Option<&T> {
+        let tracer: &dyn Any = self.tracer.0.as_deref()?;
+        tracer.downcast_ref()
+    }
+
+    pub fn tracer_mut<T: Tracer<M::Value>>(&mut self) -> Option<&mut T> {
+        let tracer: &mut dyn Any = self.tracer.0.as_deref_mut()?;
+        tracer.downcast_mut()
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer<M::Value>>> {
+        self.tracer.0.take()
+    }
+
+    /// Starts counting executed instructions. The counts survive `reset`.
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(Profile::default);
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Stops profiling and returns the counts so far.
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    /// Starts counting memory reads and writes. The counts survive `reset`.
+    pub fn enable_heatmap(&mut self) {
+        self.heatmap.get_or_insert_with(Heatmap::default);
+    }
+
+    pub fn heatmap(&self) -> Option<&Heatmap> {
+        self.heatmap.as_ref()
+    }
+
+    pub fn take_heatmap(&mut self) -> Option<Heatmap> {
+        self.heatmap.take()
+    }
+
+    /// Writes the profile and heatmap collected so far as CSV with the
+    /// columns `kind,key,value`, one row per measurement:
+    ///
+    /// * `executions` and `nanoseconds` per opcode mnemonic,
+    /// * `hits` per instruction address,
+    /// * `reads` and `writes` per data address.
+    ///
+    /// Statistics that are not enabled are left out.
+    pub fn write_stats<W: io::Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "kind,key,value")?;
+        if let Some(profile) = &self.profile {
+            for (op, executions, time) in profile.opcodes() {
+                writeln!(out, "executions,{},{}", op.mnemonic(), executions)?;
+                writeln!(out, "nanoseconds,{},{}", op.mnemonic(), time.as_nanos())?;
+            }
+            for (address, hits) in profile.addresses() {
+                writeln!(out, "hits,{},{}", address, hits)?;
+            }
+        }
+        if let Some(heatmap) = &self.heatmap {
+            for (address, reads, writes) in heatmap.accesses() {
+                writeln!(out, "reads,{},{}", address, reads)?;
+                writeln!(out, "writes,{},{}", address, writes)?;
+            }
+        }
+        out.flush()
+    }
+
+    fn next_step<I, O>(&mut self, read: &mut I, write: &mut O) -> Option<Result<State>>
+    where
+        I: FnMut() -> Result<M::Value>,
+        O: FnMut(M::Value) -> Result<()>,
+    {
+        if self.halted {
+            return None;
+        }
+        let state = self.step(&mut || read().map(Some), write);
+        self.halted = state == Ok(State::Halted);
+        Some(state)
+    }
+
+    /// Iterates over the states after each executed instruction, ending after `Halted`.
+    pub fn steps<I, O>(&mut self, read: I, write: O) -> Steps<'_, M, I, O>
+    where
+        I: FnMut() -> Result<M::Value>,
+        O: FnMut(M::Value) -> Result<()>,
+    {
+        Steps {
+            computer: self,
+            read,
+            write,
+        }
+    }
+
+    /// Iterates over the outputs until the program halts.
+    ///
+    /// Queued inputs are used before calling `read`. Iteration stops after the
+    /// first error.
+    pub fn outputs<I>(&mut self, read: I) -> Outputs<'_, M, I>
+    where
+        I: FnMut() -> Result<M::Value>,
+    {
+        Outputs {
+            computer: self,
+            read,
+            failed: false,
+        }
     }
 
     fn step<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<State>
     where
-        I: FnMut() -> Result<Value>,
-        O: FnMut(Value) -> Result<()>,
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
+    {
+        let result = self.traced_step(read, write);
+        self.state = match &result {
+            Ok(state) => state.clone(),
+            Err(Error::NeedsInput) => State::NeedsInput,
+            Err(err) => State::Faulted(err.clone()),
+        };
+        result
+    }
+
+    fn traced_step<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<State>
+    where
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
+    {
+        let mut tracer = match self.tracer.0.take() {
+            Some(tracer) => tracer,
+            None => return self.untraced_step(read, write),
+        };
+        let memory = &self.memory;
+        let instruction = Instruction::decode(|address| memory.read(address).ok(), self.ip);
+        if let Some(instruction) = &instruction {
+            tracer.before(instruction);
+        }
+        self.effects = Some(Effects::default());
+        let mut output = None;
+        let result = self.untraced_step(read, &mut |value| {
+            output = Some(value.clone());
+            write(value)
+        });
+        let effects = Effects {
+            output,
+            ..self.effects.take().unwrap_or_default()
+        };
+        match (&instruction, &result) {
+            (_, Ok(State::NeedsInput)) | (None, _) => (),
+            (Some(instruction), _) => tracer.after(instruction, &result, &effects),
+        }
+        self.tracer.0 = Some(tracer);
+        result
+    }
+
+    fn untraced_step<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<State>
+    where
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
+    {
+        let ip = self.ip;
+        self.watch_hit = None;
+        // Decoded before executing, since the instruction may overwrite
+        // itself. Opcodes handled by an extension are not profiled.
+        let profiled = self.profile.as_ref().map(|_| {
+            let op = self
+                .memory
+                .read(ip)
+                .ok()
+                .and_then(|word| OpCode::try_from(opcode_digits(&word) % 100).ok());
+            (op, Instant::now())
+        });
+        let state = self.execute_next(read, write).map_err(|err| {
+            let instruction = self.memory.read(ip).ok().and_then(|word| word.to_i64());
+            Error::at(ip, instruction)(err)
+        })?;
+        match (&mut self.profile, profiled) {
+            (Some(profile), Some((Some(op), start))) if state != State::NeedsInput => {
+                profile.record(ip, op, start.elapsed())
+            }
+            _ => (),
+        }
+        Ok(match (state, self.watch_hit.take()) {
+            (State::Running, Some((address, access))) => State::Watchpoint { address, access },
+            (state, _) => state,
+        })
+    }
+
+    fn execute_instruction<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<State>
+    where
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
     {
         let mut ip = self.ip;
         let mut next_inst = || -> usize {
@@ -62,8 +563,12 @@ impl<M: Memory> Computer<M> {
             ip += 1;
             ret
         };
-        let mut inst = self.memory.read(next_inst())?;
-        let op_code = (inst % 100).try_into()?;
+        let word = self.fetch(next_inst())?;
+        let mut inst = opcode_digits(&word);
+        let op_code = match OpCode::try_from(inst % 100) {
+            Ok(op_code) => op_code,
+            Err(_) => return self.execute_extension(&word),
+        };
         inst /= 100;
         let mut pop_mode = || -> Result<Mode> {
             let mode = (inst % 10).try_into();
@@ -72,18 +577,23 @@ impl<M: Memory> Computer<M> {
         };
         match op_code {
             OpCode::Add => {
-                let res =
-                    self.read(next_inst(), pop_mode()?)? + self.read(next_inst(), pop_mode()?)?;
+                let res = add(
+                    self.read(next_inst(), pop_mode()?)?,
+                    self.read(next_inst(), pop_mode()?)?,
+                )?;
                 self.write(next_inst(), pop_mode()?, res)?;
             }
             OpCode::Multiply => {
-                let res =
-                    self.read(next_inst(), pop_mode()?)? * self.read(next_inst(), pop_mode()?)?;
+                let res = mul(
+                    self.read(next_inst(), pop_mode()?)?,
+                    self.read(next_inst(), pop_mode()?)?,
+                )?;
                 self.write(next_inst(), pop_mode()?, res)?;
             }
-            OpCode::Input => {
-                self.write(next_inst(), pop_mode()?, read()?)?;
-            }
+            OpCode::Input => match read()? {
+                Some(value) => self.write(next_inst(), pop_mode()?, value)?,
+                None => return Ok(State::NeedsInput),
+            },
             OpCode::Output => {
                 write(self.read(next_inst(), pop_mode()?)?)?;
             }
@@ -103,6 +613,10 @@ impl<M: Memory> Computer<M> {
             OpCode::Equals => {
                 self.write_if(Ordering::Equal, &mut next_inst, &mut pop_mode)?;
             }
+            OpCode::AdjustRelativeBase => {
+                let offset = self.read(next_inst(), pop_mode()?)?;
+                self.relative_base = add(self.relative_base.clone(), offset)?;
+            }
             OpCode::Halt => return Ok(State::Halted),
         };
         self.ip = ip;
@@ -115,10 +629,10 @@ impl<M: Memory> Computer<M> {
         next_inst: &mut dyn FnMut() -> usize,
         pop_mode: &mut dyn FnMut() -> Result<Mode>,
     ) -> Result<Option<usize>> {
-        let zero = self.read(next_inst(), pop_mode()?)?.eq(&0);
+        let zero = self.read(next_inst(), pop_mode()?)?.is_zero();
         let target = self.read(next_inst(), pop_mode()?)?;
         if zero ^ nonzero {
-            Ok(Some(target as usize))
+            to_address(target).map(Some)
         } else {
             Ok(None)
         }
@@ -133,57 +647,340 @@ impl<M: Memory> Computer<M> {
         let res = self
             .read(next_inst(), pop_mode()?)?
             .cmp(&self.read(next_inst(), pop_mode()?)?);
-        let value = if res == order { 1 } else { 0 };
+        let value = if res == order {
+            M::Value::one()
+        } else {
+            M::Value::zero()
+        };
         self.write(next_inst(), pop_mode()?, value)?;
         Ok(())
     }
 
-    fn run_all<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<()>
+    fn run_until<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<State>
     where
-        I: FnMut() -> Result<Value>,
-        O: FnMut(Value) -> Result<()>,
+        I: FnMut() -> Result<Option<M::Value>> + ?Sized,
+        O: FnMut(M::Value) -> Result<()> + ?Sized,
     {
         loop {
             match self.step(read, write)? {
-                State::Running => (),
-                State::Halted => return Ok(()),
+                State::Running | State::Watchpoint { .. } => (),
+                state => return Ok(state),
             }
         }
     }
 
-    pub fn execute(&mut self) -> Result<Value> {
+    fn run_all<I, O>(&mut self, read: &mut I, write: &mut O) -> Result<()>
+    where
+        I: FnMut() -> Result<M::Value>,
+        O: FnMut(M::Value) -> Result<()>,
+    {
+        self.run_until(&mut || read().map(Some), write)?;
+        Ok(())
+    }
+
+    pub fn push_input(&mut self, value: M::Value) {
+        self.inputs.push_back(value);
+    }
+
+    pub fn extend_inputs<I: IntoIterator<Item = M::Value>>(&mut self, values: I) {
+        self.inputs.extend(values);
+    }
+
+    /// Inputs queued but not yet read by the program.
+    pub fn pending_inputs(&self) -> &VecDeque<M::Value> {
+        &self.inputs
+    }
+
+    /// Runs on the queued inputs until the program halts or needs more input.
+    ///
+    /// Outputs are collected in the output buffer. Returns `State::NeedsInput`
+    /// without consuming the input instruction, so execution can resume after
+    /// `push_input`. With `OutputLimit::Block`, returns `State::OutputFull`
+    /// the same way once the buffer is full. Errors are reported as
+    /// `State::Faulted`.
+    pub fn run_until_blocked(&mut self) -> State {
+        self.run_buffered(|_| None)
+    }
+
+    /// Like `run_until_blocked`, but returns `State::StepLimitExceeded` after
+    /// executing `max_steps` instructions. Execution can be resumed later.
+    pub fn run_with_limit(&mut self, max_steps: usize) -> State {
+        self.run_buffered(|steps| {
+            if steps >= max_steps {
+                Some(State::StepLimitExceeded)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Executes one instruction on the queued inputs like `run_until_blocked`.
+    ///
+    /// Returns `State::Running` if the program can continue.
+    pub fn single_step(&mut self) -> State {
+        self.run_buffered(|steps| {
+            if steps >= 1 {
+                Some(State::Running)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Like `run_until_blocked`, but returns `State::DeadlineExceeded` once
+    /// `deadline` has passed. The clock is only checked every few hundred
+    /// instructions.
+    pub fn run_with_deadline(&mut self, deadline: Instant) -> State {
+        self.run_buffered(|steps| {
+            if steps % CLOCK_INTERVAL == 0 && Instant::now() >= deadline {
+                Some(State::DeadlineExceeded)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn run_with_timeout(&mut self, timeout: Duration) -> State {
+        self.run_with_deadline(Instant::now() + timeout)
+    }
+
+    /// Runs on the queued inputs, calling `interrupt` with the number of
+    /// executed instructions before each step.
+    fn run_buffered<F>(&mut self, mut interrupt: F) -> State
+    where
+        F: FnMut(usize) -> Option<State>,
+    {
+        let mut outputs = mem::take(&mut self.outputs);
+        let limit = self.output_limit;
+        let mut write = |value| match limit {
+            OutputLimit::Fail(max) | OutputLimit::Block(max) if outputs.len() >= max => {
+                Err(Error::OutputBufferFull(max))
+            }
+            _ => {
+                outputs.push(value);
+                Ok(())
+            }
+        };
+        let state = self.with_queued_inputs(|comp, read| {
+            let mut steps = 0;
+            loop {
+                if let Some(state) = interrupt(steps) {
+                    return Ok(state);
+                }
+                match comp.step(read, &mut write)? {
+                    State::Running => steps += 1,
+                    state => return Ok(state),
+                }
+            }
+        });
+        self.outputs = outputs;
+        // Interrupts stop between instructions, so only this knows the
+        // state the run ended in.
+        self.state = match (state, limit) {
+            (Ok(state), _) => state,
+            (Err(Error::OutputBufferFull(_)), OutputLimit::Block(_)) => State::OutputFull,
+            (Err(err), _) => State::Faulted(err),
+        };
+        self.state.clone()
+    }
+
+    pub fn set_output_limit(&mut self, limit: OutputLimit) {
+        self.output_limit = limit;
+    }
+
+    /// Removes and returns all buffered outputs.
+    pub fn take_outputs(&mut self) -> Vec<M::Value> {
+        mem::take(&mut self.outputs)
+    }
+
+    /// The most recent output still in the buffer.
+    pub fn last_output(&self) -> Option<M::Value> {
+        self.outputs.last().cloned()
+    }
+
+    /// Runs until the next output and returns it, or `None` once the program halts.
+    ///
+    /// Fails with `Error::NeedsInput` if the input queue runs dry; push more
+    /// input and call it again to resume.
+    pub fn run_until_output(&mut self) -> Result<Option<M::Value>> {
+        self.with_queued_inputs(|comp, read| loop {
+            let mut output = None;
+            let mut write = |value| {
+                output = Some(value);
+                Ok(())
+            };
+            match comp.step(read, &mut write)? {
+                State::Running | State::Watchpoint { .. } if output.is_some() => return Ok(output),
+                State::Running | State::Watchpoint { .. } => (),
+                State::Halted => return Ok(None),
+                State::NeedsInput => return Err(Error::NeedsInput),
+                state => unreachable!("step never returns {:?}", state),
+            }
+        })
+    }
+
+    fn with_queued_inputs<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self, &mut dyn FnMut() -> Result<Option<M::Value>>) -> R,
+    {
+        let mut inputs = mem::take(&mut self.inputs);
+        let result = f(self, &mut || Ok(inputs.pop_front()));
+        self.inputs = inputs;
+        result
+    }
+
+    pub fn execute(&mut self) -> Result<M::Value> {
         self.run_all(&mut reading_not_supported, &mut writing_not_supported)?;
-        Ok(self.memory.read(0)?)
+        self.memory.read(0)
     }
 
     pub fn run<I, O>(&mut self, mut read: I, mut write: O) -> Result<()>
     where
-        I: FnMut() -> Result<Value>,
-        O: FnMut(Value) -> Result<()>,
+        I: FnMut() -> Result<M::Value>,
+        O: FnMut(M::Value) -> Result<()>,
     {
         self.run_all(&mut read, &mut write)
     }
 
-    fn read(&self, address: usize, mode: Mode) -> Result<Value> {
-        let value = self.memory.read(address);
+    fn read(&mut self, address: usize, mode: Mode) -> Result<M::Value> {
+        let value = self.fetch(address);
         match mode {
-            Mode::Position => self.memory.read(value? as usize),
+            Mode::Position => self.load(to_address(value?)?),
             Mode::Immediate => value,
+            Mode::Relative => self.load(to_address(add(self.relative_base.clone(), value?)?)?),
         }
     }
 
-    fn write(&mut self, address: usize, mode: Mode, value: Value) -> Result<()> {
+    fn write(&mut self, address: usize, mode: Mode, value: M::Value) -> Result<()> {
         match mode {
-            Mode::Position => self
-                .memory
-                .write(self.memory.read(address)? as usize, value),
+            Mode::Position => self.store(to_address(self.fetch(address)?)?, value),
             Mode::Immediate => Err(Error::InvalidWriteMode(mode)),
+            Mode::Relative => self.store(
+                to_address(add(self.relative_base.clone(), self.fetch(address)?)?)?,
+                value,
+            ),
+        }
+    }
+
+    // All memory accesses of an instruction go through `fetch` for the
+    // instruction and its parameters and `load`/`store` for the data.
+
+    fn fetch(&self, address: usize) -> Result<M::Value> {
+        self.memory.read(address)
+    }
+
+    fn load(&mut self, address: usize) -> Result<M::Value> {
+        self.watch(address, Access::Read);
+        if let Some(effects) = &mut self.effects {
+            effects.reads.push(address);
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.read(address);
+        }
+        match self.device_at(address) {
+            Some(mapping) => device::read(mapping, address),
+            None => self.memory.read(address),
+        }
+    }
+
+    fn store(&mut self, address: usize, value: M::Value) -> Result<()> {
+        self.watch(address, Access::Write);
+        if let Some(effects) = &mut self.effects {
+            effects.writes.push((address, value.clone()));
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.write(address);
+        }
+        if let Some(mapping) = self.device_at(address) {
+            return device::write(mapping, address, value);
+        }
+        if self.invalidate(address) {
+            self.self_modified = true;
+        }
+        self.memory.write(address, value)
+    }
+
+    fn watch(&mut self, address: usize, access: Access) {
+        if self.watch_hit.is_none() && self.watchpoints.contains(&address) {
+            self.watch_hit = Some((address, access));
+        }
+    }
+}
+
+pub struct Steps<'c, M: Memory, I, O> {
+    computer: &'c mut Computer<M>,
+    read: I,
+    write: O,
+}
+
+impl<'c, M, I, O> Iterator for Steps<'c, M, I, O>
+where
+    M: Memory,
+    I: FnMut() -> Result<M::Value>,
+    O: FnMut(M::Value) -> Result<()>,
+{
+    type Item = Result<State>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.computer.next_step(&mut self.read, &mut self.write)
+    }
+}
+
+pub struct Outputs<'c, M: Memory, I> {
+    computer: &'c mut Computer<M>,
+    read: I,
+    failed: bool,
+}
+
+impl<'c, M, I> Iterator for Outputs<'c, M, I>
+where
+    M: Memory,
+    I: FnMut() -> Result<M::Value>,
+{
+    type Item = Result<M::Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.computer.halted {
+            return None;
         }
+        let source = &mut self.read;
+        let result = self.computer.with_queued_inputs(|comp, queued| loop {
+            let mut read = || match queued()? {
+                Some(value) => Ok(Some(value)),
+                None => source().map(Some),
+            };
+            let mut output = None;
+            let mut write = |value| {
+                output = Some(value);
+                Ok(())
+            };
+            match comp.step(&mut read, &mut write) {
+                Ok(State::Halted) => {
+                    comp.halted = true;
+                    return None;
+                }
+                Ok(_) if output.is_some() => return output.map(Ok),
+                Ok(_) => (),
+                Err(err) => return Some(Err(err)),
+            }
+        });
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Steps through a program that does not use input or output.
+impl<M: Memory> Iterator for Computer<M> {
+    type Item = Result<State>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_step(&mut reading_not_supported, &mut writing_not_supported)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::memory::{GrowableMemory, SparseMemory};
     use super::*;
 
     #[test]
@@ -227,4 +1024,589 @@ mod tests {
         assert_eq!(comp.read(5, Mode::Immediate)?, 0);
         Ok(())
     }
+
+    #[test]
+    fn test_iterator() -> Result<()> {
+        let mut comp = Computer::new(vec![1, 0, 0, 0, 1, 0, 0, 0, 99]);
+        comp.by_ref().take(1).try_for_each(|state| {
+            assert_eq!(state?, State::Running);
+            Ok(())
+        })?;
+        assert_eq!(comp.memory.read(0)?, 2);
+        assert_eq!(
+            comp.collect::<Result<Vec<_>>>()?,
+            vec![State::Running, State::Halted]
+        );
+
+        let mut comp = Computer::new(vec![1105, 1, 0]);
+        assert_eq!(comp.by_ref().take(1_000).count(), 1_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_steps_with_io() -> Result<()> {
+        let mut out = Vec::new();
+        let mut comp = Computer::new(vec![3, 0, 4, 0, 99]);
+        let states = comp
+            .steps(
+                || Ok(42),
+                |value| {
+                    out.push(value);
+                    Ok(())
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(states.len(), 3);
+        assert_eq!(out, vec![42]);
+        Ok(())
+    }
+
+    struct Cells(Vec<Value>);
+
+    impl Memory for Cells {
+        type Value = Value;
+
+        fn read(&self, address: usize) -> Result<Value> {
+            self.0.read(address)
+        }
+
+        fn write(&mut self, address: usize, value: Value) -> Result<()> {
+            self.0.write(address, value)
+        }
+    }
+
+    #[test]
+    fn test_slices() -> Result<()> {
+        let mut vec: Vec<Value> = vec![0; 5];
+        let mut cells = Cells(vec![0; 5]);
+        let memories: [&mut dyn Memory<Value = Value>; 2] = [&mut vec, &mut cells];
+        for memory in memories {
+            memory.write_slice(1, &[1, 2, 3])?;
+            assert_eq!(memory.read_slice(0..5)?, vec![0, 1, 2, 3, 0]);
+            assert_eq!(memory.read_slice(2..2)?, Vec::<Value>::new());
+            assert_eq!(memory.read_slice(3..7), Err(Error::SegFault(5)));
+            assert_eq!(memory.write_slice(4, &[1, 2]), Err(Error::SegFault(5)));
+            assert_eq!(memory.read_slice(0..5)?, vec![0, 1, 2, 3, 0]);
+            assert!(memory.write_slice(usize::MAX, &[1, 2]).is_err());
+        }
+        Ok(())
+    }
+
+    fn outputs(program: Vec<Value>) -> Result<Vec<Value>> {
+        let mut out = Vec::new();
+        Computer::new(program).run(reading_not_supported, |value| {
+            out.push(value);
+            Ok(())
+        })?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_relative_quine() -> Result<()> {
+        let quine: Vec<Value> = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut out = Vec::new();
+        Computer::new(GrowableMemory::new(quine.clone())).run(reading_not_supported, |value| {
+            out.push(value);
+            Ok(())
+        })?;
+        assert_eq!(out, quine);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_memory() -> Result<()> {
+        // Stores 42 at a far away address and reads it back.
+        let program: Vec<Value> = vec![1101, 40, 2, 1 << 40, 4, 1 << 40, 99];
+        let mut out = Vec::new();
+        Computer::new(SparseMemory::from(program)).run(reading_not_supported, |value| {
+            out.push(value);
+            Ok(())
+        })?;
+        assert_eq!(out, vec![42]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_integers() {
+        // Squares 2^40 twice, which overflows an i64 and then an i128.
+        let program: Vec<Value> = vec![2, 13, 13, 13, 4, 13, 2, 13, 13, 13, 4, 13, 99, 1 << 40];
+        let mut comp = Computer::new(GrowableMemory::<num::BigInt>::new(
+            program.iter().map(|&value| value.into()).collect(),
+        ));
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let outputs = comp.take_outputs();
+        assert_eq!(outputs[0], num::BigInt::from(1_i128 << 80));
+        assert_eq!(outputs[1], num::BigInt::from(1) << 160);
+
+        // Only the low digits of an instruction matter.
+        let program = vec![10_i128.pow(30) + 104, 1 << 100, 99];
+        let mut comp = Computer::new(GrowableMemory::new(program));
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        assert_eq!(comp.take_outputs(), vec![1 << 100]);
+    }
+
+    #[test]
+    fn test_large_numbers() -> Result<()> {
+        assert_eq!(
+            outputs(vec![1102, 34_915_192, 34_915_192, 7, 4, 7, 99, 0])?,
+            vec![1_219_070_632_396_864]
+        );
+        assert_eq!(
+            outputs(vec![104, 1_125_899_906_842_624, 99])?,
+            vec![1_125_899_906_842_624]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_write() -> Result<()> {
+        // Moves the relative base to 10 and stores 7 at 10 + 2.
+        let mut comp = Computer::new(vec![109, 10, 21101, 3, 4, 2, 99, 0, 0, 0, 0, 0, 0]);
+        comp.execute()?;
+        assert_eq!(comp.memory.read(12)?, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_until_blocked() -> Result<()> {
+        // Outputs the sum of two inputs.
+        let mut comp = Computer::new(vec![3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0]);
+        assert_eq!(comp.run_until_blocked(), State::NeedsInput);
+        comp.push_input(1);
+        assert_eq!(comp.run_until_blocked(), State::NeedsInput);
+        comp.push_input(2);
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        assert_eq!(comp.last_output(), Some(3));
+        assert_eq!(comp.take_outputs(), vec![3]);
+        assert_eq!(comp.last_output(), None);
+        let mut comp = Computer::new(vec![55]);
+        assert_eq!(
+            comp.run_until_blocked(),
+            State::Faulted(Error::At {
+                ip: 0,
+                instruction: Some(55),
+                modes: [Some(Mode::Position); 3],
+                source: Box::new(Error::InvalidOpCode(55)),
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_with_limit() {
+        let mut comp = Computer::new(vec![1105, 1, 0]);
+        assert_eq!(comp.run_with_limit(1000), State::StepLimitExceeded);
+        assert_eq!(comp.state(), &State::StepLimitExceeded);
+        let mut comp = Computer::new(vec![1101, 1, 1, 0, 104, 7, 99]);
+        assert_eq!(comp.run_with_limit(1), State::StepLimitExceeded);
+        assert!(comp.take_outputs().is_empty());
+        assert_eq!(comp.run_with_limit(2), State::Halted);
+        assert_eq!(comp.take_outputs(), vec![7]);
+    }
+
+    #[test]
+    fn test_run_with_deadline() {
+        let mut comp = Computer::new(vec![1105, 1, 0]);
+        assert_eq!(
+            comp.run_with_timeout(Duration::from_millis(10)),
+            State::DeadlineExceeded
+        );
+        assert_eq!(comp.state(), &State::DeadlineExceeded);
+        let mut comp = Computer::new(vec![104, 7, 99]);
+        assert_eq!(
+            comp.run_with_timeout(Duration::from_secs(60)),
+            State::Halted
+        );
+    }
+
+    #[test]
+    fn test_single_step() {
+        let mut comp = Computer::new(vec![109, 3, 204, 1, 99]);
+        assert_eq!(comp.single_step(), State::Running);
+        assert_eq!((comp.ip(), comp.relative_base()), (2, 3));
+        assert_eq!(comp.single_step(), State::Running);
+        assert_eq!(comp.take_outputs(), vec![99]);
+        assert_eq!(comp.single_step(), State::Halted);
+        assert_eq!(comp.memory().read(0), Ok(109));
+    }
+
+    #[test]
+    fn test_watchpoints() -> Result<()> {
+        // Copies m[9] to m[10] twice, then halts.
+        let mut comp = Computer::new(vec![1001, 9, 0, 10, 1001, 9, 0, 10, 99, 7, 0]);
+        comp.add_watchpoint(10);
+        let hit = State::Watchpoint {
+            address: 10,
+            access: Access::Write,
+        };
+        assert_eq!(comp.run_until_blocked(), hit);
+        assert_eq!(comp.state(), &hit);
+        assert_eq!(comp.ip(), 4);
+        assert_eq!(comp.run_until_blocked(), hit);
+        assert!(comp.remove_watchpoint(10));
+        comp.add_watchpoint(9);
+        comp.reset();
+        assert_eq!(
+            comp.single_step(),
+            State::Watchpoint {
+                address: 9,
+                access: Access::Read
+            }
+        );
+        assert_eq!(comp.execute()?, 1001);
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        before: Vec<(usize, OpCode)>,
+        effects: Vec<Effects>,
+    }
+
+    impl Tracer for Recorder {
+        fn before(&mut self, instruction: &Instruction) {
+            self.before.push((instruction.address, instruction.op));
+        }
+
+        fn after(&mut self, _: &Instruction, result: &Result<State>, effects: &Effects) {
+            assert!(result.is_ok());
+            self.effects.push(effects.clone());
+        }
+    }
+
+    #[test]
+    fn test_tracer() {
+        let mut comp = Computer::new(vec![1, 0, 0, 7, 4, 7, 99, 0]);
+        comp.set_tracer(Recorder::default());
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let recorder = comp.tracer::<Recorder>().unwrap();
+        assert_eq!(
+            recorder.before,
+            vec![(0, OpCode::Add), (4, OpCode::Output), (6, OpCode::Halt)]
+        );
+        assert_eq!(
+            recorder.effects[0],
+            Effects {
+                reads: vec![0, 0],
+                writes: vec![(7, 2)],
+                output: None
+            }
+        );
+        assert_eq!(recorder.effects[1].output, Some(2));
+        assert!(comp.fork().take_tracer().is_none());
+    }
+
+    #[test]
+    fn test_tracer_waiting_input() {
+        let mut comp = Computer::new(vec![3, 5, 4, 5, 99, 0]);
+        comp.set_tracer(Recorder::default());
+        assert_eq!(comp.run_until_blocked(), State::NeedsInput);
+        comp.push_input(7);
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let recorder = comp.tracer::<Recorder>().unwrap();
+        assert_eq!(
+            recorder.before,
+            vec![
+                (0, OpCode::Input),
+                (0, OpCode::Input),
+                (2, OpCode::Output),
+                (4, OpCode::Halt)
+            ]
+        );
+        assert_eq!(recorder.effects.len(), 3);
+        assert_eq!(recorder.effects[0].writes, vec![(5, 7)]);
+    }
+
+    #[test]
+    fn test_json_tracer() -> Result<()> {
+        let mut comp = Computer::new(vec![1001, 7, 3, 7, 4, 7, 99, 2]);
+        comp.set_tracer(JsonTracer::new(Vec::new()));
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let tracer = comp.take_tracer().unwrap() as Box<dyn Any>;
+        let trace = tracer.downcast::<JsonTracer<Vec<u8>>>().unwrap();
+        let trace = String::from_utf8(trace.finish().unwrap()).unwrap();
+        let lines = trace.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"ip":0,"opcode":"add","params":[[0,7],[1,3],[0,7]],"writes":[[7,5]]}"#,
+                r#"{"ip":4,"opcode":"out","params":[[0,7]],"writes":[]}"#,
+                r#"{"ip":6,"opcode":"halt","params":[],"writes":[]}"#,
+            ]
+        );
+        for line in lines {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile() {
+        // Counts down from the input, outputting each value.
+        let mut program = vec![3, 20, 4, 20, 1001, 20, -1, 20, 1005, 20, 2, 99];
+        program.resize(21, 0);
+        let mut comp = Computer::new(program);
+        comp.enable_profiling();
+        comp.push_input(3);
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        assert_eq!(comp.take_outputs(), vec![3, 2, 1]);
+        let profile = comp.profile().unwrap();
+        assert_eq!(profile.executions(OpCode::Input), 1);
+        assert_eq!(profile.executions(OpCode::JumpIfTrue), 3);
+        assert_eq!(profile.executions(OpCode::Multiply), 0);
+        assert_eq!(profile.hits(4), 3);
+        assert_eq!(profile.total(), 11);
+        assert_eq!(profile.hottest(2), vec![(2, 3), (4, 3)]);
+        assert!(comp.take_profile().is_some());
+        assert!(comp.profile().is_none());
+
+        // Overwrites its own opcode with a halt, which it then runs.
+        let mut comp = Computer::new(vec![1101, 1, 98, 0, 99]);
+        comp.enable_profiling();
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let profile = comp.profile().unwrap();
+        assert_eq!(profile.executions(OpCode::Add), 1);
+        assert_eq!(profile.executions(OpCode::Halt), 1);
+    }
+
+    #[test]
+    fn test_write_stats() {
+        let mut comp = Computer::new(vec![1101, 2, 3, 5, 4, 5, 99]);
+        comp.enable_profiling();
+        comp.enable_heatmap();
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        let mut csv = Vec::new();
+        comp.write_stats(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows = csv
+            .lines()
+            .filter(|row| !row.starts_with("nanoseconds,"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                "kind,key,value",
+                "executions,add,1",
+                "executions,out,1",
+                "executions,halt,1",
+                "hits,0,1",
+                "hits,4,1",
+                "hits,6,1",
+                "reads,5,1",
+                "writes,5,1",
+            ]
+        );
+        assert_eq!(csv.matches("nanoseconds,").count(), 3);
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let mut program = vec![3, 20, 4, 20, 1001, 20, -1, 20, 1005, 20, 2, 99];
+        program.resize(70, 0);
+        let mut comp = Computer::new(program);
+        comp.enable_heatmap();
+        comp.push_input(4);
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        comp.patch(69, 1).unwrap();
+        let heatmap = comp.heatmap().unwrap();
+        assert_eq!(heatmap.reads(20), 12);
+        assert_eq!(heatmap.writes(20), 5);
+        assert_eq!(heatmap.reads(2), 0);
+        assert_eq!(heatmap.accesses().collect::<Vec<_>>(), vec![(20, 12, 5)]);
+        assert_eq!(heatmap.to_string(), "     0:                     @\n");
+    }
+
+    #[test]
+    fn test_sparse_heatmap() {
+        let far: Value = 1_000_000_000_000;
+        let mut comp = Computer::new(SparseMemory::from(vec![
+            1101, 2, 3, far, 1, far, far, 20, 99,
+        ]));
+        comp.enable_heatmap();
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        assert_eq!(
+            comp.heatmap().unwrap().to_string(),
+            "     0:                     =\n   ...\n1000000000000: @\n"
+        );
+    }
+
+    #[test]
+    fn test_reset() -> Result<()> {
+        let mut comp = Computer::new(vec![1, 0, 0, 0, 99]);
+        assert_eq!(comp.execute()?, 2);
+        comp.reset();
+        comp.patch(1, 4)?;
+        assert_eq!(comp.execute()?, 100);
+        comp.reset();
+        assert_eq!(comp.execute()?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot() -> Result<()> {
+        // Adds the two inputs.
+        let mut comp = Computer::new(vec![3, 9, 3, 10, 1, 9, 10, 11, 99, 0, 0, 0]);
+        comp.push_input(1);
+        assert_eq!(comp.run_until_blocked(), State::NeedsInput);
+        let snapshot = comp.snapshot();
+        let mut fork = comp.fork();
+
+        comp.push_input(2);
+        comp.run_until_blocked();
+        fork.push_input(5);
+        fork.run_until_blocked();
+        assert_eq!(comp.memory[11], 3);
+        assert_eq!(fork.memory[11], 6);
+
+        comp.restore(&snapshot);
+        comp.push_input(10);
+        comp.run_until_blocked();
+        assert_eq!(comp.memory[11], 11);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() -> Result<()> {
+        let mut comp = Computer::new(GrowableMemory::<Value>::new(vec![
+            3, 9, 3, 10, 1, 9, 10, 11, 99,
+        ]));
+        comp.push_input(1);
+        assert_eq!(comp.run_until_blocked(), State::NeedsInput);
+        let saved = serde_json::to_string(&comp).unwrap();
+        let mut comp: Computer<GrowableMemory> = serde_json::from_str(&saved).unwrap();
+        comp.push_input(2);
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        assert_eq!(comp.memory.read(11)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_outputs() -> Result<()> {
+        // Doubles every input until it reads a zero.
+        let mut comp = Computer::new(vec![
+            3, 15, 1006, 15, 14, 102, 2, 15, 16, 4, 16, 1105, 1, 0, 99, 0, 0,
+        ]);
+        comp.push_input(1);
+        let mut inputs = vec![3, 2, 0].into_iter();
+        let outputs = comp
+            .outputs(|| inputs.next().ok_or(Error::InputClosed))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(outputs, vec![2, 6, 4]);
+
+        let mut comp = Computer::new(vec![4, 0, 3, 0, 99]);
+        let mut outputs = comp.outputs(|| Err(Error::InputClosed));
+        assert_eq!(outputs.next(), Some(Ok(4)));
+        assert_eq!(outputs.next(), Some(Err(Error::InputClosed)));
+        assert_eq!(outputs.next(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_limit() -> Result<()> {
+        let program = vec![104, 1, 104, 2, 104, 3, 99];
+        let mut comp = Computer::new(program.clone());
+        comp.set_output_limit(OutputLimit::Fail(2));
+        assert_eq!(
+            comp.run_until_blocked(),
+            State::Faulted(Error::OutputBufferFull(2))
+        );
+        assert_eq!(comp.take_outputs(), vec![1, 2]);
+
+        let mut comp = Computer::new(program);
+        comp.set_output_limit(OutputLimit::Block(2));
+        assert_eq!(comp.run_until_blocked(), State::OutputFull);
+        assert_eq!(comp.take_outputs(), vec![1, 2]);
+        assert_eq!(comp.run_until_blocked(), State::Halted);
+        assert_eq!(comp.take_outputs(), vec![3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_until_output() -> Result<()> {
+        let mut comp = Computer::new(vec![104, 1, 104, 2, 99]);
+        assert_eq!(comp.run_until_output()?, Some(1));
+        assert_eq!(comp.run_until_output()?, Some(2));
+        assert_eq!(comp.run_until_output()?, None);
+
+        let mut comp = Computer::new(vec![3, 0, 4, 0, 99]);
+        assert_eq!(comp.run_until_output(), Err(Error::NeedsInput));
+        comp.push_input(5);
+        assert_eq!(comp.run_until_output()?, Some(5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extend_inputs() -> Result<()> {
+        let mut comp = Computer::new(vec![3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0]);
+        comp.extend_inputs(vec![20, 22]);
+        assert_eq!(comp.run_until_output()?, Some(42));
+        Ok(())
+    }
+
+    #[test]
+    fn test_negative_address() {
+        let mut comp = Computer::new(vec![1, -1, 0, 0, 99]);
+        let err = comp.execute().unwrap_err();
+        assert_eq!(err.root_cause(), &Error::NegativeAddress(-1));
+        let mut comp = Computer::new(vec![1, 0, 0, -5, 99]);
+        let err = comp.execute().unwrap_err();
+        assert_eq!(err.root_cause(), &Error::NegativeAddress(-5));
+        assert_eq!(
+            err.to_string(),
+            "Tried to access negative address -5 at ip 0 \
+             (instruction 1, modes [Some(Position), Some(Position), Some(Position)])"
+        );
+        let mut comp = Computer::new(vec![1105, 1, -3]);
+        let err = comp.execute().unwrap_err();
+        assert_eq!(err.root_cause(), &Error::NegativeAddress(-3));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let programs = vec![
+            vec![1002, 5, 4, 0, 99, 1 << 62],
+            vec![1101, Value::MAX, 1, 0, 99],
+            vec![109, Value::MAX, 109, 1, 99],
+            vec![109, Value::MAX, 2201, 1, 0, 0, 99],
+        ];
+        for program in programs {
+            for engine in [Engine::Interpreter, Engine::Decoded] {
+                let mut comp = Computer::new(program.clone());
+                comp.set_engine(engine);
+                let err = comp.execute().unwrap_err();
+                assert_eq!(err.root_cause(), &Error::Overflow);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inspection() -> Result<()> {
+        // Outputs 2 + 3 for every input.
+        let mut program = vec![3, 20, 1101, 2, 3, 21, 4, 21, 1105, 1, 0];
+        program.resize(22, 0);
+        let mut comp = Computer::new(program);
+        comp.set_engine(Engine::Decoded);
+        assert_eq!(comp.state(), &State::Running);
+        comp.push_input(0);
+        assert_eq!(comp.run_until_blocked(), State::NeedsInput);
+        assert_eq!(comp.state(), &State::NeedsInput);
+        assert_eq!(comp.take_outputs(), vec![5]);
+        comp.memory_mut().write(3, 40)?;
+        comp.push_input(0);
+        comp.run_until_blocked();
+        assert_eq!(comp.take_outputs(), vec![43]);
+
+        let mut comp = Computer::new(vec![1105, 1, -1]);
+        comp.run_until_blocked();
+        assert_eq!(
+            comp.state(),
+            &State::Faulted(Error::at(0, Some(1105))(Error::NegativeAddress(-1)))
+        );
+        comp.reset();
+        assert_eq!(comp.state(), &State::Running);
+        Ok(())
+    }
 }
@@ -3,9 +3,11 @@ use super::types::Value;
 use std::convert::TryFrom;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mode {
     Position,
     Immediate,
+    Relative,
 }
 
 impl TryFrom<Value> for Mode {
@@ -15,6 +17,7 @@ impl TryFrom<Value> for Mode {
         match value {
             0 => Ok(Self::Position),
             1 => Ok(Self::Immediate),
+            2 => Ok(Self::Relative),
             val => Err(Error::InvalidMode(val)),
         }
     }
@@ -0,0 +1,275 @@
+use super::errors::{Error, Result};
+use super::types::Value;
+use super::{Computer, Memory, State};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// The address day 23 uses for the NAT.
+pub const NAT_ADDRESS: Value = 255;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Packet {
+    pub x: Value,
+    pub y: Value,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event {
+    /// A computer sent a packet to another computer or the NAT.
+    Sent {
+        from: usize,
+        to: Value,
+        packet: Packet,
+    },
+    /// A round passed without any computer having a packet to read or
+    /// sending one.
+    Idle,
+    /// The NAT woke the network by sending its last packet to computer 0.
+    Resumed(Packet),
+}
+
+struct Node<M: Memory> {
+    computer: Computer<M>,
+    /// Outputs of an incomplete packet.
+    pending: Vec<Value>,
+}
+
+/// Computers addressed `0..n` that exchange `(address, x, y)` packets.
+///
+/// Every computer first receives its address. Afterwards it reads the `x`
+/// and `y` of the packets sent to it, or `-1` when its queue is empty.
+pub struct Network<M: Memory> {
+    nodes: Vec<Node<M>>,
+    nat: Option<(Value, Option<Packet>)>,
+}
+
+impl<M: Memory<Value = Value> + Clone> Network<M> {
+    /// Runs `size` copies of `program`.
+    pub fn new(program: M, size: usize) -> Self {
+        let nodes = (0..size)
+            .map(|address| {
+                let mut computer = Computer::new(program.clone());
+                computer.push_input(address as Value);
+                Node {
+                    computer,
+                    pending: Vec::new(),
+                }
+            })
+            .collect();
+        Self { nodes, nat: None }
+    }
+}
+
+impl<M: Memory<Value = Value>> Network<M> {
+    /// Adds a NAT at `address` that remembers the last packet sent to it and
+    /// sends it to computer 0 whenever the network is idle.
+    pub fn with_nat(mut self, address: Value) -> Self {
+        self.nat = Some((address, None));
+        self
+    }
+
+    pub fn computer(&self, address: usize) -> Option<&Computer<M>> {
+        self.nodes.get(address).map(|node| &node.computer)
+    }
+
+    /// The last packet the NAT received.
+    pub fn nat_packet(&self) -> Option<Packet> {
+        self.nat.and_then(|(_, packet)| packet)
+    }
+
+    /// Lets every computer run until it waits for input and delivers the
+    /// packets sent in the meantime.
+    ///
+    /// Halted computers are skipped; packets sent to them are dropped and do
+    /// not keep the network from being idle.
+    pub fn round(&mut self) -> Result<Vec<Event>> {
+        let mut idle = true;
+        let mut sent = VecDeque::new();
+        for (from, node) in self.nodes.iter_mut().enumerate() {
+            if *node.computer.state() == State::Halted {
+                continue;
+            }
+            if node.computer.pending_inputs().is_empty() {
+                node.computer.push_input(-1);
+            } else {
+                idle = false;
+            }
+            match node.computer.run_until_blocked() {
+                State::NeedsInput | State::Halted => (),
+                State::Faulted(err) => return Err(err),
+                state => unreachable!("run_until_blocked never returns {:?}", state),
+            }
+            node.pending.extend(node.computer.take_outputs());
+            for chunk in node.pending.chunks_exact(3) {
+                let packet = Packet {
+                    x: chunk[1],
+                    y: chunk[2],
+                };
+                sent.push_back((from, chunk[0], packet));
+            }
+            let complete = node.pending.len() / 3 * 3;
+            node.pending.drain(..complete);
+        }
+
+        let mut events = Vec::new();
+        idle &= sent.is_empty();
+        let nodes = &mut self.nodes;
+        for (from, to, packet) in sent {
+            events.push(Event::Sent { from, to, packet });
+            let node = usize::try_from(to)
+                .ok()
+                .and_then(|address| nodes.get_mut(address));
+            match (&mut self.nat, node) {
+                (Some((address, last)), _) if *address == to => *last = Some(packet),
+                (_, Some(node)) => {
+                    if *node.computer.state() != State::Halted {
+                        node.computer.extend_inputs(vec![packet.x, packet.y]);
+                    }
+                }
+                _ => return Err(Error::UnknownAddress { from, to }),
+            }
+        }
+        if idle {
+            events.push(Event::Idle);
+            if let (Some(packet), Some(node)) = (self.nat_packet(), self.nodes.first_mut()) {
+                node.computer.extend_inputs(vec![packet.x, packet.y]);
+                events.push(Event::Resumed(packet));
+            }
+        }
+        Ok(events)
+    }
+
+    /// Runs rounds until `check` returns a value for one of the events.
+    pub fn run_until<T, F>(&mut self, mut check: F) -> Result<T>
+    where
+        F: FnMut(&Event) -> Option<T>,
+    {
+        loop {
+            if let Some(value) = self.round()?.iter().find_map(&mut check) {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::asm::assemble;
+
+    /// Computer 0 sends `(0, 0)` to computer 1. Every computer adds one to
+    /// the `y` of the packets it receives and passes them on to the next
+    /// address; the last one sends them to the NAT.
+    fn relay(size: usize) -> Vec<Value> {
+        let source = format!(
+            "
+            const SIZE = {}
+            var addr
+            var next
+            var x
+            var y
+            var t
+                in addr
+                add addr, #1, next
+                eq next, #SIZE, t
+                jf t, #start
+                mov #255, next
+            start:
+                jt addr, #loop
+                out next
+                out #0
+                out #0
+            loop:
+                in x
+                eq x, #-1, t
+                jt t, #loop
+                in y
+                add y, #1, y
+                out next
+                out x
+                out y
+                jmp loop",
+            size
+        );
+        assemble(&source).unwrap()
+    }
+
+    #[test]
+    fn test_relay() -> Result<()> {
+        let mut network = Network::new(relay(3), 3);
+        let to_nat = network.run_until(|event| match *event {
+            Event::Sent { from, to, packet } if to == NAT_ADDRESS => Some((from, packet)),
+            _ => None,
+        });
+        assert_eq!(
+            to_nat,
+            Err(Error::UnknownAddress {
+                from: 2,
+                to: NAT_ADDRESS
+            })
+        );
+
+        let mut network = Network::new(relay(3), 3).with_nat(NAT_ADDRESS);
+        let packet = Packet { x: 0, y: 1 };
+        assert_eq!(
+            network.round()?,
+            vec![Event::Sent {
+                from: 0,
+                to: 1,
+                packet: Packet { x: 0, y: 0 }
+            }]
+        );
+        assert_eq!(
+            network.round()?,
+            vec![Event::Sent {
+                from: 1,
+                to: 2,
+                packet
+            }]
+        );
+        network.round()?;
+        assert_eq!(network.nat_packet(), Some(Packet { x: 0, y: 2 }));
+        let resumed = Packet { x: 0, y: 2 };
+        assert_eq!(network.round()?, vec![Event::Idle, Event::Resumed(resumed)]);
+        let resumed = network.run_until(|event| match *event {
+            Event::Resumed(packet) if packet.y > 2 => Some(packet),
+            _ => None,
+        })?;
+        assert_eq!(resumed, Packet { x: 0, y: 5 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_halted() -> Result<()> {
+        // Computer 0 sends one packet to computer 1 and halts; computer 1
+        // reads forever.
+        let program = assemble(
+            "
+            var addr
+            var x
+                in addr
+                jt addr, #loop
+                out #1
+                out #2
+                out #3
+                out #1
+                out #4
+                out #5
+                halt
+            loop:
+                in x
+                jmp loop",
+        )
+        .unwrap();
+        let mut network = Network::new(program, 2);
+        let events = network.round()?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(network.round()?, vec![]);
+        assert_eq!(network.round()?, vec![Event::Idle]);
+        assert_eq!(
+            network.computer(0).map(Computer::state),
+            Some(&State::Halted)
+        );
+        Ok(())
+    }
+}
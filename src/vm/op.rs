@@ -2,8 +2,9 @@ use super::errors::{Error, Result};
 use super::types::Value;
 use std::convert::TryFrom;
 
-#[derive(Debug, PartialEq)]
-pub(super) enum OpCode {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OpCode {
     Add,
     Multiply,
     Input,
@@ -12,9 +13,72 @@ pub(super) enum OpCode {
     JumpIfFalse,
     LessThan,
     Equals,
+    AdjustRelativeBase,
     Halt,
 }
 
+impl OpCode {
+    pub const ALL: [OpCode; 10] = [
+        OpCode::Add,
+        OpCode::Multiply,
+        OpCode::Input,
+        OpCode::Output,
+        OpCode::JumpIfTrue,
+        OpCode::JumpIfFalse,
+        OpCode::LessThan,
+        OpCode::Equals,
+        OpCode::AdjustRelativeBase,
+        OpCode::Halt,
+    ];
+
+    pub fn code(self) -> Value {
+        match self {
+            OpCode::Add => 1,
+            OpCode::Multiply => 2,
+            OpCode::Input => 3,
+            OpCode::Output => 4,
+            OpCode::JumpIfTrue => 5,
+            OpCode::JumpIfFalse => 6,
+            OpCode::LessThan => 7,
+            OpCode::Equals => 8,
+            OpCode::AdjustRelativeBase => 9,
+            OpCode::Halt => 99,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            OpCode::Add => "add",
+            OpCode::Multiply => "mul",
+            OpCode::Input => "in",
+            OpCode::Output => "out",
+            OpCode::JumpIfTrue => "jt",
+            OpCode::JumpIfFalse => "jf",
+            OpCode::LessThan => "lt",
+            OpCode::Equals => "eq",
+            OpCode::AdjustRelativeBase => "arb",
+            OpCode::Halt => "halt",
+        }
+    }
+
+    pub fn parameters(self) -> usize {
+        match self {
+            OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals => 3,
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => 2,
+            OpCode::Input | OpCode::Output | OpCode::AdjustRelativeBase => 1,
+            OpCode::Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter is an address that gets written.
+    pub fn writes(self) -> bool {
+        matches!(
+            self,
+            OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals | OpCode::Input
+        )
+    }
+}
+
 impl TryFrom<Value> for OpCode {
     type Error = Error;
 
@@ -28,6 +92,7 @@ impl TryFrom<Value> for OpCode {
             6 => OpCode::JumpIfFalse,
             7 => OpCode::LessThan,
             8 => OpCode::Equals,
+            9 => OpCode::AdjustRelativeBase,
             99 => OpCode::Halt,
             _ => return Err(Error::InvalidOpCode(value)),
         })
@@ -48,9 +113,17 @@ mod tests {
         assert_eq!(OpCode::try_from(6).unwrap(), OpCode::JumpIfFalse);
         assert_eq!(OpCode::try_from(7).unwrap(), OpCode::LessThan);
         assert_eq!(OpCode::try_from(8).unwrap(), OpCode::Equals);
+        assert_eq!(OpCode::try_from(9).unwrap(), OpCode::AdjustRelativeBase);
         assert_eq!(OpCode::try_from(99).unwrap(), OpCode::Halt);
     }
 
+    #[test]
+    fn round_trip() {
+        for &op in OpCode::ALL.iter() {
+            assert_eq!(OpCode::try_from(op.code()).unwrap(), op);
+        }
+    }
+
     #[test]
     fn error() {
         assert_eq!(
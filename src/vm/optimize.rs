@@ -0,0 +1,328 @@
+use super::decompile::{reachable, Jump};
+use super::instruction::Instruction;
+use super::memory::GrowableMemory;
+use super::mode::Mode;
+use super::op::OpCode;
+use super::types::Value;
+use super::{to_address, Computer, State};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The result of [`optimize`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimized {
+    pub program: Vec<Value>,
+    /// Position parameters replaced by the constant they always read.
+    pub folded: usize,
+    /// Stores replaced by a jump over them because they are overwritten
+    /// before anything reads them.
+    pub removed: usize,
+}
+
+/// Folds constants into parameters and removes dead stores.
+///
+/// The analysis only looks at code reachable through immediate jumps, so
+/// programs with computed jumps, relative reads or writes, negative
+/// addresses, code they read or write as data or overlapping instructions
+/// are returned unchanged. Constants are only
+/// carried within straight line code, not across jumps or jump targets.
+pub fn optimize(program: &[Value]) -> Optimized {
+    let mut optimized = Optimized {
+        program: program.to_vec(),
+        folded: 0,
+        removed: 0,
+    };
+    let code = reachable(program);
+    let written = match written_cells(&code) {
+        Some(written) => written,
+        None => return optimized,
+    };
+    for block in blocks(&code) {
+        let mut block = block.into_iter().cloned().collect::<Vec<_>>();
+        optimized.folded += fold(&mut block, program, &written);
+        optimized.removed += remove_dead_stores(&mut block);
+        for instruction in &block {
+            encode(instruction, &mut optimized.program);
+        }
+    }
+    optimized
+}
+
+/// Runs both programs on `inputs` and checks that they produce the same
+/// outputs and end in the same state within `max_steps` instructions.
+///
+/// The programs run on growable memory, so they can use cells past their
+/// image like the puzzle programs from day 9 on. Faults count as the same
+/// state if their errors are the same apart from the faulting instruction,
+/// which the optimizer may have rewritten.
+pub fn verify(original: &[Value], optimized: &[Value], inputs: &[Value], max_steps: usize) -> bool {
+    let run = |program: &[Value]| {
+        let mut computer = Computer::new(GrowableMemory::new(program.to_vec()));
+        computer.extend_inputs(inputs.iter().copied());
+        let state = computer.run_with_limit(max_steps);
+        (state, computer.take_outputs())
+    };
+    let (state, outputs) = run(original);
+    let (optimized_state, optimized_outputs) = run(optimized);
+    let same_state = match (&state, &optimized_state) {
+        (State::Faulted(a), State::Faulted(b)) => a.root_cause() == b.root_cause(),
+        (a, b) => a == b,
+    };
+    same_state && outputs == optimized_outputs
+}
+
+/// Every cell a store can reach, or `None` if that is not known statically
+/// or the code itself is accessed as data, which the rewrites would change.
+fn written_cells(code: &BTreeMap<usize, Instruction>) -> Option<HashSet<usize>> {
+    let mut written = HashSet::new();
+    let mut read = HashSet::new();
+    for instruction in code.values() {
+        let computed_jump = match instruction.jump() {
+            Some(Jump::Never) | None => false,
+            Some(_) => instruction.target().is_none(),
+        };
+        if computed_jump {
+            return None;
+        }
+        if let Some(cell) = target(instruction) {
+            written.insert(cell?);
+        }
+        for &(mode, value) in inputs(instruction) {
+            match mode {
+                Mode::Position => read.insert(to_address(value).ok()?),
+                Mode::Relative => return None,
+                Mode::Immediate => continue,
+            };
+        }
+    }
+    let accesses_code = code.values().any(|instruction| {
+        (instruction.address..instruction.next())
+            .any(|word| written.contains(&word) || read.contains(&word))
+    });
+    let overlaps = code
+        .values()
+        .zip(code.values().skip(1))
+        .any(|(first, second)| first.next() > second.address);
+    if accesses_code || overlaps {
+        None
+    } else {
+        Some(written)
+    }
+}
+
+/// The cell an instruction stores to: `None` if it does not store and
+/// `Some(None)` if the cell depends on the relative base or is not a valid
+/// address.
+fn target(instruction: &Instruction) -> Option<Option<usize>> {
+    if !instruction.op.writes() {
+        return None;
+    }
+    Some(match instruction.params.last()? {
+        (Mode::Position, cell) => to_address(*cell).ok(),
+        _ => None,
+    })
+}
+
+/// Splits the code into runs of instructions that execute one after another
+/// and are only entered at the top.
+fn blocks(code: &BTreeMap<usize, Instruction>) -> Vec<Vec<&Instruction>> {
+    let targets = code
+        .values()
+        .filter_map(Instruction::target)
+        .collect::<HashSet<_>>();
+    let mut blocks: Vec<Vec<&Instruction>> = Vec::new();
+    let mut next = None;
+    for instruction in code.values() {
+        let continues =
+            next == Some(instruction.address) && !targets.contains(&instruction.address);
+        match blocks.last_mut() {
+            Some(block) if continues => block.push(instruction),
+            _ => blocks.push(vec![instruction]),
+        }
+        next = match instruction.jump() {
+            None if instruction.op != OpCode::Halt => Some(instruction.next()),
+            _ => None,
+        };
+    }
+    blocks
+}
+
+fn inputs(instruction: &Instruction) -> &[(Mode, Value)] {
+    let params = &instruction.params;
+    if instruction.op.writes() {
+        &params[..params.len() - 1]
+    } else {
+        params
+    }
+}
+
+/// Replaces position parameters that read a known value with immediates.
+fn fold(block: &mut [Instruction], program: &[Value], written: &HashSet<usize>) -> usize {
+    let mut known = HashMap::new();
+    let mut folded = 0;
+    for instruction in block {
+        let count = inputs(instruction).len();
+        for (mode, value) in &mut instruction.params[..count] {
+            if *mode != Mode::Position {
+                continue;
+            }
+            let cell = match to_address(*value) {
+                Ok(cell) => cell,
+                Err(_) => continue,
+            };
+            let constant = match known.get(&cell) {
+                Some(&constant) => Some(constant),
+                None if !written.contains(&cell) => program.get(cell).copied(),
+                None => None,
+            };
+            if let Some(constant) = constant {
+                *mode = Mode::Immediate;
+                *value = constant;
+                folded += 1;
+            }
+        }
+        let cell = match target(instruction) {
+            Some(Some(cell)) => cell,
+            _ => continue,
+        };
+        let value = match (instruction.op, &instruction.params[..]) {
+            (_, [(Mode::Immediate, a), (Mode::Immediate, b), _]) => match instruction.op {
+                OpCode::Add => a.checked_add(*b),
+                OpCode::Multiply => a.checked_mul(*b),
+                OpCode::LessThan => Some(Value::from(a < b)),
+                OpCode::Equals => Some(Value::from(a == b)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match value {
+            Some(value) => known.insert(cell, value),
+            None => known.remove(&cell),
+        };
+    }
+    folded
+}
+
+/// Turns stores that are overwritten later in the block before anything
+/// reads them into jumps to the next instruction.
+fn remove_dead_stores(block: &mut [Instruction]) -> usize {
+    let mut removed = 0;
+    for index in 0..block.len() {
+        let instruction = &block[index];
+        let cell = match target(instruction) {
+            Some(Some(cell)) if instruction.op != OpCode::Input => cell,
+            _ => continue,
+        };
+        let mut dead = false;
+        for later in &block[index + 1..] {
+            let reads = inputs(later).iter().any(|&(mode, value)| match mode {
+                Mode::Position => to_address(value).map_or(true, |read| read == cell),
+                Mode::Relative => true,
+                Mode::Immediate => false,
+            });
+            if reads {
+                break;
+            }
+            if target(later) == Some(Some(cell)) {
+                dead = true;
+                break;
+            }
+        }
+        if dead {
+            let next = instruction.next() as Value;
+            block[index].op = OpCode::JumpIfFalse;
+            block[index].params = vec![(Mode::Immediate, 0), (Mode::Immediate, next)];
+            removed += 1;
+        }
+    }
+    removed
+}
+
+fn encode(instruction: &Instruction, program: &mut [Value]) {
+    let modes = instruction
+        .params
+        .iter()
+        .rev()
+        .fold(0, |modes, &(mode, _)| {
+            let digit = match mode {
+                Mode::Position => 0,
+                Mode::Immediate => 1,
+                Mode::Relative => 2,
+            };
+            modes * 10 + digit
+        });
+    let address = instruction.address;
+    program[address] = instruction.op.code() + 100 * modes;
+    for (offset, &(_, value)) in instruction.params.iter().enumerate() {
+        program[address + 1 + offset] = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimize() {
+        let program = vec![
+            1101, 2, 3, 24, // [24] = 5
+            1002, 24, 4, 25, // [25] = [24] * 4
+            1101, 0, 0, 26, // dead, overwritten below
+            101, 1, 27, 26, // [26] = 1 + [27]
+            4, 25, 4, 26, 99, 0, 0, 0, 0, 0, 0, 7,
+        ];
+        let optimized = optimize(&program);
+        assert_eq!(optimized.folded, 4);
+        assert_eq!(optimized.removed, 1);
+        assert_eq!(
+            &optimized.program[..20],
+            &[1101, 2, 3, 24, 1102, 5, 4, 25, 1106, 0, 12, 26, 1101, 1, 7, 26, 104, 20, 104, 8]
+        );
+        assert!(verify(&program, &optimized.program, &[], 100));
+    }
+
+    #[test]
+    fn test_unchanged() {
+        // Writes relative to the base, which could reach any cell.
+        let program = vec![109, 10, 21101, 1, 2, 0, 4, 10, 99, 0, 0];
+        let optimized = optimize(&program);
+        assert_eq!(optimized.program, program);
+        assert_eq!((optimized.folded, optimized.removed), (0, 0));
+        // Rewrites its own code.
+        let program = vec![1101, 4, 0, 5, 104, 0, 99];
+        assert_eq!(optimize(&program).program, program);
+        // Outputs the opcode of its add relative to the base, which
+        // folding the add would change.
+        let program = vec![109, 0, 1, 11, 11, 12, 204, 2, 99, 0, 0, 5, 0];
+        assert_eq!(optimize(&program).program, program);
+        // The same, reading the opcode by position.
+        let program = vec![1, 7, 7, 9, 4, 0, 99, 5, 0, 0];
+        assert_eq!(optimize(&program).program, program);
+        // Reads a negative address, which faults.
+        let program = vec![1101, 2, 3, 7, 1, -1, 7, 7, 99];
+        assert_eq!(optimize(&program).program, program);
+    }
+
+    #[test]
+    fn test_verify() {
+        let programs = [
+            vec![1101, 2, 3, 11, 1, 11, 11, 12, 4, 12, 99, 0, 0],
+            vec![109, 0, 1, 11, 11, 12, 204, 2, 99, 0, 0, 5, 0],
+            vec![1, 7, 7, 9, 4, 0, 99, 5, 0, 0],
+            vec![1101, 2, 3, 7, 1, -1, 7, 7, 99],
+        ];
+        for program in &programs {
+            let optimized = optimize(program);
+            assert!(verify(program, &optimized.program, &[], 100));
+        }
+        assert_eq!(optimize(&programs[0]).folded, 3);
+        // Both write past the image, which only fixed-size memory rejects.
+        assert!(!verify(
+            &[1101, 1, 2, 100, 4, 100, 99],
+            &[1101, 1, 3, 100, 4, 100, 99],
+            &[],
+            100
+        ));
+        assert!(!verify(&[1101, 1, 2, -1], &[1101, 1, 2, -2], &[], 100));
+        assert!(verify(&[1101, 1, 2, -1], &[1101, 1, 2, -1], &[], 100));
+    }
+}
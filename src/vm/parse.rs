@@ -0,0 +1,111 @@
+use super::types::Value;
+use thiserror::Error;
+
+#[derive(Clone, Error, Debug, PartialEq)]
+pub enum ParseError {
+    #[error("Invalid value {token:?} at line {line}, column {column}")]
+    InvalidToken {
+        token: String,
+        line: usize,
+        column: usize,
+    },
+    #[error("Empty value at line {line}, column {column}")]
+    EmptyToken { line: usize, column: usize },
+}
+
+/// Parses an Intcode program.
+///
+/// Values may be separated by commas, whitespace or newlines. A leading
+/// byte order mark and trailing separators are ignored; an empty field
+/// between two commas or before the first one is an error.
+pub fn parse_program(input: &str) -> Result<Vec<Value>, ParseError> {
+    let input = input.trim_start_matches('\u{feff}');
+    let mut values = Vec::new();
+    let mut token = String::new();
+    let mut start = (1, 1);
+    let mut position = (1, 1);
+    // Starts set so that a leading comma is an empty field as well.
+    let mut after_comma = true;
+
+    let mut finish = |token: &mut String, start: (usize, usize)| -> Result<(), ParseError> {
+        let (line, column) = start;
+        let value = token.parse().map_err(|_| ParseError::InvalidToken {
+            token: token.clone(),
+            line,
+            column,
+        })?;
+        values.push(value);
+        token.clear();
+        Ok(())
+    };
+
+    for c in input.chars() {
+        if c == ',' || c.is_whitespace() {
+            if !token.is_empty() {
+                finish(&mut token, start)?;
+                after_comma = false;
+            }
+            if c == ',' {
+                if after_comma {
+                    let (line, column) = position;
+                    return Err(ParseError::EmptyToken { line, column });
+                }
+                after_comma = true;
+            }
+        } else {
+            if token.is_empty() {
+                start = position;
+            }
+            token.push(c);
+        }
+        position = if c == '\n' {
+            (position.0 + 1, 1)
+        } else {
+            (position.0, position.1 + 1)
+        };
+    }
+    if !token.is_empty() {
+        finish(&mut token, start)?;
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separators() {
+        let expected = Ok(vec![1, 0, -3, 99]);
+        assert_eq!(parse_program("1,0,-3,99"), expected);
+        assert_eq!(parse_program("1,0,-3,99,\n"), expected);
+        assert_eq!(parse_program("1 0\t-3\n99"), expected);
+        assert_eq!(parse_program("1, 0,\n-3,\r\n99\n"), expected);
+        assert_eq!(parse_program("\u{feff}1,0,-3,99"), expected);
+        assert_eq!(parse_program(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse_program("1,2\n3,x4,5"),
+            Err(ParseError::InvalidToken {
+                token: "x4".to_owned(),
+                line: 2,
+                column: 3
+            })
+        );
+        assert_eq!(
+            parse_program("1,2,,3"),
+            Err(ParseError::EmptyToken { line: 1, column: 5 })
+        );
+        assert_eq!(
+            parse_program(",1,2"),
+            Err(ParseError::EmptyToken { line: 1, column: 1 })
+        );
+        assert_eq!(
+            parse_program("\n ,1"),
+            Err(ParseError::EmptyToken { line: 2, column: 2 })
+        );
+    }
+}
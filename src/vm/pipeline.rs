@@ -0,0 +1,214 @@
+use super::errors::{Error, Result};
+use super::types::Value;
+use super::{Computer, Memory, State};
+use std::panic;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Runs one copy of `program` per phase setting, each on its own thread.
+///
+/// Every amplifier reads its phase setting first. The output of each
+/// amplifier is fed to the next one and the last amplifier feeds back into
+/// the first, which also receives the initial signal `0`. Returns the last
+/// value the final amplifier produced once all of them have halted.
+pub fn feedback_loop(program: &[Value], phases: &[Value]) -> Result<Option<Value>> {
+    let (senders, receivers): (Vec<Sender<Value>>, Vec<Receiver<Value>>) =
+        phases.iter().map(|_| channel()).unzip();
+    for (sender, &phase) in senders.iter().zip(phases) {
+        sender.send(phase).expect("receiver is still alive");
+    }
+    if let Some(first) = senders.first() {
+        first.send(0).expect("receiver is still alive");
+    }
+    let last_output = Arc::new(Mutex::new(None));
+    let count = phases.len();
+    let handles = receivers
+        .into_iter()
+        .enumerate()
+        .map(|(index, receiver)| {
+            let sender = senders[(index + 1) % count].clone();
+            let is_last = index + 1 == count;
+            let last_output = Arc::clone(&last_output);
+            let mut computer = Computer::new(program.to_owned());
+            thread::spawn(move || {
+                let read = || receiver.recv().map_err(|_| Error::InputClosed);
+                let write = |value| {
+                    if is_last {
+                        *last_output.lock().unwrap() = Some(value);
+                        // The first amplifier may already have halted.
+                        let _ = sender.send(value);
+                        Ok(())
+                    } else {
+                        sender.send(value).map_err(|_| Error::OutputClosed)
+                    }
+                };
+                computer.run(read, write)
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(senders);
+    for handle in handles {
+        handle
+            .join()
+            .unwrap_or_else(|err| panic::resume_unwind(err))?;
+    }
+    let last_output = *last_output.lock().unwrap();
+    Ok(last_output)
+}
+
+/// Computers connected in series, each feeding its outputs to the next.
+///
+/// Unlike [`feedback_loop`] the computers take turns on the current thread.
+pub struct Pipeline<M: Memory> {
+    computers: Vec<Computer<M>>,
+    feedback: bool,
+    outputs: Vec<Value>,
+}
+
+impl<M: Memory<Value = Value> + Clone> Pipeline<M> {
+    /// One copy of `program` per phase setting, each reading its phase first.
+    pub fn amplifiers(program: M, phases: &[Value]) -> Self {
+        let computers = phases
+            .iter()
+            .map(|&phase| {
+                let mut computer = Computer::new(program.clone());
+                computer.push_input(phase);
+                computer
+            })
+            .collect();
+        Self::new(computers)
+    }
+}
+
+impl<M: Memory<Value = Value>> Pipeline<M> {
+    pub fn new(computers: Vec<Computer<M>>) -> Self {
+        Self {
+            computers,
+            feedback: false,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Feeds the outputs of the last computer back into the first one.
+    pub fn with_feedback(mut self) -> Self {
+        self.feedback = true;
+        self
+    }
+
+    /// Queues an input for the first computer.
+    pub fn push_input(&mut self, value: Value) {
+        if let Some(first) = self.computers.first_mut() {
+            first.push_input(value);
+        }
+    }
+
+    /// Everything the last computer has output so far.
+    pub fn outputs(&self) -> &[Value] {
+        &self.outputs
+    }
+
+    /// Runs the computers in turn until all of them have halted and returns
+    /// the last output of the last computer.
+    ///
+    /// Fails with `Error::NeedsInput` when a computer waits for input that
+    /// will never arrive.
+    pub fn run(&mut self) -> Result<Option<Value>> {
+        let count = self.computers.len();
+        loop {
+            let mut progress = false;
+            let mut halted = 0;
+            for index in 0..count {
+                let computer = &mut self.computers[index];
+                let ip = computer.ip();
+                let state = computer.run_until_blocked();
+                let outputs = computer.take_outputs();
+                progress |= computer.ip() != ip || !outputs.is_empty();
+                match state {
+                    State::Halted => halted += 1,
+                    State::NeedsInput => (),
+                    State::Faulted(err) => return Err(err),
+                    state => unreachable!("run_until_blocked never returns {:?}", state),
+                }
+                if index + 1 < count {
+                    self.computers[index + 1].extend_inputs(outputs);
+                    continue;
+                }
+                self.outputs.extend(&outputs);
+                if self.feedback {
+                    self.computers[0].extend_inputs(outputs);
+                }
+            }
+            if halted == count {
+                return Ok(self.outputs.last().copied());
+            }
+            if !progress {
+                return Err(Error::NeedsInput);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series() -> Result<()> {
+        let program = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        assert_eq!(feedback_loop(&program, &[4, 3, 2, 1, 0])?, Some(43210));
+        Ok(())
+    }
+
+    #[test]
+    fn test_feedback() -> Result<()> {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        assert_eq!(
+            feedback_loop(&program, &[9, 8, 7, 6, 5])?,
+            Some(139_629_729)
+        );
+        let program = vec![
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ];
+        assert_eq!(feedback_loop(&program, &[9, 7, 8, 5, 6])?, Some(18216));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upstream_halted() {
+        // Halts right away for phase 0 and waits for another input otherwise.
+        let program = vec![3, 11, 1005, 11, 6, 99, 3, 11, 99, 0, 0, 0];
+        assert_eq!(feedback_loop(&program, &[0, 1]), Err(Error::InputClosed));
+    }
+
+    #[test]
+    fn test_pipeline() -> Result<()> {
+        let program = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        let mut pipeline = Pipeline::amplifiers(program, &[4, 3, 2, 1, 0]);
+        pipeline.push_input(0);
+        assert_eq!(pipeline.run()?, Some(43210));
+
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let mut pipeline = Pipeline::amplifiers(program, &[9, 8, 7, 6, 5]).with_feedback();
+        pipeline.push_input(0);
+        assert_eq!(pipeline.run()?, Some(139_629_729));
+        assert_eq!(pipeline.outputs().len(), 5);
+
+        let program = vec![3, 11, 1005, 11, 6, 99, 3, 11, 99, 0, 0, 0];
+        let mut pipeline = Pipeline::amplifiers(program, &[0, 1]);
+        assert_eq!(pipeline.run(), Err(Error::NeedsInput));
+        Ok(())
+    }
+}
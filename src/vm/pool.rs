@@ -0,0 +1,89 @@
+use super::{Computer, Memory};
+
+/// Reuses computers running the same program, so their buffers are
+/// allocated once instead of once per run.
+pub struct ComputerPool<M: Memory + Clone> {
+    program: M,
+    free: Vec<Computer<M>>,
+}
+
+impl<M: Memory + Clone> ComputerPool<M> {
+    pub fn new(program: M) -> Self {
+        Self {
+            program,
+            free: Vec::new(),
+        }
+    }
+
+    /// A computer in its initial state, as `Computer::new` creates it.
+    pub fn take(&mut self) -> Computer<M> {
+        self.free
+            .pop()
+            .unwrap_or_else(|| Computer::new(self.program.clone()))
+    }
+
+    /// Resets `computer`, dropping any tracer, watchpoints and other
+    /// settings, and keeps it for the next `take`.
+    pub fn put(&mut self, mut computer: Computer<M>) {
+        computer.reset_all();
+        self.free.push(computer);
+    }
+
+    /// Runs `f` with a computer from the pool and returns it afterwards.
+    pub fn with<T, F: FnOnce(&mut Computer<M>) -> T>(&mut self, f: F) -> T {
+        let mut computer = self.take();
+        let result = f(&mut computer);
+        self.put(computer);
+        result
+    }
+
+    /// Number of computers waiting to be reused.
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Engine, JsonTracer, State};
+
+    #[test]
+    fn test_reuse() {
+        let mut pool = ComputerPool::new(vec![3, 0, 4, 0, 99]);
+        for value in 0..3 {
+            let output = pool.with(|computer| {
+                assert_eq!(computer.memory()[0], 3);
+                computer.push_input(value);
+                assert_eq!(computer.run_until_blocked(), State::Halted);
+                computer.take_outputs()
+            });
+            assert_eq!(output, vec![value]);
+            assert_eq!(pool.available(), 1);
+        }
+        let first = pool.take();
+        let second = pool.take();
+        assert_eq!(first.ip(), 0);
+        pool.put(first);
+        pool.put(second);
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn test_settings_dropped() {
+        let mut pool = ComputerPool::new(vec![3, 0, 4, 0, 99]);
+        pool.with(|computer| {
+            computer.set_tracer(JsonTracer::new(Vec::new()));
+            computer.add_watchpoint(0);
+            computer.enable_heatmap();
+            computer.set_engine(Engine::Decoded);
+        });
+        let mut computer = pool.take();
+        assert!(computer.take_tracer().is_none());
+        assert!(computer.heatmap().is_none());
+        assert_eq!(computer.engine(), Engine::Interpreter);
+        computer.push_input(5);
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert_eq!(computer.take_outputs(), vec![5]);
+    }
+}
@@ -0,0 +1,70 @@
+use super::op::OpCode;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Execution counts and times collected by [`Computer::enable_profiling`].
+///
+/// [`Computer::enable_profiling`]: super::Computer::enable_profiling
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    opcodes: HashMap<OpCode, u64>,
+    addresses: BTreeMap<usize, u64>,
+    time: HashMap<OpCode, Duration>,
+}
+
+impl Profile {
+    pub(super) fn record(&mut self, address: usize, op: OpCode, elapsed: Duration) {
+        *self.opcodes.entry(op).or_insert(0) += 1;
+        *self.addresses.entry(address).or_insert(0) += 1;
+        *self.time.entry(op).or_default() += elapsed;
+    }
+
+    /// Number of executed instructions.
+    pub fn total(&self) -> u64 {
+        self.opcodes.values().sum()
+    }
+
+    pub fn executions(&self, op: OpCode) -> u64 {
+        self.opcodes.get(&op).copied().unwrap_or(0)
+    }
+
+    /// Time spent executing `op`, including the I/O callbacks of `in` and
+    /// `out`.
+    pub fn time(&self, op: OpCode) -> Duration {
+        self.time.get(&op).copied().unwrap_or_default()
+    }
+
+    /// Every executed opcode with its count and time, in opcode order.
+    pub fn opcodes(&self) -> impl Iterator<Item = (OpCode, u64, Duration)> + '_ {
+        let mut opcodes = self.opcodes.keys().copied().collect::<Vec<_>>();
+        opcodes.sort_by_key(|&op| op.code());
+        opcodes
+            .into_iter()
+            .map(move |op| (op, self.executions(op), self.time(op)))
+    }
+
+    /// Every executed address with its hit count, in order.
+    pub fn addresses(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.addresses
+            .iter()
+            .map(|(&address, &hits)| (address, hits))
+    }
+
+    /// How often the instruction at `address` was executed.
+    pub fn hits(&self, address: usize) -> u64 {
+        self.addresses.get(&address).copied().unwrap_or(0)
+    }
+
+    /// The `count` most executed addresses, most frequent first.
+    pub fn hottest(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut addresses = self
+            .addresses
+            .iter()
+            .map(|(&address, &hits)| (address, hits))
+            .collect::<Vec<_>>();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        addresses.truncate(count);
+        addresses
+    }
+}
@@ -0,0 +1,114 @@
+use super::errors::{Error, Result};
+use super::memory::GrowableMemory;
+use super::parse::{parse_program, ParseError};
+use super::promoting::Promoting;
+use super::types::Value;
+use super::Computer;
+use std::str::FromStr;
+
+/// The initial memory of an Intcode computer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Program(Vec<Value>);
+
+impl Program {
+    pub fn new(values: Vec<Value>) -> Self {
+        Self(values)
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.0
+    }
+
+    pub fn patch(&mut self, address: usize, value: Value) -> Result<()> {
+        let cell = self.0.get_mut(address).ok_or(Error::SegFault(address))?;
+        *cell = value;
+        Ok(())
+    }
+
+    /// A copy with the noun and verb of day 2 written to addresses 1 and 2.
+    pub fn with_noun_verb(&self, noun: Value, verb: Value) -> Result<Self> {
+        let mut program = self.clone();
+        program.patch(1, noun)?;
+        program.patch(2, verb)?;
+        Ok(program)
+    }
+
+    /// A computer whose memory is exactly the program; any access past it
+    /// faults. Enough for the days before day 9.
+    pub fn into_computer(self) -> Computer<Vec<Value>> {
+        Computer::new(self.0)
+    }
+
+    /// A computer with memory beyond the program, as day 9 requires.
+    pub fn into_growable_computer(self) -> Computer<GrowableMemory> {
+        Computer::new(GrowableMemory::new(self.0))
+    }
+
+    /// A computer with growable memory that switches to big integers instead
+    /// of failing with `Error::Overflow`.
+    pub fn into_promoting_computer(self) -> Computer<GrowableMemory<Promoting>> {
+        Computer::promoting(self.0)
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> std::result::Result<Self, ParseError> {
+        parse_program(input).map(Self)
+    }
+}
+
+impl From<Vec<Value>> for Program {
+    fn from(values: Vec<Value>) -> Self {
+        Self(values)
+    }
+}
+
+impl From<Program> for Vec<Value> {
+    fn from(program: Program) -> Self {
+        program.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::State;
+
+    #[test]
+    fn test_patch() -> Result<()> {
+        let program: Program = "1,0,0,0,99".parse().unwrap();
+        let patched = program.with_noun_verb(4, 0)?;
+        assert_eq!(patched.values(), &[1, 4, 0, 0, 99]);
+        assert_eq!(program.values(), &[1, 0, 0, 0, 99]);
+        assert_eq!(patched.into_computer().execute()?, 100);
+        assert_eq!(Program::new(vec![99]).patch(1, 0), Err(Error::SegFault(1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_growable() -> Result<()> {
+        // Stores 7 past the end of the program, then outputs it.
+        let program: Program = "1101,3,4,100,4,100,99".parse().unwrap();
+        let mut computer = program.clone().into_growable_computer();
+        computer.run_until_blocked();
+        assert_eq!(computer.take_outputs(), vec![7]);
+        assert!(program.into_computer().execute().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_promoting() {
+        let program = Program::new(vec![1102, 1 << 62, 4, 7, 4, 7, 99, 0]);
+        let mut computer = program.clone().into_promoting_computer();
+        computer.run_until_blocked();
+        assert_eq!(
+            computer.take_outputs()[0].to_string(),
+            "18446744073709551616"
+        );
+        let mut computer = program.into_growable_computer();
+        assert!(matches!(computer.run_until_blocked(), State::Faulted(_)));
+    }
+}
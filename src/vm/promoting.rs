@@ -0,0 +1,250 @@
+use super::memory::GrowableMemory;
+use super::types::Value;
+use super::Computer;
+use num::{BigInt, CheckedAdd, CheckedMul, One, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul};
+
+/// An integer that is a `Value` until an operation would overflow and
+/// becomes a `BigInt` then.
+///
+/// Results that fit into a `Value` again are stored as one, so programs only
+/// pay for big integers while they actually use them. Every number has
+/// exactly one representation, which keeps equality, hashing and ordering
+/// consistent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Promoting(Repr);
+
+/// `Big` never holds a number that fits into a `Value`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Repr {
+    Small(Value),
+    Big(BigInt),
+}
+
+impl Promoting {
+    fn from_big(big: BigInt) -> Self {
+        Promoting(match big.to_i64() {
+            Some(value) => Repr::Small(value),
+            None => Repr::Big(big),
+        })
+    }
+
+    /// Whether the number is beyond a `Value` and stored as a `BigInt`.
+    pub fn is_big(&self) -> bool {
+        matches!(self.0, Repr::Big(_))
+    }
+
+    pub fn to_big(&self) -> BigInt {
+        self.clone().into_big()
+    }
+
+    fn into_big(self) -> BigInt {
+        match self.0 {
+            Repr::Small(value) => value.into(),
+            Repr::Big(big) => big,
+        }
+    }
+
+    fn combine(
+        self,
+        other: Self,
+        small: fn(Value, Value) -> Option<Value>,
+        big: fn(BigInt, BigInt) -> BigInt,
+    ) -> Self {
+        if let (Repr::Small(a), Repr::Small(b)) = (&self.0, &other.0) {
+            if let Some(value) = small(*a, *b) {
+                return Promoting(Repr::Small(value));
+            }
+        }
+        Self::from_big(big(self.into_big(), other.into_big()))
+    }
+
+    /// The remainder of `self / other`, or `None` if `other` is zero.
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        Some(
+            self.clone()
+                .combine(other.clone(), Value::checked_rem, |a, b| a % b),
+        )
+    }
+}
+
+impl From<Value> for Promoting {
+    fn from(value: Value) -> Self {
+        Promoting(Repr::Small(value))
+    }
+}
+
+impl From<BigInt> for Promoting {
+    fn from(big: BigInt) -> Self {
+        Self::from_big(big)
+    }
+}
+
+impl fmt::Display for Promoting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Repr::Small(value) => value.fmt(f),
+            Repr::Big(big) => big.fmt(f),
+        }
+    }
+}
+
+impl Ord for Promoting {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (Repr::Small(a), Repr::Small(b)) => a.cmp(b),
+            _ => self.to_big().cmp(&other.to_big()),
+        }
+    }
+}
+
+impl PartialOrd for Promoting {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Promoting {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.combine(other, Value::checked_add, |a, b| a + b)
+    }
+}
+
+impl Mul for Promoting {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.combine(other, Value::checked_mul, |a, b| a * b)
+    }
+}
+
+impl CheckedAdd for Promoting {
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self.clone() + other.clone())
+    }
+}
+
+impl CheckedMul for Promoting {
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self.clone() * other.clone())
+    }
+}
+
+impl Zero for Promoting {
+    fn zero() -> Self {
+        Promoting(Repr::Small(0))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == Repr::Small(0)
+    }
+}
+
+impl One for Promoting {
+    fn one() -> Self {
+        Promoting(Repr::Small(1))
+    }
+}
+
+impl ToPrimitive for Promoting {
+    fn to_i64(&self) -> Option<i64> {
+        match &self.0 {
+            Repr::Small(value) => Some(*value),
+            Repr::Big(big) => big.to_i64(),
+        }
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        match &self.0 {
+            Repr::Small(value) => value.to_u64(),
+            Repr::Big(big) => big.to_u64(),
+        }
+    }
+}
+
+impl Computer<GrowableMemory<Promoting>> {
+    /// A computer for `program` that switches to big integers instead of
+    /// overflowing.
+    pub fn promoting(program: Vec<Value>) -> Self {
+        Computer::new(GrowableMemory::new(
+            program.into_iter().map(Promoting::from).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::State;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_promotion() {
+        // Outputs 2^62 * 4, -2^62 * 4 and their sum.
+        let big = 1 << 62;
+        let program = vec![
+            1002, 20, 4, 21, 1002, 22, 4, 23, 4, 21, 4, 23, 1, 21, 23, 24, 4, 24, 99, 0, big, 0,
+            -big, 0, 0,
+        ];
+        let mut computer = Computer::promoting(program);
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        let outputs = computer.take_outputs();
+        assert_eq!(outputs[0], Promoting::from(BigInt::from(1) << 64));
+        assert_eq!(outputs[1], Promoting::from(-(BigInt::from(1) << 64)));
+        assert_eq!(outputs[2], Promoting::from(0));
+        assert!(outputs[0].is_big() && !outputs[2].is_big());
+        assert_eq!(outputs[1].to_string(), "-18446744073709551616");
+        assert!(outputs[1] < outputs[2] && outputs[2] < outputs[0]);
+    }
+
+    #[test]
+    fn test_consistency() {
+        let small = Promoting::from(5);
+        let big = Promoting::from(BigInt::from(5));
+        assert!(!big.is_big());
+        assert_eq!(small, big);
+        assert_eq!(small.cmp(&big), Ordering::Equal);
+        let set = [small, big].iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(set.len(), 1);
+        let huge = Promoting::from(BigInt::from(1) << 70);
+        assert_eq!(
+            huge.clone() + Promoting::from(-1) + Promoting::from(1),
+            huge
+        );
+    }
+
+    #[test]
+    fn test_checked_rem() {
+        let huge = Promoting::from(BigInt::from(1) << 70);
+        assert_eq!(huge.checked_rem(&Promoting::zero()), None);
+        assert_eq!(
+            huge.checked_rem(&Promoting::from(1000)),
+            Some(Promoting::from(424))
+        );
+        assert_eq!(
+            Promoting::from(Value::MIN).checked_rem(&Promoting::from(-1)),
+            Some(Promoting::zero())
+        );
+    }
+
+    #[test]
+    fn test_big_opcode() {
+        // An add whose word has digits beyond a Value above its opcode.
+        let word = Promoting::from(
+            "1000000000000000000000000000001101"
+                .parse::<BigInt>()
+                .unwrap(),
+        );
+        let mut computer = Computer::promoting(vec![0, 2, 3, 0, 4, 0, 99]);
+        computer.patch(0, word).unwrap();
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        assert_eq!(computer.take_outputs(), vec![Promoting::from(5)]);
+    }
+}
@@ -0,0 +1,126 @@
+use super::differential::lockstep;
+use super::errors::Error;
+use super::types::Value;
+use super::{Computer, Engine, Memory, State};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+
+/// Start of the data cells the generated instructions work on.
+const DATA: Value = 100;
+const CELLS: Value = 8;
+
+/// Mode digit and raw value of a parameter that is read. The relative base
+/// stays zero, so relative parameters address the data like positions do.
+fn operand() -> impl Strategy<Value = (Value, Value)> {
+    prop_oneof![
+        (DATA..DATA + CELLS).prop_map(|address| (0, address)),
+        (-1000..1000 as Value).prop_map(|value| (1, value)),
+        (DATA..DATA + CELLS).prop_map(|address| (2, address)),
+    ]
+}
+
+fn target() -> impl Strategy<Value = (Value, Value)> {
+    (select(vec![0, 2]), DATA..DATA + CELLS)
+}
+
+fn instruction() -> impl Strategy<Value = Vec<Value>> {
+    prop_oneof![
+        (select(vec![1, 2, 7, 8]), operand(), operand(), target()).prop_map(
+            |(code, (m1, a), (m2, b), (m3, c))| {
+                vec![code + 100 * m1 + 1000 * m2 + 10000 * m3, a, b, c]
+            }
+        ),
+        operand().prop_map(|(mode, a)| vec![4 + 100 * mode, a]),
+    ]
+}
+
+/// Straight-line code followed by `halt` and the data cells.
+fn program() -> impl Strategy<Value = (Vec<Value>, usize)> {
+    (
+        vec(instruction(), 0..16),
+        vec(-1000..1000 as Value, CELLS as usize),
+    )
+        .prop_map(|(code, data)| {
+            let mut program = code.concat();
+            let halt = program.len();
+            program.push(99);
+            program.resize(DATA as usize, 0);
+            program.extend(data);
+            (program, halt)
+        })
+}
+
+fn run(program: Vec<Value>) -> (Computer<Vec<Value>>, State) {
+    let mut computer = Computer::new(program);
+    let state = computer.run_until_blocked();
+    (computer, state)
+}
+
+proptest! {
+    #[test]
+    fn arithmetic(a: Value, b: Value, multiply: bool) {
+        let (op, expected) = if multiply {
+            (1102, a.checked_mul(b))
+        } else {
+            (1101, a.checked_add(b))
+        };
+        let (mut computer, state) = run(vec![op, a, b, 7, 4, 7, 99, 0]);
+        match expected {
+            Some(value) => {
+                prop_assert_eq!(state, State::Halted);
+                prop_assert_eq!(computer.take_outputs(), vec![value]);
+            }
+            None => match state {
+                State::Faulted(err) => prop_assert_eq!(err.root_cause(), &Error::Overflow),
+                state => prop_assert!(false, "overflow ended in {:?}", state),
+            },
+        }
+    }
+
+    #[test]
+    fn comparisons_write_booleans(a in -3..3 as Value, b in -3..3 as Value, junk: Value) {
+        for (op, expected) in [(1107, a < b), (1108, a == b)] {
+            let (computer, state) = run(vec![op, a, b, 5, 99, junk]);
+            prop_assert_eq!(state, State::Halted);
+            prop_assert_eq!(computer.memory().read(5), Ok(expected as Value));
+        }
+    }
+
+    #[test]
+    fn jumps(condition in -2..2 as Value, target in 0..20 as Value, if_true: bool) {
+        let mut program = vec![if if_true { 1105 } else { 1106 }, condition, target];
+        program.resize(20, 99);
+        let mut computer = Computer::new(program);
+        prop_assert_eq!(computer.single_step(), State::Running);
+        let expected = if (condition != 0) == if_true { target as usize } else { 3 };
+        prop_assert_eq!(computer.ip(), expected);
+    }
+
+    #[test]
+    fn halting_is_stable((program, halt) in program()) {
+        let (mut computer, state) = run(program);
+        if let State::Faulted(err) = &state {
+            // Repeated multiplication may overflow, nothing else can fail.
+            prop_assert_eq!(err.root_cause(), &Error::Overflow);
+            return Ok(());
+        }
+        prop_assert_eq!(state, State::Halted);
+        prop_assert_eq!(computer.ip(), halt);
+        computer.take_outputs();
+        let memory = computer.memory().clone();
+        prop_assert_eq!(computer.run_until_blocked(), State::Halted);
+        prop_assert_eq!(computer.ip(), halt);
+        prop_assert!(computer.take_outputs().is_empty());
+        prop_assert_eq!(computer.memory(), &memory);
+    }
+
+    #[test]
+    fn engines_agree((program, _) in program()) {
+        let len = program.len();
+        let mut interpreted = Computer::new(program.clone());
+        let mut decoded = Computer::new(program);
+        decoded.set_engine(Engine::Decoded);
+        prop_assert!(lockstep(&mut interpreted, &mut decoded, 0..len, 1000).is_ok());
+    }
+}
@@ -0,0 +1,238 @@
+use super::errors::Error;
+use super::instruction::Instruction;
+use super::op::OpCode;
+use super::trace::{Effects, Tracer};
+use super::types::Value;
+use super::{Computer, Memory, State};
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Event {
+    Input(Value),
+    Output(Value),
+}
+
+/// The inputs and outputs of a run in the order they happened.
+///
+/// Written as one `in <value>` or `out <value>` line per event.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub events: Vec<Event>,
+}
+
+#[derive(Clone, Error, Debug, PartialEq)]
+pub enum ReplayError {
+    #[error("Invalid event {text:?} at line {line}")]
+    InvalidEvent { text: String, line: usize },
+    #[error("Program read input at event {index}, but the recording has {}", describe(.expected))]
+    UnexpectedInput {
+        index: usize,
+        expected: Option<Event>,
+    },
+    #[error(
+        "Program output {found} at event {index}, but the recording has {}",
+        describe(.expected)
+    )]
+    UnexpectedOutput {
+        index: usize,
+        expected: Option<Event>,
+        found: Value,
+    },
+    #[error("Program halted after {index} of {len} events")]
+    Unfinished { index: usize, len: usize },
+    #[error(transparent)]
+    Vm(#[from] Error),
+}
+
+fn describe(event: &Option<Event>) -> String {
+    match event {
+        Some(event) => format!("`{}`", event),
+        None => "no more events".to_owned(),
+    }
+}
+
+/// Records the inputs and outputs of a computer it is installed on as its
+/// tracer.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    recording: Recording,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+}
+
+impl Tracer for Recorder {
+    fn after(
+        &mut self,
+        instruction: &Instruction,
+        result: &Result<State, Error>,
+        effects: &Effects,
+    ) {
+        // Instructions that failed are retried or end the run.
+        if result.is_err() {
+            return;
+        }
+        let events = &mut self.recording.events;
+        match (instruction.op, effects.writes.last(), effects.output) {
+            (OpCode::Input, Some(&(_, value)), _) => events.push(Event::Input(value)),
+            (_, _, Some(value)) => events.push(Event::Output(value)),
+            _ => (),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Input(value) => write!(f, "in {}", value),
+            Event::Output(value) => write!(f, "out {}", value),
+        }
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.events
+            .iter()
+            .try_for_each(|event| writeln!(f, "{}", event))
+    }
+}
+
+impl FromStr for Recording {
+    type Err = ReplayError;
+
+    fn from_str(text: &str) -> Result<Self, ReplayError> {
+        let events = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                let event = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    ["in", value] => value.parse().ok().map(Event::Input),
+                    ["out", value] => value.parse().ok().map(Event::Output),
+                    _ => None,
+                };
+                event.ok_or_else(|| ReplayError::InvalidEvent {
+                    text: line.to_owned(),
+                    line: index + 1,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { events })
+    }
+}
+
+impl<M: Memory<Value = Value>> Computer<M> {
+    /// Runs the program on the inputs of `recording` and checks that it
+    /// reads and writes exactly the recorded events in the same order.
+    pub fn replay(&mut self, recording: &Recording) -> Result<(), ReplayError> {
+        let events = &recording.events;
+        let next = Cell::new(0);
+        let mismatch = Cell::new(None);
+        let result = self.run(
+            || match events.get(next.get()) {
+                Some(&Event::Input(value)) => {
+                    next.set(next.get() + 1);
+                    Ok(value)
+                }
+                expected => {
+                    mismatch.set(Some(ReplayError::UnexpectedInput {
+                        index: next.get(),
+                        expected: expected.copied(),
+                    }));
+                    Err(Error::InputClosed)
+                }
+            },
+            |found| match events.get(next.get()) {
+                Some(&Event::Output(value)) if value == found => {
+                    next.set(next.get() + 1);
+                    Ok(())
+                }
+                expected => {
+                    mismatch.set(Some(ReplayError::UnexpectedOutput {
+                        index: next.get(),
+                        expected: expected.copied(),
+                        found,
+                    }));
+                    Err(Error::OutputClosed)
+                }
+            },
+        );
+        if let Some(mismatch) = mismatch.take() {
+            return Err(mismatch);
+        }
+        result?;
+        if next.get() < events.len() {
+            return Err(ReplayError::Unfinished {
+                index: next.get(),
+                len: events.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outputs the running total of its inputs until it reads a zero.
+    fn summer() -> Vec<Value> {
+        vec![
+            3, 15, 1006, 15, 14, 1, 15, 16, 16, 4, 16, 1105, 1, 0, 99, 0, 0,
+        ]
+    }
+
+    #[test]
+    fn test_record_and_replay() -> Result<(), ReplayError> {
+        let mut computer = Computer::new(summer());
+        computer.set_tracer(Recorder::new());
+        computer.extend_inputs(vec![3, 4]);
+        assert_eq!(computer.run_until_blocked(), State::NeedsInput);
+        computer.push_input(0);
+        assert_eq!(computer.run_until_blocked(), State::Halted);
+        let recording = computer.tracer::<Recorder>().unwrap().recording().clone();
+        assert_eq!(recording.to_string(), "in 3\nout 3\nin 4\nout 7\nin 0\n");
+        assert_eq!(recording.to_string().parse::<Recording>()?, recording);
+
+        Computer::new(summer()).replay(&recording)?;
+
+        let mut changed = summer();
+        changed[5] = 1101;
+        assert_eq!(
+            Computer::new(changed).replay(&recording),
+            Err(ReplayError::UnexpectedOutput {
+                index: 1,
+                expected: Some(Event::Output(3)),
+                found: 31,
+            })
+        );
+        let mut truncated = recording.clone();
+        truncated.events.pop();
+        assert_eq!(
+            Computer::new(summer()).replay(&truncated),
+            Err(ReplayError::UnexpectedInput {
+                index: 4,
+                expected: None,
+            })
+        );
+        assert_eq!(
+            "in 1\nout x".parse::<Recording>(),
+            Err(ReplayError::InvalidEvent {
+                text: "out x".to_owned(),
+                line: 2
+            })
+        );
+        Ok(())
+    }
+}
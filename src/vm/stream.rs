@@ -0,0 +1,168 @@
+use super::ascii;
+use super::types::Value;
+use super::{Computer, Memory, OutputLimit, State};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+/// Values for one line of `input`, or `None` at the end of the input.
+///
+/// Numbers are separated by whitespace or commas; in ASCII mode the line is
+/// sent as text.
+pub fn read_line(input: &mut impl BufRead, ascii: bool) -> io::Result<Option<Vec<Value>>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let line = line.trim_end_matches(&['\r', '\n'][..]);
+    if ascii {
+        return Ok(Some(ascii::encode_line(line).collect()));
+    }
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            word.parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid input {:?}", word),
+                )
+            })
+        })
+        .collect::<io::Result<_>>()
+        .map(Some)
+}
+
+/// Writes values one per line, or ASCII values as text in ASCII mode.
+pub fn write_values(output: &mut impl Write, values: &[Value], ascii: bool) -> io::Result<()> {
+    for &value in values {
+        match value {
+            0..=127 if ascii => write!(output, "{}", value as u8 as char)?,
+            _ => writeln!(output, "{}", value)?,
+        }
+    }
+    Ok(())
+}
+
+/// Runs `computer` with its I/O connected to `input` and `output`.
+///
+/// Outputs are written as soon as they are produced and `output` is flushed
+/// before waiting for input. Returns `State::NeedsInput` when the program
+/// wants more input than `input` has, so it can be resumed on another stream.
+pub fn run<M, R, W>(
+    computer: &mut Computer<M>,
+    mut input: R,
+    mut output: W,
+    ascii: bool,
+) -> io::Result<State>
+where
+    M: Memory<Value = Value>,
+    R: BufRead,
+    W: Write,
+{
+    computer.set_output_limit(OutputLimit::Block(1));
+    let state = loop {
+        let state = computer.run_until_blocked();
+        write_values(&mut output, &computer.take_outputs(), ascii)?;
+        match state {
+            State::OutputFull => (),
+            State::NeedsInput => {
+                output.flush()?;
+                match read_line(&mut input, ascii)? {
+                    Some(values) => computer.extend_inputs(values),
+                    None => break state,
+                }
+            }
+            state => break state,
+        }
+    };
+    output.flush()?;
+    Ok(state)
+}
+
+/// Serves the I/O of `computer` to one TCP client at a time.
+///
+/// When a client disconnects the program keeps its state and continues with
+/// the next connection. Returns once the program stops for any other reason
+/// than waiting for input.
+pub fn serve<M: Memory<Value = Value>>(
+    computer: &mut Computer<M>,
+    listener: &TcpListener,
+    ascii: bool,
+) -> io::Result<State> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let input = BufReader::new(stream.try_clone()?);
+        match run(computer, input, &stream, ascii) {
+            Ok(State::NeedsInput) => (),
+            Ok(state) => return Ok(state),
+            Err(err) if disconnected(&err) => (),
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("TcpListener::incoming never ends")
+}
+
+fn disconnected(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::{Shutdown, TcpStream};
+    use std::thread;
+
+    /// Outputs the sum of every two inputs, then halts if `halt` is set.
+    fn adder(halt: bool) -> Computer<Vec<Value>> {
+        let mut program = vec![3, 20, 3, 21, 1, 20, 21, 22, 4, 22, 1105, 1, 0];
+        if halt {
+            program[10] = 99;
+        }
+        program.resize(23, 0);
+        Computer::new(program)
+    }
+
+    #[test]
+    fn test_run() -> io::Result<()> {
+        let mut comp = adder(false);
+        let mut output = Vec::new();
+        let state = run(&mut comp, &b"1 2\n3,\n4\n"[..], &mut output, false)?;
+        assert_eq!(state, State::NeedsInput);
+        assert_eq!(output, b"3\n7\n");
+
+        // Echoes its input.
+        let mut comp = Computer::new(vec![3, 9, 4, 9, 1105, 1, 0, 99, 0, 0]);
+        let mut output = Vec::new();
+        run(&mut comp, &b"hi\n"[..], &mut output, true)?;
+        assert_eq!(output, b"hi\n");
+        assert!(run(&mut adder(false), &b"x\n"[..], Vec::new(), false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_serve() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let server = thread::spawn(move || serve(&mut adder(true), &listener, false));
+
+        let mut first = TcpStream::connect(address)?;
+        first.write_all(b"20\n")?;
+        first.shutdown(Shutdown::Write)?;
+        let mut reply = String::new();
+        first.read_to_string(&mut reply)?;
+        assert_eq!(reply, "");
+
+        let mut second = TcpStream::connect(address)?;
+        second.write_all(b"22\n")?;
+        let mut reply = String::new();
+        second.read_to_string(&mut reply)?;
+        assert_eq!(reply, "42\n");
+        assert_eq!(server.join().unwrap()?, State::Halted);
+        Ok(())
+    }
+}
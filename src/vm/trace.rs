@@ -0,0 +1,126 @@
+use super::errors::Error;
+use super::instruction::Instruction;
+use super::mode::Mode;
+use super::types::{Int, Value};
+use super::State;
+use std::any::Any;
+use std::io::{self, Write};
+
+/// Memory accesses and output of one executed instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effects<V = Value> {
+    /// Addresses read as data, not counting the instruction itself.
+    pub reads: Vec<usize>,
+    pub writes: Vec<(usize, V)>,
+    pub output: Option<V>,
+}
+
+impl<V> Default for Effects<V> {
+    fn default() -> Self {
+        Self {
+            reads: Vec::new(),
+            writes: Vec::new(),
+            output: None,
+        }
+    }
+}
+
+/// Callbacks around every instruction a computer executes.
+///
+/// Instructions that cannot be decoded fault without reaching the tracer.
+/// An input instruction that has to wait for input gets `before` again when
+/// it is retried, but `after` only once it has executed.
+pub trait Tracer<V = Value>: Any + Send {
+    fn before(&mut self, _instruction: &Instruction<V>) {}
+
+    fn after(
+        &mut self,
+        _instruction: &Instruction<V>,
+        _result: &Result<State, Error>,
+        _effects: &Effects<V>,
+    ) {
+    }
+}
+
+/// Holds the tracer of a computer; clones start without one.
+pub(super) struct TracerSlot<V>(pub(super) Option<Box<dyn Tracer<V>>>);
+
+impl<V> Default for TracerSlot<V> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<V> Clone for TracerSlot<V> {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+/// Writes one JSON object per executed instruction, e.g.
+/// `{"ip":0,"opcode":"add","params":[[0,9],[1,3],[0,9]],"writes":[[9,5]]}`.
+///
+/// Parameters are `[mode, value]` pairs with the mode digit as encoded in
+/// the instruction. The first I/O error stops the trace and is returned by
+/// [`JsonTracer::finish`].
+pub struct JsonTracer<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flushes the writer and hands it back.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush().map(|()| self.writer),
+        }
+    }
+
+    fn line<V: Int>(
+        &mut self,
+        instruction: &Instruction<V>,
+        effects: &Effects<V>,
+    ) -> io::Result<()> {
+        let params = instruction.params.iter().map(|(mode, value)| {
+            let mode = match mode {
+                Mode::Position => 0,
+                Mode::Immediate => 1,
+                Mode::Relative => 2,
+            };
+            format!("[{},{}]", mode, value)
+        });
+        let writes = effects
+            .writes
+            .iter()
+            .map(|(address, value)| format!("[{},{}]", address, value));
+        writeln!(
+            self.writer,
+            r#"{{"ip":{},"opcode":"{}","params":[{}],"writes":[{}]}}"#,
+            instruction.address,
+            instruction.op.mnemonic(),
+            params.collect::<Vec<_>>().join(","),
+            writes.collect::<Vec<_>>().join(","),
+        )
+    }
+}
+
+impl<W: Write + Send + 'static, V: Int> Tracer<V> for JsonTracer<W> {
+    fn after(
+        &mut self,
+        instruction: &Instruction<V>,
+        _: &Result<State, Error>,
+        effects: &Effects<V>,
+    ) {
+        if self.error.is_none() {
+            self.error = self.line(instruction, effects).err();
+        }
+    }
+}
@@ -1 +1,66 @@
+use super::errors::{Error, Result};
+use num::{CheckedAdd, CheckedMul, One, ToPrimitive, Zero};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
 pub type Value = i64;
+
+/// An integer type the VM can compute with, e.g. `i64`, `i128`,
+/// `num::BigInt` or [`Promoting`](super::Promoting). The memories in
+/// [`memory`](super::memory) hold any of them, while a plain `Vec` only
+/// serves as memory for `Value`.
+pub trait Int:
+    Zero
+    + One
+    + CheckedAdd
+    + CheckedMul
+    + Ord
+    + Hash
+    + ToPrimitive
+    + From<Value>
+    + Clone
+    + Debug
+    + Display
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl<T> Int for T where
+    T: Zero
+        + One
+        + CheckedAdd
+        + CheckedMul
+        + Ord
+        + Hash
+        + ToPrimitive
+        + From<Value>
+        + Clone
+        + Debug
+        + Display
+        + Send
+        + Sync
+        + 'static
+{
+}
+
+/// `a + b`, failing instead of overflowing.
+pub(crate) fn add<V: Int>(a: V, b: V) -> Result<V> {
+    a.checked_add(&b).ok_or(Error::Overflow)
+}
+
+/// `a * b`, failing instead of overflowing.
+pub(crate) fn mul<V: Int>(a: V, b: V) -> Result<V> {
+    a.checked_mul(&b).ok_or(Error::Overflow)
+}
+
+/// `value` as a `Value`, saturating if it does not fit. Used where errors
+/// report a value.
+pub(crate) fn saturate<V: Int>(value: &V) -> Value {
+    value.to_i64().unwrap_or(if value < &V::zero() {
+        Value::MIN
+    } else {
+        Value::MAX
+    })
+}
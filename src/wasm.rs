@@ -0,0 +1,135 @@
+use crate::vm::decompile::disassemble;
+use crate::vm::memory::GrowableMemory;
+use crate::vm::types::Value;
+use crate::vm::{parse_program, Computer, JsonTracer, Memory, State};
+use std::any::Any;
+use wasm_bindgen::prelude::*;
+
+/// An Intcode computer for JavaScript, built with
+/// `wasm-pack build --target web --features wasm`.
+///
+/// Runs report their state as a string; faults are thrown as errors.
+#[wasm_bindgen]
+pub struct WasmComputer {
+    computer: Computer<GrowableMemory>,
+    len: usize,
+}
+
+#[wasm_bindgen]
+impl WasmComputer {
+    /// Loads the comma separated `source`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(source: &str) -> Result<WasmComputer, JsValue> {
+        let program = parse_program(source).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(Self {
+            len: program.len(),
+            computer: Computer::new(GrowableMemory::new(program)),
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.computer.reset();
+    }
+
+    pub fn step(&mut self) -> Result<String, JsValue> {
+        describe(self.computer.single_step())
+    }
+
+    /// Runs until the program blocks or after `max_steps` instructions.
+    pub fn run(&mut self, max_steps: usize) -> Result<String, JsValue> {
+        describe(self.computer.run_with_limit(max_steps))
+    }
+
+    pub fn push_input(&mut self, value: Value) {
+        self.computer.push_input(value);
+    }
+
+    /// Queues `line` as ASCII input, terminated by a newline.
+    pub fn push_line(&mut self, line: &str) {
+        self.computer.push_line(line);
+    }
+
+    pub fn take_outputs(&mut self) -> Vec<Value> {
+        self.computer.take_outputs()
+    }
+
+    pub fn ip(&self) -> usize {
+        self.computer.ip()
+    }
+
+    pub fn relative_base(&self) -> Value {
+        self.computer.relative_base()
+    }
+
+    /// The `len` cells from `start`; cells beyond the program read as zero.
+    pub fn memory(&self, start: usize, len: usize) -> Result<Vec<Value>, JsValue> {
+        self.computer
+            .memory()
+            .read_slice(start..start.saturating_add(len))
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// The current memory as assembly, as far as the loaded program goes.
+    pub fn disassemble(&self) -> Result<String, JsValue> {
+        Ok(disassemble(&self.memory(0, self.len)?))
+    }
+
+    /// Records every executed instruction for `take_trace`.
+    pub fn enable_trace(&mut self) {
+        self.computer.set_tracer(JsonTracer::new(Vec::new()));
+    }
+
+    /// The instructions executed since tracing was enabled or the trace was
+    /// last taken, one JSON object per line.
+    pub fn take_trace(&mut self) -> String {
+        let tracer = self.computer.take_tracer().and_then(|tracer| {
+            let tracer: Box<dyn Any> = tracer;
+            tracer.downcast::<JsonTracer<Vec<u8>>>().ok()
+        });
+        match tracer {
+            Some(tracer) => {
+                self.enable_trace();
+                let trace = tracer.finish().unwrap_or_default();
+                String::from_utf8_lossy(&trace).into_owned()
+            }
+            None => String::new(),
+        }
+    }
+}
+
+fn describe(state: State) -> Result<String, JsValue> {
+    let state = match state {
+        State::Running => "running",
+        State::Halted => "halted",
+        State::NeedsInput => "needs-input",
+        State::OutputFull => "output-full",
+        State::StepLimitExceeded => "step-limit",
+        State::DeadlineExceeded => "deadline",
+        State::Watchpoint { .. } => "watchpoint",
+        State::Faulted(err) => return Err(JsValue::from_str(&err.to_string())),
+    };
+    Ok(state.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_computer() -> Result<(), JsValue> {
+        let mut computer = WasmComputer::new("3,9,8,9,10,9,4,9,99,-1,8")?;
+        assert_eq!(computer.run(100)?, "needs-input");
+        computer.enable_trace();
+        computer.push_input(8);
+        assert_eq!(computer.step()?, "running");
+        assert_eq!(computer.ip(), 2);
+        assert_eq!(computer.run(100)?, "halted");
+        assert_eq!(computer.take_outputs(), vec![1]);
+        assert_eq!(computer.memory(9, 3)?, vec![1, 8, 0]);
+        assert_eq!(computer.take_trace().lines().count(), 4);
+        assert!(computer.disassemble()?.starts_with("    0: in 9\n"));
+        computer.reset();
+        assert_eq!(computer.memory(9, 1)?, vec![-1]);
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const TEMPLATE: &str = r#"use crate::prelude::*;
+use anyhow::anyhow;
+
+fn part1(input: &str) -> Result<i64> {
+    Err(anyhow!("Part 1 not solved yet ({} bytes of input)", input.len()))
+}
+
+fn part2(input: &str) -> Result<i64> {
+    Err(anyhow!("Part 2 not solved yet ({} bytes of input)", input.len()))
+}
+
+pub fn main() -> Result<()> {
+    let input = input::read({day})?;
+    answer(1, part1(&input)?);
+    answer(2, part2(&input)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "";
+
+    #[test]
+    #[ignore]
+    fn test_part1() -> Result<()> {
+        assert_eq!(part1(EXAMPLE)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn test_part2() -> Result<()> {
+        assert_eq!(part2(EXAMPLE)?, 0);
+        Ok(())
+    }
+}
+"#;
+
+fn root() -> PathBuf {
+    let manifest_dir =
+        env::var_os("CARGO_MANIFEST_DIR").unwrap_or_else(|| env!("CARGO_MANIFEST_DIR").into());
+    Path::new(&manifest_dir)
+        .parent()
+        .expect("xtask lives inside the workspace")
+        .to_owned()
+}
+
+/// Adds `item` to the comma separated list between the braces following `prefix`.
+fn insert_into_list(source: &str, prefix: &str, item: &str) -> Result<String> {
+    let start = source
+        .find(prefix)
+        .ok_or_else(|| anyhow!("Could not find `{}`", prefix))?
+        + prefix.len();
+    let end = start
+        + source[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Unterminated list after `{}`", prefix))?;
+    let mut items = source[start..end]
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>();
+    if items.contains(&item) {
+        bail!("{} is already registered", item);
+    }
+    items.push(item);
+    items.sort_unstable();
+    Ok(format!(
+        "{}{}{}",
+        &source[..start],
+        items.join(", "),
+        &source[end..]
+    ))
+}
+
+/// Adds `pub mod <module>;` after the last day module declaration.
+fn insert_module(source: &str, module: &str) -> Result<String> {
+    let declaration = format!("pub mod {};", module);
+    if source.lines().any(|line| line == declaration) {
+        bail!("{} is already declared", module);
+    }
+    let mut lines = source.lines().collect::<Vec<_>>();
+    let position = lines
+        .iter()
+        .rposition(|line| line.starts_with("pub mod day") && *line < declaration.as_str())
+        .map_or(0, |pos| pos + 1);
+    lines.insert(position, &declaration);
+    Ok(lines.join("\n") + "\n")
+}
+
+fn new_day(day: u32) -> Result<()> {
+    let root = root();
+    let module = format!("day{:02}", day);
+    let module_path = root.join("src").join(format!("{}.rs", module));
+    if module_path.exists() {
+        bail!("{} already exists", module_path.display());
+    }
+
+    let lib_path = root.join("src/lib.rs");
+    let lib = insert_module(&fs::read_to_string(&lib_path)?, &module)?;
+    let main_path = root.join("src/main.rs");
+    let main = fs::read_to_string(&main_path)?;
+    let main = insert_into_list(&main, "use aoc2019::{", &module)?;
+    let main = insert_into_list(&main, "days! {", &module)?;
+
+    fs::write(&module_path, TEMPLATE.replace("{day}", &day.to_string()))?;
+    fs::write(&lib_path, lib)?;
+    fs::write(&main_path, main)?;
+    let data_path = root.join(format!("data/{}.txt", module));
+    if !data_path.exists() {
+        fs::write(&data_path, "")?;
+    }
+
+    let status = Command::new("rustfmt")
+        .args(["--edition", "2018"])
+        .arg(&main_path)
+        .arg(&lib_path)
+        .status()
+        .context("Failed to run rustfmt")?;
+    if !status.success() {
+        bail!("rustfmt failed");
+    }
+    println!("Created {}", module_path.display());
+    Ok(())
+}
+
+fn header_bindings(root: &Path) -> Result<cbindgen::Bindings> {
+    let mut config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("INTCODE_H".to_owned()),
+        autogen_warning: Some("/* Generated by `cargo xtask header`, do not edit. */".to_owned()),
+        ..Default::default()
+    };
+    config.enumeration.prefix_with_name = true;
+    config
+        .export
+        .rename
+        .insert("Value".to_owned(), "IntcodeValue".to_owned());
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src/ffi.rs"))
+        .with_src(root.join("src/vm/types.rs"))
+        .generate()
+        .context("Failed to generate the header")
+}
+
+/// Regenerates the C header for the FFI in `src/ffi.rs`.
+fn header() -> Result<()> {
+    let root = root();
+    let path = root.join("include/intcode.h");
+    header_bindings(&root)?.write_to_file(&path);
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["new-day", day] => {
+            let day = day.parse().context("Day must be a number")?;
+            if !(1..=25).contains(&day) {
+                bail!("Day must be between 1 and 25");
+            }
+            new_day(day)
+        }
+        ["header"] => header(),
+        _ => bail!("Usage: cargo xtask new-day <day> | header"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_into_list() -> Result<()> {
+        let source = "use aoc2019::{day01, day03, explain};\ndays! {day01, day03}\n";
+        let source = insert_into_list(source, "use aoc2019::{", "day02")?;
+        let source = insert_into_list(&source, "days! {", "day02")?;
+        assert_eq!(
+            source,
+            "use aoc2019::{day01, day02, day03, explain};\ndays! {day01, day02, day03}\n"
+        );
+        assert!(insert_into_list(&source, "days! {", "day02").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_module() -> Result<()> {
+        let source = "pub mod day01;\npub mod day03;\npub mod explain;\n";
+        assert_eq!(
+            insert_module(source, "day02")?,
+            "pub mod day01;\npub mod day02;\npub mod day03;\npub mod explain;\n"
+        );
+        assert_eq!(
+            insert_module(source, "day04")?,
+            "pub mod day01;\npub mod day03;\npub mod day04;\npub mod explain;\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_is_current() -> Result<()> {
+        let root = root();
+        let mut generated = Vec::new();
+        header_bindings(&root)?.write(&mut generated);
+        let committed = fs::read(root.join("include/intcode.h"))?;
+        assert!(
+            generated == committed,
+            "include/intcode.h is outdated, run `cargo xtask header`"
+        );
+        Ok(())
+    }
+}